@@ -0,0 +1,76 @@
+// --- Function age: when each function first appeared in the history store ---
+// Functions are known by file and name, as elsewhere in history, so a move
+// or rename starts a function over. Snapshots carry every function's
+// first-seen time forward, so ages survive pruning of old history lines;
+// a function no snapshot knows is new as of this scan.
+use crate::history::Snapshot;
+use crate::locale::tr;
+use crate::{logging, Report};
+use std::collections::HashMap;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+// Earliest sighting per (file, function). RFC 3339 UTC times order as text.
+pub fn first_seen(snapshots: &[Snapshot]) -> HashMap<(String, String), String> {
+    let mut seen: HashMap<(String, String), String> = HashMap::new();
+    for snapshot in snapshots {
+        for f in &snapshot.function_metrics {
+            let when = f.first_seen.as_ref().unwrap_or(&snapshot.timestamp);
+            seen.entry((f.file.clone(), f.function.clone()))
+                .and_modify(|w| {
+                    if when < w {
+                        *w = when.clone();
+                    }
+                })
+                .or_insert_with(|| when.clone());
+        }
+    }
+    seen
+}
+
+// Sets `first_seen` on functions and `first_seen`/`age_days` on findings
+// in a function; `now` is the scan time. Runs after canonicalization,
+// since history stores relative paths.
+pub fn attach(report: &mut Report, snapshots: &[Snapshot], now: &str) {
+    let seen = first_seen(snapshots);
+    let lookup = |file: &str, function: &str| {
+        seen.get(&(file.to_string(), function.to_string()))
+            .cloned()
+            .unwrap_or_else(|| now.to_string())
+    };
+    let functions = report.files.iter_mut().flat_map(|f| &mut f.functions);
+    for f in functions.chain(&mut report.top_functions) {
+        f.first_seen = Some(lookup(&f.file, &f.function));
+    }
+    let known: HashMap<(&str, &str), &str> = report
+        .files
+        .iter()
+        .flat_map(|f| &f.functions)
+        .filter_map(|f| {
+            Some((
+                (f.file.as_str(), f.function.as_str()),
+                f.first_seen.as_deref()?,
+            ))
+        })
+        .collect();
+    let now_secs = logging::parse_rfc3339(now);
+    for finding in &mut report.findings {
+        let Some(when) = known.get(&(finding.file.as_str(), finding.function.as_str())) else {
+            continue;
+        };
+        finding.first_seen = when.get(..10).map(str::to_string);
+        finding.age_days = logging::parse_rfc3339(when)
+            .zip(now_secs)
+            .map(|(then, now)| now.saturating_sub(then) / SECONDS_PER_DAY);
+    }
+}
+
+// Compact age for the findings table: `new` within the first week.
+pub fn label(days: u64) -> String {
+    match days {
+        0..=6 => tr!("age-new"),
+        7..=59 => tr!("age-days", days),
+        60..=729 => tr!("age-months", days / 30),
+        _ => tr!("age-years", format!("{:.1}", days as f64 / 365.0)),
+    }
+}
@@ -0,0 +1,109 @@
+// --- `annotate`: write complexity comments above functions over threshold ---
+use crate::config::ComplexityModel;
+use crate::{analyze_source, logging, rust_files};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+
+pub const MARKER: &str = "// codehealth:";
+
+fn is_annotation(line: &str) -> bool {
+    line.trim_start().starts_with(MARKER)
+}
+
+// Rejoin lines, keeping the file's original trailing newline (or lack of it).
+fn join_lines(lines: &[String], original: &str) -> String {
+    let mut out = lines.join("\n");
+    if original.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+fn strip_annotations(content: &str) -> (String, usize) {
+    let kept: Vec<String> = content
+        .lines()
+        .filter(|l| !is_annotation(l))
+        .map(str::to_string)
+        .collect();
+    let removed = content.lines().count() - kept.len();
+    (join_lines(&kept, content), removed)
+}
+
+// Existing annotations are always stripped first, so functions that dropped
+// back under the threshold lose their stale comment on the next run.
+fn annotate_source(
+    content: &str,
+    file: &str,
+    threshold: usize,
+    model: ComplexityModel,
+) -> (String, usize) {
+    let (stripped, _) = strip_annotations(content);
+    let (_, detail) = analyze_source(&stripped, file, model);
+    if let Some(err) = &detail.parse_error {
+        logging::warn(&format!("Failed to parse {}: {}", file, err), &[]);
+    }
+
+    let notes: HashMap<usize, String> = detail
+        .functions
+        .iter()
+        .filter(|f| f.complexity > threshold)
+        .map(|f| {
+            (
+                f.line,
+                format!("{} cc={} loc={}", MARKER, f.complexity, f.loc),
+            )
+        })
+        .collect();
+
+    let mut lines = Vec::new();
+    for (i, line) in stripped.lines().enumerate() {
+        if let Some(note) = notes.get(&(i + 1)) {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            lines.push(format!("{}{}", indent, note));
+        }
+        lines.push(line.to_string());
+    }
+    (join_lines(&lines, &stripped), notes.len())
+}
+
+pub fn run(root: &str, threshold: usize, remove: bool, model: ComplexityModel) {
+    let mut touched_files = 0;
+    let mut count = 0;
+
+    for (path, _) in rust_files(&[root.to_string()], &[]) {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let file = path.to_string_lossy();
+        let (updated, n) = if remove {
+            strip_annotations(&content)
+        } else {
+            annotate_source(&content, &file, threshold, model)
+        };
+        if updated == content {
+            continue;
+        }
+        if let Err(err) = fs::write(&path, &updated) {
+            logging::error(
+                &format!("cannot write {}: {}", file, err),
+                &[("file", json!(file))],
+            );
+            continue;
+        }
+        touched_files += 1;
+        count += n;
+    }
+
+    if remove {
+        println!(
+            "Removed {} annotation(s) from {} file(s)",
+            count, touched_files
+        );
+    } else {
+        println!(
+            "Annotated {} function(s) over complexity {} in {} file(s)",
+            count, threshold, touched_files
+        );
+    }
+}
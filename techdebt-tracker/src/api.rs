@@ -0,0 +1,274 @@
+// --- Public API surface: exported items per crate, by kind ---
+use crate::FileMetrics;
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use syn::spanned::Spanned;
+use syn::visit_mut::{self, VisitMut};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ApiItem {
+    pub name: String, // path within the file, e.g. `client::Client::send`
+    pub kind: String,
+    pub visibility: String, // "pub" or "pub(crate)"
+    pub line: usize,
+    pub signature: String,
+}
+
+// Doc comments and other attributes don't change an item's contract.
+struct StripAttrs;
+
+impl VisitMut for StripAttrs {
+    fn visit_attributes_mut(&mut self, attrs: &mut Vec<syn::Attribute>) {
+        attrs.clear();
+    }
+}
+
+// The part of an item callers depend on: fn signatures without bodies,
+// trait declarations without default bodies, const/static types without
+// values, and full type definitions.
+fn signature(item: &syn::Item) -> String {
+    let mut item = item.clone();
+    StripAttrs.visit_item_mut(&mut item);
+    match &mut item {
+        syn::Item::Fn(f) => f.sig.to_token_stream().to_string(),
+        syn::Item::Const(c) => format!("const {}: {}", c.ident, c.ty.to_token_stream()),
+        syn::Item::Static(s) => format!("static {}: {}", s.ident, s.ty.to_token_stream()),
+        syn::Item::Trait(t) => {
+            for member in &mut t.items {
+                if let syn::TraitItem::Fn(f) = member {
+                    f.default = None;
+                }
+            }
+            t.to_token_stream().to_string()
+        }
+        other => other.to_token_stream().to_string(),
+    }
+}
+
+fn method_signature(f: &syn::ImplItemFn) -> String {
+    let mut sig = f.sig.clone();
+    visit_mut::visit_signature_mut(&mut StripAttrs, &mut sig);
+    sig.to_token_stream().to_string()
+}
+
+fn visibility(vis: &syn::Visibility) -> Option<&'static str> {
+    match vis {
+        syn::Visibility::Public(_) => Some("pub"),
+        syn::Visibility::Restricted(r) if r.in_token.is_none() && r.path.is_ident("crate") => {
+            Some("pub(crate)")
+        }
+        _ => None,
+    }
+}
+
+fn qualified(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}::{}", prefix, name)
+    }
+}
+
+fn is_macro_export(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|a| a.path().is_ident("macro_export"))
+}
+
+// Walks inline `mod` blocks and inherent impls; items of a private inline
+// module are not exported, whatever their own visibility says.
+fn collect(items: &[syn::Item], prefix: &str, out: &mut Vec<ApiItem>) {
+    for item in items {
+        let (vis, ident, kind) = match item {
+            syn::Item::Fn(i) => (&i.vis, &i.sig.ident, "fn"),
+            syn::Item::Struct(i) => (&i.vis, &i.ident, "struct"),
+            syn::Item::Union(i) => (&i.vis, &i.ident, "struct"),
+            syn::Item::Enum(i) => (&i.vis, &i.ident, "enum"),
+            syn::Item::Trait(i) => (&i.vis, &i.ident, "trait"),
+            syn::Item::Const(i) => (&i.vis, &i.ident, "const"),
+            syn::Item::Static(i) => (&i.vis, &i.ident, "const"),
+            syn::Item::Type(i) => (&i.vis, &i.ident, "type"),
+            syn::Item::Macro(m) => {
+                if let Some(ident) = m.ident.as_ref().filter(|_| is_macro_export(&m.attrs)) {
+                    out.push(ApiItem {
+                        name: ident.to_string(),
+                        kind: "macro".to_string(),
+                        visibility: "pub".to_string(),
+                        line: m.span().start().line,
+                        signature: format!("macro_rules! {}", ident),
+                    });
+                }
+                continue;
+            }
+            syn::Item::Mod(m) => {
+                if let (Some(_), Some((_, content))) = (visibility(&m.vis), &m.content) {
+                    collect(content, &qualified(prefix, &m.ident.to_string()), out);
+                }
+                continue;
+            }
+            syn::Item::Impl(i) if i.trait_.is_none() => {
+                let syn::Type::Path(ty) = &*i.self_ty else {
+                    continue;
+                };
+                let Some(ty) = ty.path.segments.last() else {
+                    continue;
+                };
+                let ty_prefix = qualified(prefix, &ty.ident.to_string());
+                for f in &i.items {
+                    if let syn::ImplItem::Fn(f) = f {
+                        if let Some(v) = visibility(&f.vis) {
+                            out.push(ApiItem {
+                                name: qualified(&ty_prefix, &f.sig.ident.to_string()),
+                                kind: "fn".to_string(),
+                                visibility: v.to_string(),
+                                line: f.span().start().line,
+                                signature: method_signature(f),
+                            });
+                        }
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(v) = visibility(vis) {
+            out.push(ApiItem {
+                name: qualified(prefix, &ident.to_string()),
+                kind: kind.to_string(),
+                visibility: v.to_string(),
+                line: item.span().start().line,
+                signature: signature(item),
+            });
+        }
+    }
+}
+
+pub fn api_items(file: &syn::File) -> Vec<ApiItem> {
+    let mut items = Vec::new();
+    collect(&file.items, "", &mut items);
+    items
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct CrateApi {
+    pub crate_name: String,
+    pub public: usize,
+    pub crate_visible: usize,
+    pub public_by_kind: BTreeMap<String, usize>,
+    pub crate_visible_by_kind: BTreeMap<String, usize>,
+}
+
+pub fn summarize(files: &[FileMetrics]) -> Vec<CrateApi> {
+    let mut by_crate: BTreeMap<&str, CrateApi> = BTreeMap::new();
+    for file in files {
+        let entry = by_crate
+            .entry(file.crate_name.as_str())
+            .or_insert_with(|| CrateApi {
+                crate_name: file.crate_name.clone(),
+                ..Default::default()
+            });
+        for item in &file.api_items {
+            let (count, by_kind) = if item.visibility == "pub" {
+                (&mut entry.public, &mut entry.public_by_kind)
+            } else {
+                (&mut entry.crate_visible, &mut entry.crate_visible_by_kind)
+            };
+            *count += 1;
+            *by_kind.entry(item.kind.clone()).or_default() += 1;
+        }
+    }
+    by_crate.into_values().collect()
+}
+
+// Total `pub` items, the figure the growth gate compares between releases.
+pub fn public_total(crates: &[CrateApi]) -> usize {
+    crates.iter().map(|c| c.public).sum()
+}
+
+// --- Semver-aware diff of the `pub` item set between two snapshots ---
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ApiSignature {
+    pub name: String, // module-qualified, e.g. `my_crate::client::Client::send`
+    pub kind: String,
+    pub signature: String,
+}
+
+#[derive(Serialize)]
+pub struct ApiChange {
+    pub name: String,
+    pub kind: String,
+    pub change: &'static str, // "added", "removed" or "signature"
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub breaking: bool,
+}
+
+#[derive(Serialize)]
+pub struct ApiDiff {
+    pub semver: &'static str, // "major", "minor" or "patch"
+    pub changes: Vec<ApiChange>,
+}
+
+pub fn public_signatures(files: &[FileMetrics]) -> Vec<ApiSignature> {
+    let mut items: Vec<ApiSignature> = files
+        .iter()
+        .flat_map(|file| {
+            file.api_items
+                .iter()
+                .filter(|i| i.visibility == "pub")
+                .map(|i| ApiSignature {
+                    name: qualified(&file.module, &i.name),
+                    kind: i.kind.clone(),
+                    signature: i.signature.clone(),
+                })
+        })
+        .collect();
+    items.sort_by(|a, b| (&a.name, &a.kind).cmp(&(&b.name, &b.kind)));
+    items
+}
+
+// Removals and signature changes are treated as breaking; a changed
+// signature may be compatible (e.g. a new defaulted generic) but needs review.
+pub fn diff(before: &[ApiSignature], after: &[ApiSignature]) -> ApiDiff {
+    let key = |s: &ApiSignature| (s.name.clone(), s.kind.clone());
+    let old: BTreeMap<_, _> = before.iter().map(|s| (key(s), s)).collect();
+    let new: BTreeMap<_, _> = after.iter().map(|s| (key(s), s)).collect();
+
+    let mut changes = Vec::new();
+    for ((name, kind), b) in &old {
+        let change = match new.get(&(name.clone(), kind.clone())) {
+            None => "removed",
+            Some(a) if a.signature != b.signature => "signature",
+            Some(_) => continue,
+        };
+        changes.push(ApiChange {
+            name: name.clone(),
+            kind: kind.clone(),
+            change,
+            before: Some(b.signature.clone()),
+            after: new
+                .get(&(name.clone(), kind.clone()))
+                .map(|a| a.signature.clone()),
+            breaking: true,
+        });
+    }
+    for ((name, kind), a) in new.iter().filter(|(k, _)| !old.contains_key(*k)) {
+        changes.push(ApiChange {
+            name: name.clone(),
+            kind: kind.clone(),
+            change: "added",
+            before: None,
+            after: Some(a.signature.clone()),
+            breaking: false,
+        });
+    }
+    changes.sort_by(|a, b| (&a.name, &a.kind).cmp(&(&b.name, &b.kind)));
+
+    let semver = if changes.iter().any(|c| c.breaking) {
+        "major"
+    } else if changes.is_empty() {
+        "patch"
+    } else {
+        "minor"
+    };
+    ApiDiff { semver, changes }
+}
@@ -0,0 +1,100 @@
+// --- Compressed report artifacts: gzip/zstd output and transparent input ---
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fs;
+use std::io::{Read, Write};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+pub const COMPRESSIONS: [&str; 2] = ["gzip", "zstd"];
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+impl Compression {
+    pub fn from_name(name: &str) -> Option<Compression> {
+        match name {
+            "gzip" => Some(Compression::Gzip),
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    // `report.json.gz` and `report.json.zst` imply their format.
+    pub fn from_path(path: &str) -> Option<Compression> {
+        if path.ends_with(".gz") {
+            Some(Compression::Gzip)
+        } else if path.ends_with(".zst") {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    fn sniff(bytes: &[u8]) -> Option<Compression> {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if bytes.starts_with(&ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn compress(text: &str, compression: Compression) -> Result<Vec<u8>, String> {
+    match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder
+                .write_all(text.as_bytes())
+                .and_then(|_| encoder.finish())
+                .map_err(|e| format!("gzip failed: {}", e))
+        }
+        Compression::Zstd => zstd::encode_all(text.as_bytes(), zstd::DEFAULT_COMPRESSION_LEVEL)
+            .map_err(|e| format!("zstd failed: {}", e)),
+    }
+}
+
+// Reads a report, baseline or other input, decompressing gzip or zstd by
+// content rather than by file name.
+pub fn read_to_string(path: &str) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let decoded = match Compression::sniff(&bytes) {
+        None => bytes,
+        Some(Compression::Gzip) => {
+            let mut out = Vec::new();
+            GzDecoder::new(bytes.as_slice())
+                .read_to_end(&mut out)
+                .map_err(|e| format!("cannot decompress {}: {}", path, e))?;
+            out
+        }
+        Some(Compression::Zstd) => zstd::decode_all(bytes.as_slice())
+            .map_err(|e| format!("cannot decompress {}: {}", path, e))?,
+    };
+    String::from_utf8(decoded).map_err(|_| format!("{}: not UTF-8 text", path))
+}
+
+// Writes a rendered report to `path` (stdout when `None`), compressed when
+// asked to or when the file name ends in `.gz`/`.zst`.
+pub fn write(
+    path: Option<&str>,
+    text: &str,
+    compression: Option<Compression>,
+) -> Result<(), String> {
+    let compression = compression.or_else(|| path.and_then(Compression::from_path));
+    let bytes = match compression {
+        Some(c) => compress(text, c)?,
+        None => text.as_bytes().to_vec(),
+    };
+    match path {
+        Some(path) => fs::write(path, bytes).map_err(|e| format!("cannot write {}: {}", path, e)),
+        None => std::io::stdout()
+            .write_all(&bytes)
+            .map_err(|e| format!("cannot write report: {}", e)),
+    }
+}
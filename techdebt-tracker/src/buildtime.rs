@@ -0,0 +1,164 @@
+// --- Compile-time debt: `cargo build --timings` correlated with complexity ---
+// Cargo times whole units (one crate target each), so time is attributed per
+// crate; the most complex modules of a slow crate are listed as the likely
+// hotspots rather than measured ones.
+use crate::{artifact, FileMetrics};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+pub struct UnitTiming {
+    pub crate_name: String, // normalized to underscores
+    pub seconds: f64,
+}
+
+#[derive(Serialize)]
+pub struct ModuleHotspot {
+    pub module: String,
+    pub complexity: usize,
+    pub loc: usize,
+}
+
+#[derive(Serialize)]
+pub struct CompileDebt {
+    pub crate_name: String,
+    pub build_seconds: f64,
+    pub loc: usize,
+    pub functions: usize,
+    pub total_complexity: usize,
+    pub average_complexity: f64,
+    pub seconds_per_kloc: f64,
+    pub complex_and_slow: bool, // at or above the median in both build time and complexity
+    pub hotspots: Vec<ModuleHotspot>,
+}
+
+fn normalize(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+// `cargo-timing.html` embeds `const UNIT_DATA = [...]`.
+fn parse_html(text: &str) -> Option<Vec<UnitTiming>> {
+    let start = text.find("UNIT_DATA = ")? + "UNIT_DATA = ".len();
+    let units: Value = serde_json::Deserializer::from_str(&text[start..])
+        .into_iter()
+        .next()?
+        .ok()?;
+    Some(
+        units
+            .as_array()?
+            .iter()
+            .filter(|u| u["mode"] != "run-custom-build")
+            .filter_map(|u| {
+                Some(UnitTiming {
+                    crate_name: normalize(u["name"].as_str()?),
+                    seconds: u["duration"].as_f64()?,
+                })
+            })
+            .collect(),
+    )
+}
+
+// Package name from a package id, old (`name 1.0.0 (source)`) or new
+// (`source#name@1.0.0`, or `path+file:///dir/name#1.0.0`) style.
+fn package_name(id: &str) -> Option<&str> {
+    match id.split_once('#') {
+        Some((_, fragment)) => match fragment.split_once('@') {
+            Some((name, _)) => Some(name),
+            None => id[..id.len() - fragment.len() - 1].rsplit('/').next(),
+        },
+        None => id.split_whitespace().next(),
+    }
+}
+
+// `cargo build --timings=json` prints one `timing-info` message per unit.
+fn parse_json_lines(text: &str) -> Vec<UnitTiming> {
+    text.lines()
+        .filter_map(|l| serde_json::from_str::<Value>(l).ok())
+        .filter(|m| m["reason"] == "timing-info" && m["mode"] != "run-custom-build")
+        .filter_map(|m| {
+            Some(UnitTiming {
+                crate_name: normalize(package_name(m["package_id"].as_str()?)?),
+                seconds: m["duration"].as_f64()?,
+            })
+        })
+        .collect()
+}
+
+pub fn load(path: &str) -> Result<Vec<UnitTiming>, String> {
+    let text = artifact::read_to_string(path)?;
+    let units = parse_html(&text).unwrap_or_else(|| parse_json_lines(&text));
+    if units.is_empty() {
+        return Err(format!(
+            "{}: no unit timings (expected cargo-timing.html or --timings=json output)",
+            path
+        ));
+    }
+    Ok(units)
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    values.get(values.len() / 2).copied().unwrap_or(0.0)
+}
+
+// Crates with both timings and analyzed files, slowest first.
+pub fn correlate(files: &[FileMetrics], timings: &[UnitTiming]) -> Vec<CompileDebt> {
+    let mut seconds: BTreeMap<&str, f64> = BTreeMap::new();
+    for unit in timings {
+        *seconds.entry(&unit.crate_name).or_default() += unit.seconds;
+    }
+
+    let mut modules: BTreeMap<String, BTreeMap<&str, (usize, usize)>> = BTreeMap::new();
+    for file in files {
+        let entry = modules
+            .entry(normalize(&file.crate_name))
+            .or_default()
+            .entry(&file.module)
+            .or_default();
+        entry.0 += file.total_complexity;
+        entry.1 += file.loc;
+    }
+
+    let mut rows: Vec<CompileDebt> = modules
+        .into_iter()
+        .filter_map(|(name, mods)| {
+            let build_seconds = *seconds.get(name.as_str())?;
+            let crate_files = files.iter().filter(|f| normalize(&f.crate_name) == name);
+            let functions = crate_files
+                .clone()
+                .map(|f| f.functions.len())
+                .sum::<usize>();
+            let loc: usize = mods.values().map(|m| m.1).sum();
+            let total_complexity: usize = mods.values().map(|m| m.0).sum();
+            let mut hotspots: Vec<ModuleHotspot> = mods
+                .into_iter()
+                .map(|(module, (complexity, loc))| ModuleHotspot {
+                    module: module.to_string(),
+                    complexity,
+                    loc,
+                })
+                .collect();
+            hotspots.sort_by_key(|h| std::cmp::Reverse(h.complexity));
+            hotspots.truncate(3);
+            Some(CompileDebt {
+                crate_name: name,
+                build_seconds,
+                loc,
+                functions,
+                total_complexity,
+                average_complexity: total_complexity as f64 / functions.max(1) as f64,
+                seconds_per_kloc: build_seconds / (loc.max(1) as f64 / 1000.0),
+                complex_and_slow: false,
+                hotspots,
+            })
+        })
+        .collect();
+
+    let slow = median(rows.iter().map(|r| r.build_seconds).collect());
+    let complex = median(rows.iter().map(|r| r.average_complexity).collect());
+    for r in &mut rows {
+        r.complex_and_slow = r.build_seconds >= slow && r.average_complexity >= complex;
+    }
+    rows.sort_by(|a, b| b.build_seconds.total_cmp(&a.build_seconds));
+    rows
+}
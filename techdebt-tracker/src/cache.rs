@@ -0,0 +1,188 @@
+// --- Incremental cache: per-file results keyed by content version ---
+// The whole cache is tied to a key made of the analyzer version, the cache
+// format and the analysis settings, so upgrading the tool or changing config
+// discards it instead of mixing results computed under different rules.
+// A file's version is its git blob id when it is tracked and unmodified, so
+// fresh CI checkouts sharing a cache volume hit without reading any file;
+// otherwise it is an FNV-1a hash of the content on disk, which unlike
+// std's hashers stays the same across Rust releases.
+use crate::{
+    api::ApiItem, fingerprint, git, modtree::ModDecl, CodeMetrics, Diagnostic, FileAnalysis,
+    FileMetrics,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_CACHE_FILE: &str = ".codehealth/cache.json";
+
+// Bump when the entry layout or any per-file counting rule changes.
+const CACHE_FORMAT: u32 = 21;
+
+// Fields the JSON report leaves out still have to survive a round trip.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Hidden {
+    conditions: Vec<Vec<(usize, usize, String)>>,
+    calls: Vec<Vec<String>>,
+    api_items: Vec<ApiItem>,
+    references: BTreeMap<String, usize>,
+    mod_decls: Vec<ModDecl>,
+}
+
+impl Hidden {
+    pub fn capture(detail: &FileMetrics) -> Hidden {
+        Hidden {
+            conditions: detail
+                .functions
+                .iter()
+                .map(|f| f.conditions.clone())
+                .collect(),
+            calls: detail.functions.iter().map(|f| f.calls.clone()).collect(),
+            api_items: detail.api_items.clone(),
+            references: detail.references.clone(),
+            mod_decls: detail.mod_decls.clone(),
+        }
+    }
+
+    pub fn restore(&self, detail: &mut FileMetrics) {
+        for ((f, conditions), calls) in detail
+            .functions
+            .iter_mut()
+            .zip(&self.conditions)
+            .zip(&self.calls)
+        {
+            f.conditions = conditions.clone();
+            f.calls = calls.clone();
+        }
+        detail.api_items = self.api_items.clone();
+        detail.references = self.references.clone();
+        detail.mod_decls = self.mod_decls.clone();
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Entry {
+    version: String, // `git:<blob id>` or `hash:<content hash>`
+    metrics: CodeMetrics,
+    detail: FileMetrics,
+    #[serde(flatten)]
+    hidden: Hidden,
+    warnings: Vec<Diagnostic>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CacheFile {
+    key: String,
+    entries: BTreeMap<String, Entry>,
+}
+
+pub struct Cache {
+    path: String,
+    key: String,
+    old: BTreeMap<String, Entry>,
+    // Only files seen this run are written back, so deleted files drop out.
+    new: BTreeMap<String, Entry>,
+    blob_ids: HashMap<PathBuf, String>,
+}
+
+pub fn key(settings: &impl Serialize) -> String {
+    format!(
+        "{}/{}/{}",
+        env!("CARGO_PKG_VERSION"),
+        CACHE_FORMAT,
+        serde_json::to_string(settings).unwrap_or_default()
+    )
+}
+
+impl Cache {
+    // A missing, unreadable or stale-keyed file just means a cold cache.
+    // Roots outside a git repository fall back to content hashes.
+    pub fn open(path: &str, key: String, roots: &[String]) -> Cache {
+        let old = fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<CacheFile>(&text).ok())
+            .filter(|file| file.key == key)
+            .map(|file| file.entries)
+            .unwrap_or_default();
+        Cache {
+            path: path.to_string(),
+            key,
+            old,
+            new: BTreeMap::new(),
+            blob_ids: roots
+                .iter()
+                .filter_map(|root| git::blob_ids(root).ok())
+                .flatten()
+                .collect(),
+        }
+    }
+
+    fn version(&self, file: &Path) -> String {
+        let blob = file
+            .canonicalize()
+            .ok()
+            .and_then(|abs| self.blob_ids.get(&abs));
+        match blob {
+            Some(oid) => format!("git:{}", oid),
+            None => format!(
+                "hash:{:016x}",
+                fs::read(file)
+                    .map(|c| fingerprint::fnv1a_bytes(&c))
+                    .unwrap_or(0)
+            ),
+        }
+    }
+
+    // Returns the file's version and, on a hit, the stored result.
+    pub fn lookup(&mut self, file: &Path) -> (String, Option<FileAnalysis>) {
+        let version = self.version(file);
+        let name = file.to_string_lossy().into_owned();
+        let Some(entry) = self.old.get(&name).filter(|e| e.version == version) else {
+            return (version, None);
+        };
+        let mut detail = entry.detail.clone();
+        entry.hidden.restore(&mut detail);
+        self.new.insert(name, entry.clone());
+        (
+            version,
+            Some((entry.metrics.clone(), detail, entry.warnings.clone())),
+        )
+    }
+
+    pub fn store(
+        &mut self,
+        file: &Path,
+        version: String,
+        metrics: &CodeMetrics,
+        detail: &FileMetrics,
+        warnings: &[Diagnostic],
+    ) {
+        self.new.insert(
+            file.to_string_lossy().into_owned(),
+            Entry {
+                version,
+                metrics: metrics.clone(),
+                detail: detail.clone(),
+                hidden: Hidden::capture(detail),
+                warnings: warnings.to_vec(),
+            },
+        );
+    }
+
+    pub fn save(self) -> Result<(), String> {
+        if let Some(dir) = Path::new(&self.path)
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+        {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+        }
+        let file = CacheFile {
+            key: self.key,
+            entries: self.new,
+        };
+        let json = serde_json::to_string(&file).map_err(|e| e.to_string())?;
+        fs::write(&self.path, json).map_err(|e| format!("cannot write {}: {}", self.path, e))
+    }
+}
@@ -0,0 +1,208 @@
+// --- Call graph between analyzed functions: recursion and feature envy ---
+use crate::FileMetrics;
+use std::collections::{BTreeMap, HashMap};
+use syn::visit::{self, Visit};
+
+// Callee names of plain function calls: `foo()`, `module::foo()`,
+// `crate::foo()`. Calls through a type (`Vec::new()`) and method calls can't
+// be resolved to our free functions by name, so they are left out.
+#[derive(Default)]
+pub struct CallVisitor {
+    pub calls: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for CallVisitor {
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(p) = &*node.func {
+            let segments: Vec<String> = p
+                .path
+                .segments
+                .iter()
+                .map(|s| s.ident.to_string())
+                .collect();
+            let through_type = segments.len() > 1
+                && segments[segments.len() - 2]
+                    .chars()
+                    .next()
+                    .is_some_and(char::is_uppercase);
+            if !through_type {
+                if let Some(name) = segments.last() {
+                    self.calls.push(name.clone());
+                }
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
+struct Tarjan<'a> {
+    edges: &'a [Vec<usize>],
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn connect(&mut self, v: usize) {
+        self.index[v] = Some(self.next);
+        self.low[v] = self.next;
+        self.next += 1;
+        self.stack.push(v);
+        self.on_stack[v] = true;
+
+        for &w in &self.edges[v] {
+            match self.index[w] {
+                None => {
+                    self.connect(w);
+                    self.low[v] = self.low[v].min(self.low[w]);
+                }
+                Some(i) if self.on_stack[w] => self.low[v] = self.low[v].min(i),
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.low[v]) == self.index[v] {
+            let mut component = Vec::new();
+            while let Some(w) = self.stack.pop() {
+                self.on_stack[w] = false;
+                component.push(w);
+                if w == v {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+// Every analyzed function as (file index, function index).
+type FunctionIds = Vec<(usize, usize)>;
+
+// Resolves each call site by name within the caller's crate, preferring the
+// caller's file; a name defined in several other files yields every candidate.
+fn resolve_calls(files: &[FileMetrics]) -> (FunctionIds, Vec<Vec<Vec<usize>>>) {
+    let ids: FunctionIds = files
+        .iter()
+        .enumerate()
+        .flat_map(|(fi, file)| (0..file.functions.len()).map(move |i| (fi, i)))
+        .collect();
+    let mut by_name: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+    for (id, &(fi, i)) in ids.iter().enumerate() {
+        let file = &files[fi];
+        by_name
+            .entry((
+                file.crate_name.as_str(),
+                file.functions[i].function.as_str(),
+            ))
+            .or_default()
+            .push(id);
+    }
+
+    let sites = ids
+        .iter()
+        .map(|&(fi, i)| {
+            let file = &files[fi];
+            file.functions[i]
+                .calls
+                .iter()
+                .filter_map(|callee| by_name.get(&(file.crate_name.as_str(), callee.as_str())))
+                .map(|candidates| {
+                    let same_file: Vec<usize> = candidates
+                        .iter()
+                        .copied()
+                        .filter(|&c| ids[c].0 == fi)
+                        .collect();
+                    if same_file.is_empty() {
+                        candidates.clone()
+                    } else {
+                        same_file
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    (ids, sites)
+}
+
+// Marks every function in a call cycle. Each call into the cycle adds 1 to
+// the caller's cognitive complexity.
+pub fn mark_recursion(files: &mut [FileMetrics]) {
+    let (ids, sites) = resolve_calls(files);
+    let edges: Vec<Vec<usize>> = sites
+        .into_iter()
+        .map(|calls| {
+            let mut targets: Vec<usize> = calls.into_iter().flatten().collect();
+            targets.sort_unstable();
+            targets
+        })
+        .collect();
+
+    let mut tarjan = Tarjan {
+        edges: &edges,
+        index: vec![None; ids.len()],
+        low: vec![0; ids.len()],
+        on_stack: vec![false; ids.len()],
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    for v in 0..ids.len() {
+        if tarjan.index[v].is_none() {
+            tarjan.connect(v);
+        }
+    }
+
+    let mut component_of = vec![0; ids.len()];
+    for (c, members) in tarjan.components.iter().enumerate() {
+        for &m in members {
+            component_of[m] = c;
+        }
+    }
+    for (v, &(fi, i)) in ids.iter().enumerate() {
+        let size = tarjan.components[component_of[v]].len();
+        let recursive_calls = edges[v]
+            .iter()
+            .filter(|&&w| component_of[w] == component_of[v])
+            .count();
+        if recursive_calls > 0 {
+            let f = &mut files[fi].functions[i];
+            f.is_recursive = true;
+            f.recursion_cycle = size;
+            f.cognitive_complexity += recursive_calls;
+        }
+    }
+}
+
+// Counts resolved calls into the caller's own module and into each other
+// module. Calls whose candidates live in different modules are ambiguous and
+// not counted.
+pub fn count_module_calls(files: &mut [FileMetrics]) {
+    let (ids, sites) = resolve_calls(files);
+    for (v, calls) in sites.into_iter().enumerate() {
+        let (fi, i) = ids[v];
+        let mut own = 0;
+        let mut foreign: BTreeMap<String, usize> = BTreeMap::new();
+        for candidates in calls {
+            let module = |c: usize| files[ids[c].0].module.as_str();
+            let Some(&first) = candidates.first() else {
+                continue;
+            };
+            if candidates.iter().any(|&c| module(c) != module(first)) {
+                continue;
+            }
+            if module(first) == files[fi].module {
+                own += 1;
+            } else {
+                *foreign.entry(module(first).to_string()).or_default() += 1;
+            }
+        }
+        let f = &mut files[fi].functions[i];
+        f.own_module_calls = own;
+        f.foreign_module_calls = foreign;
+    }
+}
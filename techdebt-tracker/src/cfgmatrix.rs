@@ -0,0 +1,296 @@
+// --- `--cfg-matrix`: metric ranges across `#[cfg(feature = ...)]` combinations ---
+// Each `[cfg-matrix]` entry names a set of enabled features; the code is
+// analyzed again with whatever a `#[cfg]` switches off under that set
+// blanked out (as whitespace, so lines don't move). Only `feature`
+// predicates are decided: code behind `test`, `unix` and the like is kept.
+use crate::{run_analysis, AnalysisOptions, Report};
+use proc_macro2::{LineColumn, TokenStream};
+use quote::ToTokens;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use syn::parse::{ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+// `None` when the predicate depends on more than features.
+fn eval(meta: &syn::Meta, features: &BTreeSet<String>) -> Option<bool> {
+    match meta {
+        syn::Meta::NameValue(nv) if nv.path.is_ident("feature") => match &nv.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s),
+                ..
+            }) => Some(features.contains(&s.value())),
+            _ => None,
+        },
+        syn::Meta::List(list) => {
+            let nested = list
+                .parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+                .ok()?;
+            let values: Vec<Option<bool>> = nested.iter().map(|m| eval(m, features)).collect();
+            let all = |v: bool| values.iter().all(|&x| x == Some(v));
+            if list.path.is_ident("not") {
+                match values.as_slice() {
+                    [value] => value.map(|b| !b),
+                    _ => None,
+                }
+            } else if list.path.is_ident("all") {
+                if values.contains(&Some(false)) {
+                    Some(false)
+                } else {
+                    all(true).then_some(true)
+                }
+            } else if list.path.is_ident("any") {
+                if values.contains(&Some(true)) {
+                    Some(true)
+                } else {
+                    all(false).then_some(false)
+                }
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn switched_off(attrs: &[syn::Attribute], features: &BTreeSet<String>) -> bool {
+    attrs.iter().filter(|a| a.path().is_ident("cfg")).any(|a| {
+        a.parse_args::<syn::Meta>()
+            .ok()
+            .and_then(|m| eval(&m, features))
+            == Some(false)
+    })
+}
+
+// Outer attributes of any node, read back from its tokens, since syn has no
+// common accessor for items, statements and expressions.
+fn outer_attrs(node: &impl ToTokens) -> Vec<syn::Attribute> {
+    let parser = |input: ParseStream| {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        input.parse::<TokenStream>()?;
+        Ok(attrs)
+    };
+    parser.parse2(node.to_token_stream()).unwrap_or_default()
+}
+
+struct Stripper<'a> {
+    features: &'a BTreeSet<String>,
+    spans: Vec<(LineColumn, LineColumn)>,
+}
+
+impl Stripper<'_> {
+    // Records a switched-off node; the caller descends only into kept ones.
+    fn keep(&mut self, node: &impl ToTokens) -> bool {
+        if switched_off(&outer_attrs(node), self.features) {
+            let span = node.span();
+            self.spans.push((span.start(), span.end()));
+            false
+        } else {
+            true
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Stripper<'_> {
+    fn visit_item(&mut self, node: &'ast syn::Item) {
+        if self.keep(node) {
+            syn::visit::visit_item(self, node);
+        }
+    }
+
+    fn visit_impl_item(&mut self, node: &'ast syn::ImplItem) {
+        if self.keep(node) {
+            syn::visit::visit_impl_item(self, node);
+        }
+    }
+
+    fn visit_trait_item(&mut self, node: &'ast syn::TraitItem) {
+        if self.keep(node) {
+            syn::visit::visit_trait_item(self, node);
+        }
+    }
+
+    fn visit_foreign_item(&mut self, node: &'ast syn::ForeignItem) {
+        if self.keep(node) {
+            syn::visit::visit_foreign_item(self, node);
+        }
+    }
+
+    fn visit_stmt(&mut self, node: &'ast syn::Stmt) {
+        if self.keep(node) {
+            syn::visit::visit_stmt(self, node);
+        }
+    }
+
+    fn visit_arm(&mut self, node: &'ast syn::Arm) {
+        if self.keep(node) {
+            syn::visit::visit_arm(self, node);
+        }
+    }
+
+    fn visit_field(&mut self, node: &'ast syn::Field) {
+        if self.keep(node) {
+            syn::visit::visit_field(self, node);
+        }
+    }
+
+    fn visit_variant(&mut self, node: &'ast syn::Variant) {
+        if self.keep(node) {
+            syn::visit::visit_variant(self, node);
+        }
+    }
+}
+
+// Spans are 1-based lines and 0-based char columns, end exclusive.
+fn blank(content: &str, spans: &[(LineColumn, LineColumn)]) -> String {
+    let (mut line, mut column) = (1, 0);
+    let mut out = String::with_capacity(content.len());
+    for c in content.chars() {
+        let here = (line, column);
+        let inside = spans
+            .iter()
+            .any(|(s, e)| (s.line, s.column) <= here && here < (e.line, e.column));
+        out.push(if inside && c != '\n' && c != '\r' {
+            ' '
+        } else {
+            c
+        });
+        if c == '\n' {
+            line += 1;
+            column = 0;
+        } else {
+            column += 1;
+        }
+    }
+    out
+}
+
+// The source as compiled with `features`; `None` when nothing is switched
+// off or the file does not parse.
+pub fn strip(content: &str, features: &BTreeSet<String>) -> Option<String> {
+    let syntax = syn::parse_file(content).ok()?;
+    if switched_off(&syntax.attrs, features) {
+        // `#![cfg(...)]` drops the whole file.
+        return Some(blank(
+            content,
+            &[(
+                LineColumn { line: 1, column: 0 },
+                LineColumn {
+                    line: usize::MAX,
+                    column: 0,
+                },
+            )],
+        ));
+    }
+    let mut stripper = Stripper {
+        features,
+        spans: Vec::new(),
+    };
+    stripper.visit_file(&syntax);
+    (!stripper.spans.is_empty()).then(|| blank(content, &stripper.spans))
+}
+
+#[derive(Serialize)]
+pub struct Combination {
+    pub name: String,
+    pub features: Vec<String>,
+    pub nloc: usize,
+    pub functions: usize,
+    pub cyclomatic_complexity: usize,
+    pub average_complexity: f64,
+    pub max_function_complexity: usize,
+    pub findings: usize, // before suppressions
+}
+
+#[derive(Serialize)]
+pub struct MetricRange {
+    pub metric: String,
+    pub min: f64,
+    pub min_in: String, // combination name
+    pub max: f64,
+    pub max_in: String,
+}
+
+#[derive(Serialize)]
+pub struct CfgMatrix {
+    pub combinations: Vec<Combination>, // in name order
+    pub ranges: Vec<MetricRange>,
+}
+
+type ComboValue = fn(&Combination) -> f64;
+
+const METRICS: [(&str, ComboValue); 6] = [
+    ("nloc", |c| c.nloc as f64),
+    ("functions", |c| c.functions as f64),
+    ("cyclomatic_complexity", |c| c.cyclomatic_complexity as f64),
+    ("average_complexity", |c| c.average_complexity),
+    ("max_function_complexity", |c| {
+        c.max_function_complexity as f64
+    }),
+    ("findings", |c| c.findings as f64),
+];
+
+fn combination(name: &str, features: &[String], report: &Report) -> Combination {
+    let functions = report.files.iter().flat_map(|f| &f.functions);
+    Combination {
+        name: name.to_string(),
+        features: features.to_vec(),
+        nloc: report.files.iter().map(|f| f.nloc).sum(),
+        functions: report.metrics.functions,
+        cyclomatic_complexity: report.metrics.cyclomatic_complexity,
+        average_complexity: report.metrics.cyclomatic_complexity as f64
+            / report.metrics.functions.max(1) as f64,
+        max_function_complexity: functions.map(|f| f.complexity).max().unwrap_or(0),
+        findings: report.findings.len(),
+    }
+}
+
+fn ranges(combinations: &[Combination]) -> Vec<MetricRange> {
+    METRICS
+        .iter()
+        .filter_map(|(metric, value)| {
+            let min = combinations
+                .iter()
+                .min_by(|a, b| value(a).total_cmp(&value(b)))?;
+            let max = combinations
+                .iter()
+                .max_by(|a, b| value(a).total_cmp(&value(b)))?;
+            Some(MetricRange {
+                metric: metric.to_string(),
+                min: value(min),
+                min_in: min.name.clone(),
+                max: value(max),
+                max_in: max.name.clone(),
+            })
+        })
+        .collect()
+}
+
+// One full analysis per combination. The cache is bypassed, since its
+// entries hold the unstripped results, and so is --fail-fast.
+pub fn analyze(
+    opts: &mut AnalysisOptions,
+    matrix: &BTreeMap<String, Vec<String>>,
+) -> Result<CfgMatrix, String> {
+    let cache = opts.cache.take();
+    let fail_fast = std::mem::replace(&mut opts.fail_fast, false);
+    let mut run = || -> Result<Vec<Combination>, String> {
+        let mut combinations = Vec::new();
+        for (name, features) in matrix {
+            opts.cfg_features = Some(features.iter().cloned().collect());
+            let report = run_analysis(opts).map_err(|e| format!("cfg matrix '{}': {}", name, e))?;
+            combinations.push(combination(name, features, &report));
+        }
+        Ok(combinations)
+    };
+    let combinations = run();
+    opts.cfg_features = None;
+    opts.cache = cache;
+    opts.fail_fast = fail_fast;
+    let combinations = combinations?;
+    Ok(CfgMatrix {
+        ranges: ranges(&combinations),
+        combinations,
+    })
+}
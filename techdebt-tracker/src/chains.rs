@@ -0,0 +1,49 @@
+// --- Method-call chains: builder chains and iterator pipelines ---
+// `v.iter().map(f).filter(g).collect()` is a chain of 4 calls. `?`,
+// `.await` and field access continue a chain without adding to it.
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+#[derive(Default)]
+struct ChainVisitor {
+    longest: (usize, usize, usize), // (calls, first line, last line)
+}
+
+impl<'ast> Visit<'ast> for ChainVisitor {
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        let mut calls = 1;
+        for arg in &node.args {
+            self.visit_expr(arg);
+        }
+        // Walk down to the chain's root; arguments may hold chains of their own.
+        let mut expr = &*node.receiver;
+        loop {
+            match expr {
+                syn::Expr::MethodCall(call) => {
+                    calls += 1;
+                    for arg in &call.args {
+                        self.visit_expr(arg);
+                    }
+                    expr = &call.receiver;
+                }
+                syn::Expr::Try(t) => expr = &t.expr,
+                syn::Expr::Await(a) => expr = &a.base,
+                syn::Expr::Field(f) => expr = &f.base,
+                _ => break,
+            }
+        }
+        self.visit_expr(expr);
+        if calls > self.longest.0 {
+            self.longest = (calls, node.span().start().line, node.span().end().line);
+        }
+    }
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
+// The longest chain in `block` as (calls, first line, last line).
+pub fn longest(block: &syn::Block) -> (usize, usize, usize) {
+    let mut visitor = ChainVisitor::default();
+    visitor.visit_block(block);
+    visitor.longest
+}
@@ -0,0 +1,96 @@
+// --- Changed risk: before/after metrics of functions touched since a base ---
+// The base version of each changed file is re-analyzed from git, so a diff
+// shows which function got worse rather than how the whole file moved.
+use crate::compare::FunctionSide;
+use crate::config::ComplexityModel;
+use crate::{analyze_source, git, FileMetrics, FunctionMetric};
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Serialize)]
+pub struct ChangedFunction {
+    pub file: String,
+    pub function: String,
+    pub line: usize,
+    pub status: String, // added, worsened, improved or unchanged
+    pub before: Option<FunctionSide>,
+    pub after: FunctionSide,
+    pub complexity_delta: i64,
+}
+
+fn side(f: &FunctionMetric) -> FunctionSide {
+    FunctionSide {
+        complexity: f.complexity,
+        cognitive_complexity: f.cognitive_complexity,
+        loc: f.loc,
+        nesting: f.nesting,
+    }
+}
+
+// Same-named functions in one file keep the most complex, as in `compare`.
+fn base_functions(
+    content: &str,
+    file: &str,
+    model: ComplexityModel,
+) -> HashMap<String, FunctionSide> {
+    let (_, detail) = analyze_source(content, file, model);
+    let mut out: HashMap<String, FunctionSide> = HashMap::new();
+    for f in &detail.functions {
+        let entry = out.entry(f.function.clone()).or_insert_with(|| side(f));
+        if f.complexity > entry.complexity {
+            *entry = side(f);
+        }
+    }
+    out
+}
+
+fn status(before: Option<&FunctionSide>, after: &FunctionSide) -> &'static str {
+    let Some(b) = before else {
+        return "added";
+    };
+    let old = (b.complexity, b.cognitive_complexity);
+    let new = (after.complexity, after.cognitive_complexity);
+    if new.0 > old.0 || (new.0 == old.0 && new.1 > old.1) {
+        "worsened"
+    } else if new < old {
+        "improved"
+    } else {
+        "unchanged"
+    }
+}
+
+// `touched` holds only the functions overlapping changed lines (see
+// `new_code_only`); worst regressions come first.
+pub fn compare(
+    touched: &[FileMetrics],
+    changed: &git::ChangedLines,
+    model: ComplexityModel,
+) -> Vec<ChangedFunction> {
+    let mut out = Vec::new();
+    for file in touched.iter().filter(|f| !f.functions.is_empty()) {
+        let base = changed
+            .base_content(&file.file)
+            .map(|content| base_functions(&content, &file.file, model))
+            .unwrap_or_default();
+        for f in &file.functions {
+            let before = base.get(&f.function).copied();
+            let after = side(f);
+            out.push(ChangedFunction {
+                file: f.file.clone(),
+                function: f.function.clone(),
+                line: f.line,
+                status: status(before.as_ref(), &after).to_string(),
+                complexity_delta: after.complexity as i64
+                    - before.map_or(0, |b| b.complexity) as i64,
+                before,
+                after,
+            });
+        }
+    }
+    out.sort_by(|a, b| {
+        b.complexity_delta
+            .cmp(&a.complexity_delta)
+            .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+    });
+    out
+}
@@ -0,0 +1,142 @@
+// --- Class-level metrics for Rust types: WMC, method count and LCOM ---
+use crate::config::ComplexityModel;
+use crate::{CyclomaticComplexityVisitor, FileMetrics};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+// Fields read or written through `self`, e.g. `self.items` or `self.0`.
+#[derive(Default)]
+struct FieldUseVisitor {
+    fields: BTreeSet<String>,
+}
+
+impl<'ast> Visit<'ast> for FieldUseVisitor {
+    fn visit_expr_field(&mut self, node: &'ast syn::ExprField) {
+        if matches!(&*node.base, syn::Expr::Path(p) if p.path.is_ident("self")) {
+            let name = match &node.member {
+                syn::Member::Named(ident) => ident.to_string(),
+                syn::Member::Unnamed(index) => index.index.to_string(),
+            };
+            self.fields.insert(name);
+        }
+        visit::visit_expr_field(self, node);
+    }
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TypeMetrics {
+    pub file: String,
+    pub name: String,
+    pub line: usize, // first impl block
+    pub methods: usize,
+    pub wmc: usize, // weighted methods per class: sum of method complexities
+    pub max_method_complexity: usize,
+    pub fields: usize, // 0 when the type is declared in another file
+    pub lcom: usize,
+}
+
+#[derive(Default)]
+struct TypeAccumulator {
+    line: usize,
+    complexities: Vec<usize>,
+    field_sets: Vec<BTreeSet<String>>, // one per method taking `self`
+}
+
+// Chidamber–Kemerer LCOM: method pairs sharing no field minus pairs sharing
+// one, floored at 0. Associated functions without `self` are left out.
+fn lcom(sets: &[BTreeSet<String>]) -> usize {
+    let (mut disjoint, mut shared) = (0usize, 0usize);
+    for (i, a) in sets.iter().enumerate() {
+        for b in &sets[i + 1..] {
+            if a.is_disjoint(b) {
+                disjoint += 1;
+            } else {
+                shared += 1;
+            }
+        }
+    }
+    disjoint.saturating_sub(shared)
+}
+
+fn collect(
+    items: &[syn::Item],
+    model: ComplexityModel,
+    types: &mut BTreeMap<String, TypeAccumulator>,
+    fields: &mut BTreeMap<String, usize>,
+) {
+    for item in items {
+        match item {
+            syn::Item::Struct(s) => {
+                fields.insert(s.ident.to_string(), s.fields.len());
+            }
+            syn::Item::Mod(m) => {
+                if let Some((_, inner)) = &m.content {
+                    collect(inner, model, types, fields);
+                }
+            }
+            syn::Item::Impl(imp) => {
+                let syn::Type::Path(ty) = &*imp.self_ty else {
+                    continue;
+                };
+                let Some(name) = ty.path.segments.last().map(|s| s.ident.to_string()) else {
+                    continue;
+                };
+                let acc = types.entry(name).or_insert_with(|| TypeAccumulator {
+                    line: imp.span().start().line,
+                    ..Default::default()
+                });
+                for method in imp.items.iter().filter_map(|i| match i {
+                    syn::ImplItem::Fn(f) => Some(f),
+                    _ => None,
+                }) {
+                    let mut complexity = CyclomaticComplexityVisitor::new(model);
+                    complexity.visit_impl_item_fn(method);
+                    acc.complexities.push(complexity.complexity);
+                    if method.sig.receiver().is_some() {
+                        let mut uses = FieldUseVisitor::default();
+                        uses.visit_block(&method.block);
+                        acc.field_sets.push(uses.fields);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Inherent and trait impls of a type in one file are combined.
+pub fn type_metrics(file: &syn::File, path: &str, model: ComplexityModel) -> Vec<TypeMetrics> {
+    let mut types = BTreeMap::new();
+    let mut fields = BTreeMap::new();
+    collect(&file.items, model, &mut types, &mut fields);
+    types
+        .into_iter()
+        .filter(|(_, acc)| !acc.complexities.is_empty())
+        .map(|(name, acc)| TypeMetrics {
+            file: path.to_string(),
+            line: acc.line,
+            methods: acc.complexities.len(),
+            wmc: acc.complexities.iter().sum(),
+            max_method_complexity: acc.complexities.iter().copied().max().unwrap_or(0),
+            fields: fields.get(&name).copied().unwrap_or(0),
+            lcom: lcom(&acc.field_sets),
+            name,
+        })
+        .collect()
+}
+
+// Heaviest types across the project by WMC.
+pub fn rank(files: &[FileMetrics], limit: usize) -> Vec<TypeMetrics> {
+    let mut rows: Vec<TypeMetrics> = files.iter().flat_map(|f| f.classes.clone()).collect();
+    rows.sort_by(|a, b| {
+        b.wmc
+            .cmp(&a.wmc)
+            .then_with(|| (&a.file, &a.name).cmp(&(&b.file, &b.name)))
+    });
+    rows.truncate(limit);
+    rows
+}
@@ -0,0 +1,124 @@
+// --- Clippy lints: `cargo clippy --message-format=json` merged into the report ---
+use crate::{artifact, FileMetrics};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub struct Lint {
+    pub file: PathBuf, // canonical, so it compares equal to scanned paths
+    pub line: usize,
+    pub lint: String, // without the `clippy::` prefix
+}
+
+// Cargo emits one JSON message per line; only clippy's own lints are kept.
+// Span paths are relative to `base` (the workspace root cargo ran in).
+fn parse(text: &str, base: &Path) -> Vec<Lint> {
+    text.lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .filter(|m| m["reason"] == "compiler-message")
+        .filter_map(|m| {
+            let message = &m["message"];
+            let lint = message["code"]["code"].as_str()?.strip_prefix("clippy::")?;
+            let span = message["spans"]
+                .as_array()?
+                .iter()
+                .find(|s| s["is_primary"] == true)?;
+            let file = base.join(span["file_name"].as_str()?);
+            Some(Lint {
+                file: fs::canonicalize(&file).unwrap_or(file),
+                line: span["line_start"].as_u64()? as usize,
+                lint: lint.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn cargo(dir: &Path, args: &[&str]) -> Result<std::process::Output, String> {
+    Command::new("cargo")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run cargo: {}", e))
+}
+
+// The directory clippy reports paths against.
+pub fn workspace_root(dir: &Path) -> Result<PathBuf, String> {
+    let output = cargo(
+        dir,
+        &["locate-project", "--workspace", "--message-format", "plain"],
+    )?;
+    let manifest = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    match manifest.parent() {
+        Some(root) if output.status.success() => Ok(root.to_path_buf()),
+        _ => Err(format!("{} is not inside a cargo workspace", dir.display())),
+    }
+}
+
+// Saved output is assumed to come from the workspace being analyzed.
+pub fn load(path: &str, root: &Path) -> Result<Vec<Lint>, String> {
+    let text = artifact::read_to_string(path)?;
+    Ok(parse(&text, root))
+}
+
+// Lints are warnings, so a non-zero exit means the build itself failed.
+pub fn run(root: &Path) -> Result<Vec<Lint>, String> {
+    let output = cargo(root, &["clippy", "--quiet", "--message-format=json"])?;
+    if !output.status.success() {
+        return Err(format!(
+            "cargo clippy failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse(&String::from_utf8_lossy(&output.stdout), root))
+}
+
+// Counts each lint against its file and the function whose lines contain it.
+// Lints in files outside the scan are dropped.
+pub fn attach(files: &mut [FileMetrics], lints: &[Lint]) {
+    let mut by_file: HashMap<&Path, Vec<&Lint>> = HashMap::new();
+    for lint in lints {
+        by_file.entry(&lint.file).or_default().push(lint);
+    }
+    for file in files {
+        let Some(found) = fs::canonicalize(&file.file)
+            .ok()
+            .and_then(|p| by_file.get(p.as_path()))
+        else {
+            continue;
+        };
+        for lint in found {
+            *file.lints.entry(lint.lint.clone()).or_default() += 1;
+            if let Some(f) = file
+                .functions
+                .iter_mut()
+                .find(|f| (f.line..=f.end_line).contains(&lint.line))
+            {
+                f.lints += 1;
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct FileLints {
+    pub file: String,
+    pub total: usize,
+    pub lints: BTreeMap<String, usize>,
+}
+
+// Files with lints, most first.
+pub fn summarize(files: &[FileMetrics]) -> Vec<FileLints> {
+    let mut rows: Vec<FileLints> = files
+        .iter()
+        .filter(|f| !f.lints.is_empty())
+        .map(|f| FileLints {
+            file: f.file.clone(),
+            total: f.lints.values().sum(),
+            lints: f.lints.clone(),
+        })
+        .collect();
+    rows.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.file.cmp(&b.file)));
+    rows
+}
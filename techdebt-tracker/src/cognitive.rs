@@ -0,0 +1,106 @@
+// --- Cognitive complexity (SonarSource model, adapted to Rust) ---
+// Flow-breaking structures cost 1 plus their nesting depth; `else`/`else if`,
+// labelled jumps and each run of like logical operators cost 1. Recursion is
+// added afterwards from the call graph (see `callgraph::mark_recursion`).
+use syn::visit::{self, Visit};
+
+#[derive(Default)]
+pub struct CognitiveVisitor {
+    pub score: usize,
+    nesting: usize,
+}
+
+impl CognitiveVisitor {
+    fn nested<F: FnOnce(&mut Self)>(&mut self, f: F) {
+        self.nesting += 1;
+        f(self);
+        self.nesting -= 1;
+    }
+
+    fn visit_if_chain(&mut self, node: &syn::ExprIf) {
+        self.visit_expr(&node.cond);
+        self.nested(|v| v.visit_block(&node.then_branch));
+        if let Some((_, else_branch)) = &node.else_branch {
+            self.score += 1;
+            match &**else_branch {
+                syn::Expr::If(else_if) => self.visit_if_chain(else_if),
+                other => self.nested(|v| v.visit_expr(other)),
+            }
+        }
+    }
+}
+
+fn logical_ops(expr: &syn::Expr, ops: &mut Vec<bool>, leaves: &mut Vec<syn::Expr>) {
+    match expr {
+        syn::Expr::Binary(bin) if matches!(bin.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) => {
+            logical_ops(&bin.left, ops, leaves);
+            ops.push(matches!(bin.op, syn::BinOp::And(_)));
+            logical_ops(&bin.right, ops, leaves);
+        }
+        syn::Expr::Paren(p) => logical_ops(&p.expr, ops, leaves),
+        other => leaves.push(other.clone()),
+    }
+}
+
+impl<'ast> Visit<'ast> for CognitiveVisitor {
+    fn visit_expr_if(&mut self, node: &'ast syn::ExprIf) {
+        self.score += 1 + self.nesting;
+        self.visit_if_chain(node);
+    }
+
+    fn visit_expr_match(&mut self, node: &'ast syn::ExprMatch) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&node.expr);
+        self.nested(|v| {
+            for arm in &node.arms {
+                v.visit_arm(arm);
+            }
+        });
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&node.expr);
+        self.nested(|v| v.visit_block(&node.body));
+    }
+
+    fn visit_expr_while(&mut self, node: &'ast syn::ExprWhile) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&node.cond);
+        self.nested(|v| v.visit_block(&node.body));
+    }
+
+    fn visit_expr_loop(&mut self, node: &'ast syn::ExprLoop) {
+        self.score += 1 + self.nesting;
+        self.nested(|v| v.visit_block(&node.body));
+    }
+
+    fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+        self.nested(|v| visit::visit_expr_closure(v, node));
+    }
+
+    fn visit_expr_break(&mut self, node: &'ast syn::ExprBreak) {
+        self.score += usize::from(node.label.is_some());
+        visit::visit_expr_break(self, node);
+    }
+
+    fn visit_expr_continue(&mut self, node: &'ast syn::ExprContinue) {
+        self.score += usize::from(node.label.is_some());
+    }
+
+    // `a && b && c` costs 1, `a && b || c` costs 2.
+    fn visit_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
+        if !matches!(node.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) {
+            return visit::visit_expr_binary(self, node);
+        }
+        let (mut ops, mut leaves) = (Vec::new(), Vec::new());
+        logical_ops(&syn::Expr::Binary(node.clone()), &mut ops, &mut leaves);
+        self.score += 1 + ops.windows(2).filter(|w| w[0] != w[1]).count();
+        for leaf in &leaves {
+            self.visit_expr(leaf);
+        }
+    }
+
+    // Nested items are measured on their own.
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
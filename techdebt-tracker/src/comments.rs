@@ -0,0 +1,210 @@
+// --- Comment classification: doc, inline and license/header boilerplate ---
+// A small lexer rather than line prefixes, so trailing comments and block
+// comments count and `//` inside string literals does not.
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+pub struct CommentLines {
+    pub doc: usize,    // `///`, `//!`, `/** */`, `/*! */`
+    pub inline: usize, // any other comment, including trailing ones
+    pub header: usize, // leading license/copyright block, counted in neither
+}
+
+// A license header must start this close to the top of the file.
+const HEADER_WITHIN_LINES: usize = 30;
+const HEADER_MARKERS: [&str; 5] = [
+    "spdx-license-identifier",
+    "copyright",
+    "licensed under",
+    "all rights reserved",
+    "permission is hereby granted",
+];
+
+#[derive(Default, Clone, Copy)]
+struct Line {
+    code: bool,
+    doc: bool,
+    inline: bool,
+}
+
+fn is_doc_line_comment(rest: &[char]) -> bool {
+    // `///` but not `////`, or `//!`.
+    matches!(rest, ['/', '/', '/', c, ..] if *c != '/')
+        || matches!(rest, ['/', '/', '/'])
+        || matches!(rest, ['/', '/', '!', ..])
+}
+
+fn is_doc_block_comment(rest: &[char]) -> bool {
+    // `/**` but not `/**/` or `/***`, or `/*!`.
+    matches!(rest, ['/', '*', '*', c, ..] if *c != '*' && *c != '/')
+        || matches!(rest, ['/', '*', '!', ..])
+}
+
+fn mark(lines: &mut [Line], doc: bool) {
+    let line = lines.last_mut().unwrap();
+    if doc {
+        line.doc = true;
+    } else {
+        line.inline = true;
+    }
+}
+
+fn lex(content: &str) -> Vec<Line> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut lines = vec![Line::default()];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = &chars[i..];
+        if c == '\n' {
+            lines.push(Line::default());
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if rest.starts_with(&['/', '/']) {
+            let doc = is_doc_line_comment(rest);
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            mark(&mut lines, doc);
+        } else if rest.starts_with(&['/', '*']) {
+            let doc = is_doc_block_comment(rest);
+            let mut depth = 0;
+            while i < chars.len() {
+                if chars[i..].starts_with(&['/', '*']) {
+                    depth += 1;
+                    i += 2;
+                } else if chars[i..].starts_with(&['*', '/']) {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    if chars[i] == '\n' {
+                        mark(&mut lines, doc);
+                        lines.push(Line::default());
+                    }
+                    i += 1;
+                }
+            }
+            mark(&mut lines, doc);
+        } else {
+            lines.last_mut().unwrap().code = true;
+            i = skip_token(&chars, i, &mut lines);
+        }
+    }
+    lines
+}
+
+// Skips one code token, stepping over string and char literals whole so
+// their contents are never mistaken for comments.
+fn skip_token(chars: &[char], start: usize, lines: &mut Vec<Line>) -> usize {
+    let at = |i: usize| chars.get(i).copied().unwrap_or('\0');
+    let ident_before = start > 0 && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_');
+    let mut i = start;
+
+    // Raw strings: r"..", r#".."#, br#".."#.
+    if !ident_before && (at(i) == 'r' || (at(i) == 'b' && at(i + 1) == 'r')) {
+        let mut j = i + if at(i) == 'b' { 2 } else { 1 };
+        let hashes = chars[j.min(chars.len())..]
+            .iter()
+            .take_while(|&&c| c == '#')
+            .count();
+        j += hashes;
+        if at(j) == '"' {
+            j += 1;
+            while j < chars.len() {
+                if chars[j] == '"'
+                    && chars[j + 1..].iter().take_while(|&&c| c == '#').count() >= hashes
+                {
+                    return j + 1 + hashes;
+                }
+                if chars[j] == '\n' {
+                    lines.push(Line {
+                        code: true,
+                        ..Line::default()
+                    });
+                }
+                j += 1;
+            }
+            return j;
+        }
+    }
+    if at(i) == 'b' && (at(i + 1) == '"' || at(i + 1) == '\'') && !ident_before {
+        i += 1;
+    }
+    match at(i) {
+        '"' => {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                if at(i) == '\n' {
+                    lines.push(Line {
+                        code: true,
+                        ..Line::default()
+                    });
+                }
+                i += 1;
+            }
+            i + 1
+        }
+        // A char literal ('x', '\n', '\u{1F600}'); otherwise a lifetime.
+        '\'' if at(i + 1) == '\\' => {
+            i += 2;
+            while i < chars.len() && chars[i] != '\'' && chars[i] != '\n' {
+                i += 1;
+            }
+            i + 1
+        }
+        '\'' if at(i + 2) == '\'' => i + 3,
+        c if c.is_alphanumeric() || c == '_' => {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            i
+        }
+        _ => i + 1,
+    }
+}
+
+pub fn classify(content: &str) -> CommentLines {
+    let lines = lex(content);
+    let text: Vec<&str> = content.lines().collect();
+
+    // The leading run of comment-only lines, if it reads like a license.
+    let first = lines
+        .iter()
+        .position(|l| l.code || l.doc || l.inline)
+        .unwrap_or(lines.len());
+    let mut header = 0..0;
+    if first < HEADER_WITHIN_LINES && lines.get(first).is_some_and(|l| l.inline && !l.code) {
+        let end = lines[first..]
+            .iter()
+            .position(|l| l.code || l.doc || !l.inline)
+            .map_or(lines.len(), |n| first + n);
+        let block = text[first..end.min(text.len())].join("\n").to_lowercase();
+        if HEADER_MARKERS.iter().any(|m| block.contains(m)) {
+            header = first..end;
+        }
+    }
+
+    let mut counts = CommentLines {
+        header: header.len(),
+        ..CommentLines::default()
+    };
+    for (n, line) in lines.iter().enumerate() {
+        if header.contains(&n) {
+            continue;
+        }
+        if line.doc {
+            counts.doc += 1;
+        } else if line.inline {
+            counts.inline += 1;
+        }
+    }
+    counts
+}
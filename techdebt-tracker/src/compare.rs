@@ -0,0 +1,608 @@
+// --- `compare`: function-level diff of two report JSONs (text, JSON, HTML or Markdown) ---
+use crate::artifact;
+use crate::locale::tr;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// Only the fields the diff needs, so reports from older versions still load.
+#[derive(Deserialize)]
+struct InputMetrics {
+    loc: usize,
+    cyclomatic_complexity: usize,
+    #[serde(default)]
+    cyclomatic_distribution_bounds: Vec<usize>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct FunctionSide {
+    pub complexity: usize,
+    #[serde(default)]
+    pub cognitive_complexity: usize,
+    pub loc: usize,
+    #[serde(default)]
+    pub nesting: usize,
+}
+
+#[derive(Deserialize)]
+struct InputFunction {
+    file: String,
+    function: String,
+    #[serde(flatten)]
+    side: FunctionSide,
+}
+
+#[derive(Deserialize)]
+struct InputFile {
+    functions: Vec<InputFunction>,
+}
+
+#[derive(Deserialize)]
+struct InputGrades {
+    grade: String,
+}
+
+#[derive(Deserialize)]
+struct InputReport {
+    metrics: InputMetrics,
+    #[serde(default)]
+    grades: Option<InputGrades>, // absent before grading existed
+    #[serde(default)]
+    maintainability_index: Option<f64>, // NaN is written as null
+    #[serde(default)]
+    files: Vec<InputFile>,
+}
+
+#[derive(Serialize)]
+pub struct FunctionChange {
+    pub name: String,   // file::function
+    pub status: String, // "added", "removed", "worsened" or "improved"
+    pub before: Option<FunctionSide>,
+    pub after: Option<FunctionSide>,
+    pub complexity_delta: i64,
+    pub review_minutes: f64, // estimated, see review_minutes()
+}
+
+#[derive(Serialize)]
+pub struct ReportDiff {
+    pub from: String,
+    pub to: String,
+    pub loc_delta: i64,
+    pub complexity_delta: i64,
+    pub maintainability_delta: f64,
+    pub grade_before: Option<String>, // None for reports without grades
+    pub grade_after: Option<String>,
+    pub review_minutes: f64, // over all changes
+    pub changes: Vec<FunctionChange>,
+    pub distribution_drift: DistributionDrift,
+}
+
+#[derive(Serialize)]
+pub struct BucketShift {
+    pub from: String,
+    pub to: String,
+    pub functions: usize,
+}
+
+// How the shape of the complexity distribution moved, which catches slow
+// decay spread over many functions that no single threshold trips on.
+#[derive(Serialize)]
+pub struct DistributionDrift {
+    pub buckets: Vec<String>,
+    pub before: Vec<usize>, // functions per bucket
+    pub after: Vec<usize>,
+    pub moved_up: usize, // functions in both reports, now in a higher bucket
+    pub moved_down: usize,
+    pub shifts: Vec<BucketShift>, // from one bucket to another, most first
+    pub ks_statistic: f64,        // largest gap between the two complexity CDFs, 0..1
+    pub ks_p_value: f64,          // chance of a gap that large without real drift
+}
+
+const STATUSES: [&str; 4] = ["added", "removed", "worsened", "improved"];
+
+// Review-time model: a fixed cost to load the context, plus reading time per
+// statement, plus time per decision to follow, plus time for each nesting
+// level past the first, since deep code must be held in the head at once.
+const REVIEW_BASE_MINUTES: f64 = 1.0;
+const REVIEW_MINUTES_PER_STATEMENT: f64 = 0.5;
+const REVIEW_MINUTES_PER_DECISION: f64 = 0.5;
+const REVIEW_MINUTES_PER_NESTING: f64 = 1.0;
+
+// A changed function is reviewed as it now stands; a removed one only needs
+// the deletion confirmed, which the base cost covers.
+fn review_minutes(after: Option<FunctionSide>) -> f64 {
+    let Some(f) = after else {
+        return REVIEW_BASE_MINUTES;
+    };
+    REVIEW_BASE_MINUTES
+        + f.loc as f64 * REVIEW_MINUTES_PER_STATEMENT
+        + f.complexity.saturating_sub(1) as f64 * REVIEW_MINUTES_PER_DECISION
+        + f.nesting.saturating_sub(1) as f64 * REVIEW_MINUTES_PER_NESTING
+}
+
+fn load(path: &str) -> Result<InputReport, String> {
+    let text = artifact::read_to_string(path)?;
+    serde_json::from_str(&text).map_err(|e| format!("{}: not a report: {}", path, e))
+}
+
+// Same-named functions in one file keep the most complex.
+fn functions(report: &InputReport) -> BTreeMap<String, FunctionSide> {
+    let mut out: BTreeMap<String, FunctionSide> = BTreeMap::new();
+    for f in report.files.iter().flat_map(|f| &f.functions) {
+        let name = format!("{}::{}", f.file, f.function);
+        let entry = out.entry(name).or_insert(f.side);
+        if f.side.complexity > entry.complexity {
+            *entry = f.side;
+        }
+    }
+    out
+}
+
+// Kolmogorov-Smirnov statistic of two sorted samples.
+fn ks_statistic(a: &[usize], b: &[usize]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let (mut i, mut j, mut d) = (0, 0, 0.0_f64);
+    while i < a.len() && j < b.len() {
+        let v = a[i].min(b[j]);
+        while i < a.len() && a[i] == v {
+            i += 1;
+        }
+        while j < b.len() && b[j] == v {
+            j += 1;
+        }
+        d = d.max((i as f64 / a.len() as f64 - j as f64 / b.len() as f64).abs());
+    }
+    d
+}
+
+// Asymptotic significance of `d` (Numerical Recipes' probks); 1 when the
+// series does not converge, i.e. for tiny gaps.
+fn ks_p_value(d: f64, n: usize, m: usize) -> f64 {
+    if n == 0 || m == 0 {
+        return 1.0;
+    }
+    let en = (n as f64 * m as f64 / (n + m) as f64).sqrt();
+    let lambda = (en + 0.12 + 0.11 / en) * d;
+    let a2 = -2.0 * lambda * lambda;
+    let (mut sum, mut sign, mut previous) = (0.0, 2.0, 0.0_f64);
+    for k in 1..=100 {
+        let term = sign * (a2 * (k * k) as f64).exp();
+        sum += term;
+        if term.abs() <= 0.001 * previous || term.abs() <= 1e-8 * sum {
+            return sum.clamp(0.0, 1.0);
+        }
+        sign = -sign;
+        previous = term.abs();
+    }
+    1.0
+}
+
+fn drift(
+    before: &BTreeMap<String, FunctionSide>,
+    after: &BTreeMap<String, FunctionSide>,
+    bounds: &[usize],
+) -> DistributionDrift {
+    let buckets = crate::bucket_labels(bounds);
+    let bucket = |s: &FunctionSide| {
+        bounds
+            .iter()
+            .position(|&b| s.complexity <= b)
+            .unwrap_or(bounds.len())
+    };
+    let counts = |side: &BTreeMap<String, FunctionSide>| {
+        let mut counts = vec![0; buckets.len()];
+        for s in side.values() {
+            counts[bucket(s)] += 1;
+        }
+        counts
+    };
+
+    let mut moves: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (name, b) in before {
+        if let Some(a) = after.get(name) {
+            let (from, to) = (bucket(b), bucket(a));
+            if from != to {
+                *moves.entry((from, to)).or_insert(0) += 1;
+            }
+        }
+    }
+    let mut shifts: Vec<BucketShift> = moves
+        .iter()
+        .map(|(&(from, to), &functions)| BucketShift {
+            from: buckets[from].clone(),
+            to: buckets[to].clone(),
+            functions,
+        })
+        .collect();
+    shifts.sort_by_key(|s| std::cmp::Reverse(s.functions));
+
+    let sorted = |side: &BTreeMap<String, FunctionSide>| {
+        let mut v: Vec<usize> = side.values().map(|s| s.complexity).collect();
+        v.sort_unstable();
+        v
+    };
+    let ks = ks_statistic(&sorted(before), &sorted(after));
+    DistributionDrift {
+        before: counts(before),
+        after: counts(after),
+        moved_up: moves
+            .iter()
+            .filter(|(k, _)| k.1 > k.0)
+            .map(|(_, n)| n)
+            .sum(),
+        moved_down: moves
+            .iter()
+            .filter(|(k, _)| k.1 < k.0)
+            .map(|(_, n)| n)
+            .sum(),
+        shifts,
+        ks_statistic: ks,
+        ks_p_value: ks_p_value(ks, before.len(), after.len()),
+        buckets,
+    }
+}
+
+pub fn diff(from: &str, to: &str) -> Result<ReportDiff, String> {
+    let (old, new) = (load(from)?, load(to)?);
+    let (before, after) = (functions(&old), functions(&new));
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut changes: Vec<FunctionChange> = names
+        .into_iter()
+        .filter_map(|name| {
+            let (b, a) = (before.get(name).copied(), after.get(name).copied());
+            let complexity = |s: Option<FunctionSide>| s.map_or(0, |s| s.complexity) as i64;
+            let delta = complexity(a) - complexity(b);
+            let status = match (b, a) {
+                (None, _) => "added",
+                (_, None) => "removed",
+                _ if delta > 0 => "worsened",
+                _ if delta < 0 => "improved",
+                _ => return None,
+            };
+            Some(FunctionChange {
+                name: name.clone(),
+                status: status.to_string(),
+                before: b,
+                after: a,
+                complexity_delta: delta,
+                review_minutes: review_minutes(a),
+            })
+        })
+        .collect();
+    let rank = |s: &str| STATUSES.iter().position(|&x| x == s);
+    changes.sort_by(|x, y| {
+        rank(&x.status)
+            .cmp(&rank(&y.status))
+            .then_with(|| y.complexity_delta.abs().cmp(&x.complexity_delta.abs()))
+            .then_with(|| x.name.cmp(&y.name))
+    });
+
+    // The newer report's buckets; reports predating the field used 5 and 10.
+    let bounds = [
+        &new.metrics.cyclomatic_distribution_bounds,
+        &old.metrics.cyclomatic_distribution_bounds,
+    ]
+    .into_iter()
+    .find(|b| !b.is_empty())
+    .cloned()
+    .unwrap_or_else(|| vec![5, 10]);
+    let distribution_drift = drift(&before, &after, &bounds);
+
+    let mi = |r: &InputReport| {
+        r.maintainability_index
+            .filter(|m| m.is_finite())
+            .unwrap_or(0.0)
+    };
+    let grade = |r: &InputReport| {
+        r.grades
+            .as_ref()
+            .map(|g| g.grade.clone())
+            .filter(|g| !g.is_empty())
+    };
+    Ok(ReportDiff {
+        from: from.to_string(),
+        to: to.to_string(),
+        loc_delta: new.metrics.loc as i64 - old.metrics.loc as i64,
+        complexity_delta: new.metrics.cyclomatic_complexity as i64
+            - old.metrics.cyclomatic_complexity as i64,
+        maintainability_delta: mi(&new) - mi(&old),
+        grade_before: grade(&old),
+        grade_after: grade(&new),
+        review_minutes: changes.iter().map(|c| c.review_minutes).sum(),
+        changes,
+        distribution_drift,
+    })
+}
+
+// "B → C", with "?" for a side without grades; `None` if neither has one.
+fn grade_change(d: &ReportDiff) -> Option<String> {
+    if d.grade_before.is_none() && d.grade_after.is_none() {
+        return None;
+    }
+    let side = |g: &Option<String>| g.clone().unwrap_or_else(|| "?".to_string());
+    Some(format!(
+        "{} → {}",
+        side(&d.grade_before),
+        side(&d.grade_after)
+    ))
+}
+
+pub fn print_diff(d: &ReportDiff) {
+    println!("{}", tr!("compare-title", d.from, d.to));
+    if let Some(change) = grade_change(d) {
+        println!("{}", tr!("compare-grade", change));
+    }
+    println!("{}", tr!("compare-loc", format!("{:+}", d.loc_delta)));
+    println!("{}", tr!("complexity", format!("{:+}", d.complexity_delta)));
+    println!(
+        "{}",
+        tr!("compare-mi", format!("{:+.2}", d.maintainability_delta))
+    );
+    println!(
+        "{}",
+        tr!("compare-review", format!("{:.0}", d.review_minutes))
+    );
+    print_drift(&d.distribution_drift);
+    for status in STATUSES {
+        let rows: Vec<&FunctionChange> = d.changes.iter().filter(|c| c.status == status).collect();
+        if rows.is_empty() {
+            continue;
+        }
+        let title = tr!(
+            "compare-status",
+            tr!(&format!("status-{}", status)),
+            rows.len()
+        );
+        println!("\n{}:", title);
+        for c in rows {
+            let side =
+                |s: Option<FunctionSide>| s.map_or("-".to_string(), |s| s.complexity.to_string());
+            println!(
+                "{}: {} → {} ({:+}), ~{:.1} min review",
+                c.name,
+                side(c.before),
+                side(c.after),
+                c.complexity_delta,
+                c.review_minutes
+            );
+        }
+    }
+}
+
+fn print_drift(drift: &DistributionDrift) {
+    println!(
+        "\n{}",
+        tr!(
+            "compare-drift",
+            format!("{:.3}", drift.ks_statistic),
+            format!("{:.3}", drift.ks_p_value),
+            drift.moved_up,
+            drift.moved_down
+        )
+    );
+    for (i, label) in drift.buckets.iter().enumerate() {
+        println!("  {}: {} → {}", label, drift.before[i], drift.after[i]);
+    }
+    for s in &drift.shifts {
+        println!("  {} → {}: {}", s.from, s.to, s.functions);
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Growth is bad, so positive deltas are red.
+fn delta_cell(delta: i64) -> String {
+    let class = match delta {
+        d if d > 0 => "up",
+        d if d < 0 => "down",
+        _ => "flat",
+    };
+    format!("<td class=\"{}\">{:+}</td>", class, delta)
+}
+
+// A self-contained page (inline CSS, no scripts) suitable for CI artifacts.
+pub fn render_html(d: &ReportDiff) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>CodeHealth: {} → {}</title>\n\
+         <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse;margin-bottom:2em}}\
+         td,th{{border:1px solid #ccc;padding:4px 8px;text-align:right}}td:first-child{{text-align:left;font-family:monospace}}\
+         .up{{color:#b00020}}.down{{color:#1b7f3b}}.flat{{color:#666}}</style></head><body>\n",
+        escape(&d.from),
+        escape(&d.to)
+    );
+    let _ = writeln!(
+        out,
+        "<h1>{} → {}</h1>\n<table><tr><th>{}</th><th>Δ</th></tr>",
+        escape(&d.from),
+        escape(&d.to),
+        escape(&tr!("html-metric"))
+    );
+    if let Some(change) = grade_change(d) {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"flat\">{}</td></tr>",
+            escape(&tr!("html-grade")),
+            escape(&change)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "<tr><td>{}</td>{}</tr>",
+        escape(&tr!("label-loc")),
+        delta_cell(d.loc_delta)
+    );
+    let _ = writeln!(
+        out,
+        "<tr><td>{}</td>{}</tr>",
+        escape(&tr!("html-complexity")),
+        delta_cell(d.complexity_delta)
+    );
+    let mi_class = match d.maintainability_delta {
+        m if m < 0.0 => "up",
+        m if m > 0.0 => "down",
+        _ => "flat",
+    };
+    let _ = writeln!(
+        out,
+        "<tr><td>{}</td><td class=\"{}\">{:+.2}</td></tr>",
+        escape(&tr!("html-mi")),
+        mi_class,
+        d.maintainability_delta
+    );
+    let _ = writeln!(
+        out,
+        "<tr><td>{}</td><td class=\"flat\">{:.0}</td></tr>\n</table>",
+        escape(&tr!("html-review")),
+        d.review_minutes
+    );
+    let drift = &d.distribution_drift;
+    let _ = writeln!(
+        out,
+        "<h2>{}</h2>\n<table><tr><th></th><th>{}</th><th>{}</th><th>Δ</th></tr>",
+        escape(&tr!(
+            "compare-drift",
+            format!("{:.3}", drift.ks_statistic),
+            format!("{:.3}", drift.ks_p_value),
+            drift.moved_up,
+            drift.moved_down
+        )),
+        escape(&d.from),
+        escape(&d.to)
+    );
+    for (i, label) in drift.buckets.iter().enumerate() {
+        let (b, a) = (drift.before[i], drift.after[i]);
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td>{}</tr>",
+            escape(label),
+            b,
+            a,
+            delta_cell(a as i64 - b as i64)
+        );
+    }
+    out.push_str("</table>\n");
+
+    for status in STATUSES {
+        let rows: Vec<&FunctionChange> = d.changes.iter().filter(|c| c.status == status).collect();
+        if rows.is_empty() {
+            continue;
+        }
+        let title = tr!(
+            "compare-status",
+            tr!(&format!("status-{}", status)),
+            rows.len()
+        );
+        // An empty key is the untranslated Δ column.
+        let header: String = [
+            "html-function",
+            "html-complexity-before",
+            "html-complexity-after",
+            "",
+            "html-loc-before",
+            "html-loc-after",
+            "html-review-min",
+        ]
+        .iter()
+        .map(|&k| match k {
+            "" => "<th>Δ</th>".to_string(),
+            k => format!("<th>{}</th>", escape(&tr!(k))),
+        })
+        .collect();
+        let _ = writeln!(
+            out,
+            "<h2>{}</h2>\n<table><tr>{}</tr>",
+            escape(&title),
+            header
+        );
+        for c in rows {
+            let cell = |s: Option<FunctionSide>, f: fn(FunctionSide) -> usize| {
+                s.map_or("–".to_string(), |s| f(s).to_string())
+            };
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td>{}<td>{}</td><td>{}</td><td>{:.1}</td></tr>",
+                escape(&c.name),
+                cell(c.before, |s| s.complexity),
+                cell(c.after, |s| s.complexity),
+                delta_cell(c.complexity_delta),
+                cell(c.before, |s| s.loc),
+                cell(c.after, |s| s.loc),
+                c.review_minutes,
+            );
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
+
+// Pipes would split table cells; backticks would end the code span.
+fn markdown_code(value: &str) -> String {
+    format!("`{}`", value.replace('`', "'").replace('|', "\\|"))
+}
+
+// A PR comment body: headline deltas, the review budget, then one table of
+// changed functions, most expensive to review first.
+pub fn render_markdown(d: &ReportDiff) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "### CodeHealth: {} → {}\n", d.from, d.to);
+    if let Some(change) = grade_change(d) {
+        let _ = writeln!(out, "**Grade {}**\n", change);
+    }
+    let _ = writeln!(
+        out,
+        "LOC {:+} · complexity {:+} · maintainability {:+.2}\n",
+        d.loc_delta, d.complexity_delta, d.maintainability_delta
+    );
+    let _ = writeln!(
+        out,
+        "**Estimated review time: {:.0} min** across {} changed function(s)\n",
+        d.review_minutes,
+        d.changes.len()
+    );
+    let drift = &d.distribution_drift;
+    let _ = writeln!(
+        out,
+        "Complexity drift: KS {:.3} (p={:.3}), {} function(s) moved to a higher bucket, {} to a lower one\n",
+        drift.ks_statistic, drift.ks_p_value, drift.moved_up, drift.moved_down
+    );
+    if d.changes.is_empty() {
+        return out;
+    }
+    let mut rows: Vec<&FunctionChange> = d.changes.iter().collect();
+    rows.sort_by(|x, y| {
+        y.review_minutes
+            .total_cmp(&x.review_minutes)
+            .then_with(|| x.name.cmp(&y.name))
+    });
+    out.push_str("| Function | Status | Complexity | Δ | Review min |\n");
+    out.push_str("|---|---|---|---|---|\n");
+    for c in rows {
+        let side =
+            |s: Option<FunctionSide>| s.map_or("–".to_string(), |s| s.complexity.to_string());
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} → {} | {:+} | {:.1} |",
+            markdown_code(&c.name),
+            c.status,
+            side(c.before),
+            side(c.after),
+            c.complexity_delta,
+            c.review_minutes
+        );
+    }
+    out
+}
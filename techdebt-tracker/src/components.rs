@@ -0,0 +1,139 @@
+// --- `[components]`: logical components from path globs, with totals ---
+// `api = "src/api/**"` or `api = ["src/api/**", "src/http.rs"]`. Globs
+// match `/`-separated paths relative to a scan root or the working
+// directory: `*` and `?` stay within one segment, `**` spans any number.
+// A file matching several components goes to the most specific pattern.
+use crate::config::Globs;
+use crate::{paths, relative_path, FileMetrics, Report};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+pub const UNASSIGNED: &str = "(unassigned)";
+
+fn segment_matches(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            segment_matches(&pattern[1..], name)
+                || (!name.is_empty() && segment_matches(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => segment_matches(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => segment_matches(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            segments_match(&pattern[1..], path)
+                || (!path.is_empty() && segments_match(pattern, &path[1..]))
+        }
+        (Some(p), Some(s)) => {
+            let p: Vec<char> = p.chars().collect();
+            let s: Vec<char> = s.chars().collect();
+            segment_matches(&p, &s) && segments_match(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    path.split(['/', '\\']).filter(|s| !s.is_empty()).collect()
+}
+
+// Either separator works in both; case is folded where the OS does.
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, path) = (paths::fold_case(pattern), paths::fold_case(path));
+    segments_match(&segments(&pattern), &segments(&path))
+}
+
+// Sets each file's component; runs on raw paths, before canonicalization.
+pub fn assign(files: &mut [FileMetrics], roots: &[String], components: &BTreeMap<String, Globs>) {
+    if components.is_empty() {
+        return;
+    }
+    for file in files {
+        let candidates: Vec<String> = roots
+            .iter()
+            .map(String::as_str)
+            .chain(["."])
+            .map(|base| relative_path(&file.file, base))
+            .collect();
+        file.component = components
+            .iter()
+            .flat_map(|(name, globs)| globs.patterns().iter().map(move |p| (name, p)))
+            .filter(|(_, p)| candidates.iter().any(|c| glob_matches(p, c)))
+            .max_by_key(|(_, p)| p.len())
+            .map_or(UNASSIGNED.to_string(), |(name, _)| name.clone());
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct ComponentSummary {
+    pub component: String,
+    pub files: usize,
+    pub loc: usize,
+    pub functions: usize,
+    pub total_complexity: usize,
+    pub average_complexity: f64,
+    pub max_complexity: usize,
+    pub most_complex_function: String, // `file::function`
+    pub findings: usize,               // after suppressions, see count_findings()
+}
+
+// One entry per configured component, in name order, including empty
+// ones; unmatched files are totalled last.
+pub fn summarize(
+    files: &[FileMetrics],
+    components: &BTreeMap<String, Globs>,
+) -> Vec<ComponentSummary> {
+    if components.is_empty() {
+        return Vec::new();
+    }
+    let mut summaries: BTreeMap<&str, ComponentSummary> = components
+        .keys()
+        .map(|name| (name.as_str(), ComponentSummary::default()))
+        .collect();
+    for file in files {
+        let s = summaries.entry(&file.component).or_default();
+        s.files += 1;
+        s.loc = s.loc.saturating_add(file.loc);
+        s.functions += file.functions.len();
+        s.total_complexity = s.total_complexity.saturating_add(file.total_complexity);
+        for f in &file.functions {
+            if f.complexity > s.max_complexity {
+                s.max_complexity = f.complexity;
+                s.most_complex_function = format!("{}::{}", f.file, f.function);
+            }
+        }
+    }
+    let unassigned = summaries.remove(UNASSIGNED);
+    summaries
+        .into_iter()
+        .map(|(name, s)| (name.to_string(), s))
+        .chain(unassigned.map(|s| (UNASSIGNED.to_string(), s)))
+        .map(|(component, mut s)| {
+            s.component = component;
+            s.average_complexity = s.total_complexity as f64 / s.functions.max(1) as f64;
+            s
+        })
+        .collect()
+}
+
+// Run with the rule statistics, once suppressions have removed findings.
+pub fn count_findings(report: &mut Report) {
+    let owner: HashMap<&str, &str> = report
+        .files
+        .iter()
+        .map(|f| (f.file.as_str(), f.component.as_str()))
+        .collect();
+    for c in &mut report.components {
+        c.findings = report
+            .findings
+            .iter()
+            .filter(|f| owner.get(f.file.as_str()) == Some(&c.component.as_str()))
+            .count();
+    }
+}
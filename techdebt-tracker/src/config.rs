@@ -0,0 +1,344 @@
+// --- Configuration (.codehealth.toml) and threshold profiles ---
+use crate::{derived, fingerprint, logging, outliers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub const DEFAULT_CONFIG_FILE: &str = ".codehealth.toml";
+
+// Every rule limit lives here so profiles can bundle them consistently.
+// `None` means the rule is disabled.
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Thresholds {
+    pub max_complexity: Option<u32>,
+    pub max_exit_points: Option<usize>,
+    pub max_condition_operands: Option<usize>,
+    pub max_call_chain: Option<usize>,
+    pub max_quote_tokens: Option<usize>,
+    pub max_macro_rules: Option<usize>,
+    pub max_macro_depth: Option<usize>,
+    pub max_macro_tokens: Option<usize>,
+    pub max_file_loc: Option<usize>,
+    pub max_include_lines: Option<usize>, // include!/include_str! target size
+    pub auto_threshold: Option<String>,   // outlier method, see outliers.rs
+    pub max_items_per_module: Option<usize>,
+    pub max_api_growth: Option<usize>,
+    // Delta gates against a tagged history snapshot, see --delta-baseline.
+    pub max_total_complexity_increase: Option<usize>,
+    pub max_new_functions_over_complexity: Option<usize>, // newly over `outlier-complexity`
+    pub max_findings: Option<usize>, // after suppressions and ignore-file entries
+    pub max_debt_growth_per_week: Option<f64>, // debt minutes, see velocity.rs
+    // Outlier gates: how many functions may exceed `outlier-complexity`.
+    pub outlier_complexity: Option<usize>,
+    pub max_functions_over_complexity: Option<usize>,
+    pub max_percent_functions_over_complexity: Option<f64>,
+    pub feature_envy_ratio: Option<f64>,
+}
+
+impl Thresholds {
+    // Values set in `other` win over ours.
+    pub fn overlay(self, other: &Thresholds) -> Thresholds {
+        Thresholds {
+            max_complexity: other.max_complexity.or(self.max_complexity),
+            max_exit_points: other.max_exit_points.or(self.max_exit_points),
+            max_condition_operands: other.max_condition_operands.or(self.max_condition_operands),
+            max_call_chain: other.max_call_chain.or(self.max_call_chain),
+            max_quote_tokens: other.max_quote_tokens.or(self.max_quote_tokens),
+            max_macro_rules: other.max_macro_rules.or(self.max_macro_rules),
+            max_macro_depth: other.max_macro_depth.or(self.max_macro_depth),
+            max_macro_tokens: other.max_macro_tokens.or(self.max_macro_tokens),
+            max_file_loc: other.max_file_loc.or(self.max_file_loc),
+            max_include_lines: other.max_include_lines.or(self.max_include_lines),
+            auto_threshold: other.auto_threshold.clone().or(self.auto_threshold),
+            max_items_per_module: other.max_items_per_module.or(self.max_items_per_module),
+            max_api_growth: other.max_api_growth.or(self.max_api_growth),
+            max_total_complexity_increase: other
+                .max_total_complexity_increase
+                .or(self.max_total_complexity_increase),
+            max_new_functions_over_complexity: other
+                .max_new_functions_over_complexity
+                .or(self.max_new_functions_over_complexity),
+            max_findings: other.max_findings.or(self.max_findings),
+            max_debt_growth_per_week: other
+                .max_debt_growth_per_week
+                .or(self.max_debt_growth_per_week),
+            outlier_complexity: other.outlier_complexity.or(self.outlier_complexity),
+            max_functions_over_complexity: other
+                .max_functions_over_complexity
+                .or(self.max_functions_over_complexity),
+            max_percent_functions_over_complexity: other
+                .max_percent_functions_over_complexity
+                .or(self.max_percent_functions_over_complexity),
+            feature_envy_ratio: other.feature_envy_ratio.or(self.feature_envy_ratio),
+        }
+    }
+}
+
+// How cyclomatic complexity is counted. `statements` is the historical
+// model: +1 per `if`/`match`/`while`/`for` in statement position.
+// `mccabe` counts every branch (each `if`, loop and match arm after the
+// first, plus guards); `modified` counts a whole `match` as one decision;
+// `strict` is `mccabe` plus one per `&&`/`||`.
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ComplexityModel {
+    #[default]
+    Statements,
+    Mccabe,
+    Modified,
+    Strict,
+}
+
+pub const COMPLEXITY_MODELS: [&str; 4] = ["statements", "mccabe", "modified", "strict"];
+
+impl ComplexityModel {
+    pub fn from_name(name: &str) -> Option<ComplexityModel> {
+        match name {
+            "statements" => Some(ComplexityModel::Statements),
+            "mccabe" => Some(ComplexityModel::Mccabe),
+            "modified" => Some(ComplexityModel::Modified),
+            "strict" => Some(ComplexityModel::Strict),
+            _ => None,
+        }
+    }
+}
+
+// Silences findings matching every field that is set; paths are relative
+// to the path root, as in the report. From its `expires` date (YYYY-MM-DD)
+// on, it silences nothing and is listed as expired debt instead. With
+// `older-than-days`, only findings whose function the history store has
+// known for at least that long are silenced, see age.rs.
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Suppression {
+    pub rule: Option<String>,
+    pub file: Option<String>,
+    pub function: Option<String>,
+    pub fingerprint: Option<String>,
+    pub reason: Option<String>,
+    pub expires: Option<String>,
+    pub older_than_days: Option<u64>,
+    #[serde(skip)]
+    pub origin: Option<String>, // `file:line` for ignore-file entries
+}
+
+// A config-defined per-function metric, see derived.rs. With `max`, the
+// worst function over it fails the `derived:<name>` gate.
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct DerivedMetric {
+    pub expression: String,
+    pub max: Option<f64>,
+}
+
+// Path globs of one `[components]` entry, a single string or a list.
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Globs {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Globs {
+    pub fn patterns(&self) -> &[String] {
+        match self {
+            Globs::One(p) => std::slice::from_ref(p),
+            Globs::Many(ps) => ps,
+        }
+    }
+}
+
+// Lowest score (0-100) that still earns each grade; anything below `d` is
+// an F. See grades.rs for how scores are computed.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+#[serde(default)]
+pub struct GradeBoundaries {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+}
+
+impl Default for GradeBoundaries {
+    fn default() -> Self {
+        GradeBoundaries {
+            a: 60.0,
+            b: 40.0,
+            c: 30.0,
+            d: 20.0,
+        }
+    }
+}
+
+// A named scan target, e.g. `[targets.backend]`, run via `run --target`.
+#[derive(Deserialize, Default, Clone)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Target {
+    pub paths: Vec<String>,
+    pub exclude: Vec<String>,
+    pub profile: Option<String>,
+    pub thresholds: Thresholds,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Config {
+    pub profile: Option<String>,
+    pub complexity_model: ComplexityModel,
+    // Inclusive upper edges of the complexity distribution buckets.
+    pub distribution: Vec<usize>,
+    pub thresholds: Thresholds,
+    pub profiles: BTreeMap<String, Thresholds>,
+    pub targets: BTreeMap<String, Target>,
+    pub suppressions: Vec<Suppression>,
+    pub derived_metrics: BTreeMap<String, DerivedMetric>,
+    pub components: BTreeMap<String, Globs>,
+    pub grades: GradeBoundaries,
+    // Named feature sets for --cfg-matrix, e.g. `full = ["serde", "tokio"]`.
+    pub cfg_matrix: BTreeMap<String, Vec<String>>,
+    #[serde(skip)]
+    pub file: Option<String>, // where it was loaded from, if anywhere
+    #[serde(skip)]
+    pub hash: Option<String>, // FNV-1a of the file's text, hex
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            profile: None,
+            complexity_model: ComplexityModel::default(),
+            distribution: vec![5, 10],
+            thresholds: Thresholds::default(),
+            profiles: BTreeMap::new(),
+            targets: BTreeMap::new(),
+            suppressions: Vec::new(),
+            derived_metrics: BTreeMap::new(),
+            components: BTreeMap::new(),
+            grades: GradeBoundaries::default(),
+            cfg_matrix: BTreeMap::new(),
+            file: None,
+            hash: None,
+        }
+    }
+}
+
+pub fn builtin_profile(name: &str) -> Option<Thresholds> {
+    let profile = match name {
+        "strict" => (25, 3, 4, 4, 500, 500, 200, 50, 2.0),
+        "default" => (50, 5, 6, 6, 1500, 1000, 500, 100, 3.0),
+        "legacy" => (100, 10, 10, 10, 5000, 3000, 2000, 300, 5.0),
+        _ => return None,
+    };
+    let (
+        complexity,
+        exit_points,
+        operands,
+        chain,
+        quote_tokens,
+        file_loc,
+        include_lines,
+        items,
+        envy,
+    ) = profile;
+    Some(Thresholds {
+        max_complexity: Some(complexity),
+        max_exit_points: Some(exit_points),
+        max_condition_operands: Some(operands),
+        max_call_chain: Some(chain),
+        max_quote_tokens: Some(quote_tokens),
+        max_file_loc: Some(file_loc),
+        max_include_lines: Some(include_lines),
+        max_items_per_module: Some(items),
+        feature_envy_ratio: Some(envy),
+        // API and debt growth budgets, finding and outlier counts and macro limits
+        // depend on the codebase (DSL-heavy crates live in macros), so
+        // profiles leave them unset.
+        ..Thresholds::default()
+    })
+}
+
+// An explicit `--config` must exist; the default file is optional.
+pub fn load(path: Option<&str>) -> Result<Config, String> {
+    let path = match path {
+        Some(p) => p,
+        None if Path::new(DEFAULT_CONFIG_FILE).is_file() => DEFAULT_CONFIG_FILE,
+        None => return Ok(Config::default()),
+    };
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let mut config: Config =
+        toml::from_str(&text).map_err(|e| format!("invalid config {}: {}", path, e))?;
+    config.file = Some(path.to_string());
+    config.hash = Some(format!("{:016x}", fingerprint::fnv1a(&text)));
+    if config.distribution.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(format!(
+            "invalid config {}: distribution edges must be strictly increasing",
+            path
+        ));
+    }
+    let methods = config
+        .profiles
+        .values()
+        .chain([&config.thresholds])
+        .filter_map(|t| t.auto_threshold.as_deref());
+    for method in methods {
+        if !outliers::METHODS.contains(&method) {
+            return Err(format!(
+                "invalid config {}: auto-threshold '{}', expected one of {}",
+                path,
+                method,
+                outliers::METHODS.join(", ")
+            ));
+        }
+    }
+    for s in &config.suppressions {
+        if let Some(date) = s.expires.as_deref().filter(|d| !logging::is_date(d)) {
+            return Err(format!(
+                "invalid config {}: suppression expires '{}', expected YYYY-MM-DD",
+                path, date
+            ));
+        }
+    }
+    for (name, metric) in &config.derived_metrics {
+        derived::parse(&metric.expression)
+            .map_err(|e| format!("invalid config {}: derived metric '{}': {}", path, name, e))?;
+    }
+    for (name, globs) in &config.components {
+        if globs.patterns().iter().all(|p| p.is_empty()) {
+            return Err(format!(
+                "invalid config {}: component '{}' has no path globs",
+                path, name
+            ));
+        }
+    }
+    let g = &config.grades;
+    if !(100.0 >= g.a && g.a > g.b && g.b > g.c && g.c > g.d && g.d >= 0.0) {
+        return Err(format!(
+            "invalid config {}: grade boundaries must satisfy 100 >= a > b > c > d >= 0",
+            path
+        ));
+    }
+    Ok(config)
+}
+
+impl Config {
+    // Precedence: profile < [thresholds] in config < command-line flags.
+    pub fn resolve_thresholds(
+        &self,
+        profile: Option<&str>,
+        cli: &Thresholds,
+    ) -> Result<Thresholds, String> {
+        let base = match profile.or(self.profile.as_deref()) {
+            None => Thresholds::default(),
+            // A config profile may shadow a built-in one, inheriting unset limits.
+            Some(name) => match (builtin_profile(name), self.profiles.get(name)) {
+                (None, None) => return Err(format!("unknown profile '{}'", name)),
+                (builtin, custom) => builtin
+                    .unwrap_or_default()
+                    .overlay(custom.unwrap_or(&Thresholds::default())),
+            },
+        };
+        Ok(base.overlay(&self.thresholds).overlay(cli))
+    }
+}
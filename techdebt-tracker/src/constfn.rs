@@ -0,0 +1,71 @@
+// --- `const fn`: compile-time evaluated logic, totalled apart from runtime code ---
+use crate::FileMetrics;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct ConstFunction {
+    pub file: String,
+    pub function: String,
+    pub line: usize,
+    pub complexity: usize,
+    pub loc: usize,
+}
+
+#[derive(Serialize, Default)]
+pub struct Bucket {
+    pub functions: usize,
+    pub loc: usize,
+    pub total_complexity: usize,
+    pub average_complexity: f64,
+    pub max_complexity: usize,
+}
+
+#[derive(Serialize, Default)]
+pub struct CompileTime {
+    pub const_fn: Bucket,
+    pub runtime: Bucket,
+    pub complexity_share: f64, // % of all function complexity in `const fn`
+    pub most_complex: Vec<ConstFunction>,
+}
+
+fn add(bucket: &mut Bucket, complexity: usize, loc: usize) {
+    bucket.functions += 1;
+    bucket.loc = bucket.loc.saturating_add(loc);
+    bucket.total_complexity = bucket.total_complexity.saturating_add(complexity);
+    bucket.max_complexity = bucket.max_complexity.max(complexity);
+}
+
+// `None` when no function in scope is `const`.
+pub fn summarize(files: &[FileMetrics], top: usize) -> Option<CompileTime> {
+    let mut summary = CompileTime::default();
+    for f in files.iter().flat_map(|file| &file.functions) {
+        if f.is_const {
+            add(&mut summary.const_fn, f.complexity, f.loc);
+            summary.most_complex.push(ConstFunction {
+                file: f.file.clone(),
+                function: f.function.clone(),
+                line: f.line,
+                complexity: f.complexity,
+                loc: f.loc,
+            });
+        } else {
+            add(&mut summary.runtime, f.complexity, f.loc);
+        }
+    }
+    if summary.const_fn.functions == 0 {
+        return None;
+    }
+    for b in [&mut summary.const_fn, &mut summary.runtime] {
+        b.average_complexity = b.total_complexity as f64 / b.functions.max(1) as f64;
+    }
+    let total = summary.const_fn.total_complexity + summary.runtime.total_complexity;
+    summary.complexity_share =
+        summary.const_fn.total_complexity as f64 * 100.0 / total.max(1) as f64;
+    summary.most_complex.sort_by(|a, b| {
+        b.complexity
+            .cmp(&a.complexity)
+            .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+    });
+    summary.most_complex.truncate(top);
+    Some(summary)
+}
@@ -0,0 +1,162 @@
+// --- Test coverage (grcov covdir or llvm-cov export JSON) per function ---
+use crate::{artifact, FileMetrics, FunctionMetric};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DANGEROUS_COMPLEXITY: usize = 15;
+pub const DANGEROUS_COVERAGE: f64 = 30.0;
+
+// Covered flag of every instrumented line, per source file as the tool named it.
+pub type LineCoverage = BTreeMap<PathBuf, BTreeMap<usize, bool>>;
+
+pub fn load(path: &str) -> Result<LineCoverage, String> {
+    let text = artifact::read_to_string(path)?;
+    let json: Value =
+        serde_json::from_str(&text).map_err(|e| format!("invalid coverage {}: {}", path, e))?;
+    let mut out = LineCoverage::new();
+    if json["data"].is_array() {
+        llvm_cov(&json, &mut out);
+    } else if json["children"].is_object() || json["coverage"].is_array() {
+        covdir(&json, Path::new(""), &mut out);
+    } else {
+        return Err(format!(
+            "invalid coverage {}: expected grcov covdir or llvm-cov export JSON",
+            path
+        ));
+    }
+    Ok(out)
+}
+
+// grcov `-t covdir`: a directory tree whose leaves hold one count per line,
+// -1 for lines without code.
+fn covdir(node: &Value, dir: &Path, out: &mut LineCoverage) {
+    if let Some(children) = node["children"].as_object() {
+        for (name, child) in children {
+            covdir(child, &dir.join(name), out);
+        }
+    } else if let Some(counts) = node["coverage"].as_array() {
+        let lines = counts
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| c.as_i64().filter(|&c| c >= 0).map(|c| (i + 1, c > 0)))
+            .collect();
+        out.insert(dir.to_path_buf(), lines);
+    }
+}
+
+// `llvm-cov export`: segments are [line, col, count, has_count, is_region_entry, ..].
+// A line counts if a region starts on it or one from an earlier line spans it,
+// the same rule `llvm-cov show` uses.
+fn llvm_cov(json: &Value, out: &mut LineCoverage) {
+    let files = json["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|d| d["files"].as_array().into_iter().flatten());
+    for file in files {
+        let Some(name) = file["filename"].as_str() else {
+            continue;
+        };
+        let segments: Vec<(usize, u64, bool, bool)> = file["segments"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|s| {
+                Some((
+                    s[0].as_u64()? as usize,
+                    s[2].as_u64()?,
+                    s[3].as_bool()?,
+                    s[4].as_bool()?,
+                ))
+            })
+            .collect();
+        let last_line = segments.last().map_or(0, |s| s.0);
+        let mut lines = BTreeMap::new();
+        let mut wrapped: Option<(u64, bool)> = None;
+        let mut i = 0;
+        for line in 1..=last_line {
+            let mut count = wrapped.filter(|w| w.1).map(|w| w.0);
+            while i < segments.len() && segments[i].0 == line {
+                let (_, c, has_count, entry) = segments[i];
+                if has_count && entry {
+                    count = Some(count.map_or(c, |n| n.max(c)));
+                }
+                wrapped = Some((c, has_count));
+                i += 1;
+            }
+            if let Some(c) = count {
+                lines.insert(line, c > 0);
+            }
+        }
+        out.insert(PathBuf::from(name), lines);
+    }
+}
+
+// Coverage paths may be absolute (llvm-cov) or relative to the source root
+// (grcov), so a scanned file matches an entry its path ends with.
+fn lines_for<'a>(coverage: &'a LineCoverage, file: &str) -> Option<&'a BTreeMap<usize, bool>> {
+    let path = fs::canonicalize(file).unwrap_or_else(|_| PathBuf::from(file));
+    coverage
+        .iter()
+        .filter(|(name, _)| !name.as_os_str().is_empty() && path.ends_with(name))
+        .max_by_key(|(name, _)| name.components().count())
+        .map(|(_, lines)| lines)
+}
+
+// Percentage of instrumented lines in the function that ran; `None` when the
+// file is absent from the report or the function has no instrumented lines.
+pub fn attach<'a>(
+    functions: impl Iterator<Item = &'a mut FunctionMetric>,
+    coverage: &LineCoverage,
+) {
+    for f in functions {
+        let Some(lines) = lines_for(coverage, &f.file) else {
+            continue;
+        };
+        let range: Vec<bool> = lines
+            .range(f.line..=f.end_line)
+            .map(|(_, &covered)| covered)
+            .collect();
+        if !range.is_empty() {
+            let covered = range.iter().filter(|&&c| c).count();
+            f.coverage = Some(covered as f64 * 100.0 / range.len() as f64);
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct DangerousFunction {
+    pub file: String,
+    pub function: String,
+    pub line: usize,
+    pub complexity: usize,
+    pub coverage: f64,
+}
+
+// Complex and barely tested: complexity > 15 and coverage < 30%, most complex first.
+pub fn dangerous(files: &[FileMetrics]) -> Vec<DangerousFunction> {
+    let mut rows: Vec<DangerousFunction> = files
+        .iter()
+        .flat_map(|file| &file.functions)
+        .filter(|f| f.complexity > DANGEROUS_COMPLEXITY)
+        .filter_map(|f| {
+            let coverage = f.coverage.filter(|&c| c < DANGEROUS_COVERAGE)?;
+            Some(DangerousFunction {
+                file: f.file.clone(),
+                function: f.function.clone(),
+                line: f.line,
+                complexity: f.complexity,
+                coverage,
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.complexity
+            .cmp(&a.complexity)
+            .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+    });
+    rows
+}
@@ -0,0 +1,137 @@
+// --- Crate / module / target-kind attribution for source files ---
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone)]
+pub struct CrateInfo {
+    pub name: String,
+    pub root: PathBuf,
+    pub proc_macro: bool,
+}
+
+// Where a file sits in its crate, used as labels and grouping keys.
+pub struct Location {
+    pub crate_name: String,
+    pub module: String,
+    pub target_kind: &'static str,
+    pub proc_macro: bool,
+    pub target_root: bool, // a crate root file, e.g. `src/lib.rs` or `tests/smoke.rs`
+}
+
+// Caches the nearest `Cargo.toml` [package] lookup per directory.
+#[derive(Default)]
+pub struct CrateResolver {
+    cache: HashMap<PathBuf, Option<CrateInfo>>,
+}
+
+impl CrateResolver {
+    fn crate_for_dir(&mut self, dir: &Path) -> Option<CrateInfo> {
+        if let Some(hit) = self.cache.get(dir) {
+            return hit.clone();
+        }
+        let manifest = dir.join("Cargo.toml");
+        let found = read_manifest(&manifest)
+            .map(|(name, proc_macro)| CrateInfo {
+                name,
+                root: dir.to_path_buf(),
+                proc_macro,
+            })
+            .or_else(|| dir.parent().and_then(|p| self.crate_for_dir(p)));
+        self.cache.insert(dir.to_path_buf(), found.clone());
+        found
+    }
+
+    // Files outside any crate get a module path relative to the scan root.
+    pub fn locate(&mut self, file: &Path, scan_root: &Path) -> Location {
+        let abs = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        let info = abs.parent().and_then(|dir| self.crate_for_dir(dir));
+        match info {
+            None => Location {
+                crate_name: String::new(),
+                module: module_path("", file.strip_prefix(scan_root).unwrap_or(file)),
+                target_kind: "lib",
+                proc_macro: false,
+                target_root: false,
+            },
+            Some(info) => {
+                let rel = abs.strip_prefix(&info.root).unwrap_or(&abs);
+                Location {
+                    module: module_path(&info.name.replace('-', "_"), rel),
+                    target_kind: target_kind(rel, &info.root),
+                    target_root: is_target_root(rel),
+                    crate_name: info.name,
+                    proc_macro: info.proc_macro,
+                }
+            }
+        }
+    }
+}
+
+// Package name and whether `[lib] proc-macro = true`.
+fn read_manifest(manifest: &Path) -> Option<(String, bool)> {
+    let text = fs::read_to_string(manifest).ok()?;
+    let value: toml::Value = toml::from_str(&text).ok()?;
+    let name = value.get("package")?.get("name")?.as_str()?.to_string();
+    let proc_macro = value
+        .get("lib")
+        .and_then(|lib| lib.get("proc-macro"))
+        .and_then(toml::Value::as_bool)
+        .unwrap_or(false);
+    Some((name, proc_macro))
+}
+
+// `src/api/mod.rs` -> `my_crate::api`, `tests/smoke.rs` -> `my_crate::smoke`.
+fn module_path(crate_ident: &str, rel: &Path) -> String {
+    let mut parts: Vec<String> = rel
+        .with_extension("")
+        .components()
+        .filter_map(|c| c.as_os_str().to_str().map(str::to_string))
+        .collect();
+    if matches!(
+        parts.first().map(String::as_str),
+        Some("src" | "tests" | "examples" | "benches")
+    ) {
+        parts.remove(0);
+    }
+    if parts.first().map(String::as_str) == Some("bin") {
+        parts.remove(0);
+    }
+    if matches!(
+        parts.last().map(String::as_str),
+        Some("mod" | "lib" | "main")
+    ) {
+        parts.pop();
+    }
+    if !crate_ident.is_empty() {
+        parts.insert(0, crate_ident.to_string());
+    }
+    parts.join("::")
+}
+
+fn target_kind(rel: &Path, crate_root: &Path) -> &'static str {
+    let first = rel.components().next().and_then(|c| c.as_os_str().to_str());
+    match first {
+        Some("tests") => "test",
+        Some("examples") => "example",
+        Some("benches") => "bench",
+        Some("build.rs") => "build",
+        Some("src") if rel.starts_with("src/bin") || rel == Path::new("src/main.rs") => "bin",
+        Some("src") if !crate_root.join("src/lib.rs").is_file() => "bin",
+        _ => "lib",
+    }
+}
+
+// Files cargo compiles as a crate root rather than reaching through `mod`.
+fn is_target_root(rel: &Path) -> bool {
+    let parts: Vec<&str> = rel
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .collect();
+    match parts.as_slice() {
+        ["build.rs"] | ["src", "lib.rs"] | ["src", "main.rs"] => true,
+        ["src", "bin", file] | ["tests" | "examples" | "benches", file] => file.ends_with(".rs"),
+        ["src", "bin", _, "main.rs"] | ["examples" | "benches", _, "main.rs"] => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,195 @@
+// --- `daemon`: a warm analyzer serving JSON-RPC over a unix socket ---
+// One JSON-RPC 2.0 message per line, any number per connection; clients
+// are served one at a time. Methods:
+//   analyze-file  {"path", "text"?}  one file's metrics and findings
+//   analyze-paths {"paths"}          full analysis, kept for get-report
+//   get-report    {}                 the last analyze-paths report
+//   shutdown      {}                 stop after answering
+// Full analyses always go through the cache, so a repeated scan only
+// re-parses files that changed since the previous call.
+use crate::config::Suppression;
+use crate::{
+    analyze_source, cache, canonicalize_report, collect_findings, logging, relative_path, rules,
+    run_analysis, AnalysisOptions,
+};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+const PARSE_ERROR: i64 = -32700;
+const ANALYSIS_FAILED: i64 = -32000;
+
+struct Daemon {
+    opts: AnalysisOptions,
+    path_root: String,
+    suppressions: Vec<Suppression>,
+    last_report: Option<Value>,
+}
+
+type RpcResult = Result<Value, (i64, String)>;
+
+impl Daemon {
+    fn analyze_file(&self, params: &Value) -> RpcResult {
+        let Some(path) = params["path"].as_str() else {
+            return Err((INVALID_PARAMS, "analyze-file needs a \"path\"".to_string()));
+        };
+        // Editors may send unsaved text; otherwise read the file.
+        let text = match params["text"].as_str() {
+            Some(text) => text.to_string(),
+            None => fs::read_to_string(path)
+                .map_err(|e| (ANALYSIS_FAILED, format!("cannot read {}: {}", path, e)))?,
+        };
+        let file = relative_path(path, &self.path_root);
+        let (_, detail) = analyze_source(&text, &file, self.opts.complexity_model);
+        let findings: Vec<_> =
+            collect_findings(std::slice::from_ref(&detail), &self.opts.thresholds)
+                .into_iter()
+                .filter(|f| !self.suppressions.iter().any(|s| rules::matches(s, f)))
+                .collect();
+        Ok(json!({
+            "file": detail,
+            "parse_error": detail.parse_error,
+            "findings": findings,
+        }))
+    }
+
+    fn analyze_paths(&mut self, params: &Value) -> RpcResult {
+        let paths: Vec<String> = params["paths"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.as_str().map(str::to_string))
+            .collect();
+        if paths.is_empty() {
+            return Err((INVALID_PARAMS, "analyze-paths needs \"paths\"".to_string()));
+        }
+        self.opts.roots = paths;
+        let mut report = run_analysis(&self.opts).map_err(|e| (ANALYSIS_FAILED, e))?;
+        canonicalize_report(&mut report, &self.path_root);
+        let today = logging::today();
+        rules::apply(&mut report, &self.suppressions, None, Vec::new(), &today);
+        let summary = json!({
+            "files": report.files.len(),
+            "functions": report.metrics.functions,
+            "findings": report.findings.len(),
+            "gate_failures": report.gate_failures.len(),
+        });
+        self.last_report = Some(serde_json::to_value(&report).unwrap());
+        Ok(summary)
+    }
+
+    fn get_report(&self) -> RpcResult {
+        self.last_report.clone().ok_or_else(|| {
+            (
+                ANALYSIS_FAILED,
+                "no report yet; call analyze-paths first".to_string(),
+            )
+        })
+    }
+}
+
+fn reply(id: Value, result: RpcResult) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    }
+}
+
+// Answers requests on one connection; true once `shutdown` was received.
+fn handle(daemon: &mut Daemon, stream: UnixStream) -> io::Result<bool> {
+    let mut out = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(m) => m,
+            Err(e) => {
+                let error = reply(Value::Null, Err((PARSE_ERROR, e.to_string())));
+                writeln!(out, "{}", error)?;
+                continue;
+            }
+        };
+        let params = &message["params"];
+        let method = message["method"].as_str().unwrap_or_default();
+        let result = match method {
+            "analyze-file" => daemon.analyze_file(params),
+            "analyze-paths" => daemon.analyze_paths(params),
+            "get-report" => daemon.get_report(),
+            "shutdown" => Ok(Value::Null),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method '{}'", method))),
+        };
+        // Notifications (no id) get no answer.
+        if let Some(id) = message.get("id").cloned() {
+            writeln!(out, "{}", reply(id, result))?;
+        }
+        if method == "shutdown" {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Serves until a client sends `shutdown`. A stale socket file from a
+// daemon that died is replaced; a live one is an error.
+pub fn serve(
+    socket: &str,
+    mut opts: AnalysisOptions,
+    path_root: &str,
+    suppressions: Vec<Suppression>,
+) -> Result<(), String> {
+    let path = Path::new(socket);
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(format!("a daemon is already listening on {}", socket));
+        }
+        fs::remove_file(path).map_err(|e| format!("cannot remove {}: {}", socket, e))?;
+    }
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+    }
+    let listener =
+        UnixListener::bind(path).map_err(|e| format!("cannot bind {}: {}", socket, e))?;
+    // A gate failure must not take the daemon down with it.
+    opts.fail_fast = false;
+    opts.cache
+        .get_or_insert_with(|| cache::DEFAULT_CACHE_FILE.to_string());
+    let mut daemon = Daemon {
+        opts,
+        path_root: path_root.to_string(),
+        suppressions,
+        last_report: None,
+    };
+    logging::info(
+        &format!("codehealth daemon listening on {}", socket),
+        &[("socket", json!(socket))],
+    );
+
+    let mut result = Ok(());
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                result = Err(format!("daemon: {}", e));
+                break;
+            }
+        };
+        match handle(&mut daemon, stream) {
+            Ok(true) => break,
+            Ok(false) => {}
+            // A client hanging up mid-request only ends its connection.
+            Err(e) => logging::warn(&format!("daemon: client error: {}", e), &[]),
+        }
+    }
+    let _ = fs::remove_file(path);
+    result
+}
@@ -0,0 +1,340 @@
+// --- Derived metrics: config-defined expressions over per-function metrics ---
+// e.g. `debt_score = "0.5*cc + 0.3*loc/10 + 0.2*nesting"`. Expressions are
+// parsed once when the config loads, so typos fail before any analysis.
+use crate::config::DerivedMetric;
+use crate::{FileMetrics, FunctionMetric, GateFailure};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub const VARIABLES: [&str; 18] = [
+    "cc",
+    "cognitive",
+    "loc",
+    "nloc",
+    "nesting",
+    "exit_points",
+    "params",
+    "tokens",
+    "operands",
+    "chain",
+    "expr_nodes",
+    "unwraps",
+    "try_ops",
+    "lints",
+    "coverage",
+    "lifetimes",
+    "quote_tokens",
+    "recursive",
+];
+
+pub fn variable(f: &FunctionMetric, name: &str) -> f64 {
+    match name {
+        "cc" => f.complexity as f64,
+        "cognitive" => f.cognitive_complexity as f64,
+        "loc" => f.loc as f64,
+        "nloc" => f.nloc as f64,
+        "nesting" => f.nesting as f64,
+        "exit_points" => f.exit_points as f64,
+        "params" => f.params as f64,
+        "tokens" => f.tokens as f64,
+        "operands" => f.max_condition_operands as f64,
+        "chain" => f.max_call_chain as f64,
+        "expr_nodes" => f.max_expression_nodes as f64,
+        "unwraps" => f.unwraps as f64,
+        "try_ops" => f.try_ops as f64,
+        "lints" => f.lints as f64,
+        "coverage" => f.coverage.unwrap_or(0.0),
+        "lifetimes" => f.lifetime_annotations as f64,
+        "quote_tokens" => f.quote_tokens as f64,
+        "recursive" => f64::from(u8::from(f.is_recursive)),
+        _ => 0.0,
+    }
+}
+
+pub enum Expr {
+    Number(f64),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+}
+
+// Recursive descent over `+ - * /`, unary minus and parentheses.
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.rest = self.rest.trim_start();
+        self.rest.chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.rest = &self.rest[c.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut left = self.product()?;
+        while let Some(op) = self.peek().filter(|c| matches!(c, '+' | '-')) {
+            self.eat(op);
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(op) = self.peek().filter(|c| matches!(c, '*' | '/')) {
+            self.eat(op);
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat('-') {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        if self.eat('(') {
+            let inner = self.sum()?;
+            return if self.eat(')') {
+                Ok(inner)
+            } else {
+                Err("missing ')'".to_string())
+            };
+        }
+        let Some(first) = self.peek() else {
+            return Err("unexpected end of expression".to_string());
+        };
+        let len = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(self.rest.len());
+        let (token, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        if first.is_ascii_digit() || first == '.' {
+            token
+                .parse()
+                .map(Expr::Number)
+                .map_err(|_| format!("invalid number '{}'", token))
+        } else if VARIABLES.contains(&token) {
+            Ok(Expr::Variable(token.to_string()))
+        } else if token.is_empty() {
+            Err(format!("unexpected '{}'", first))
+        } else {
+            Err(format!(
+                "unknown metric '{}' (known: {})",
+                token,
+                VARIABLES.join(", ")
+            ))
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Expr, String> {
+    let mut parser = Parser { rest: text };
+    let expr = parser.sum()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(c) => Err(format!("unexpected '{}'", c)),
+    }
+}
+
+// Division by zero, and any result that overflows to inf or NaN, yields 0
+// so one degenerate function cannot poison a report.
+pub fn evaluate(expr: &Expr, f: &FunctionMetric) -> f64 {
+    let value = eval(expr, f);
+    if value.is_finite() {
+        value
+    } else {
+        0.0
+    }
+}
+
+fn eval(expr: &Expr, f: &FunctionMetric) -> f64 {
+    match expr {
+        Expr::Number(n) => *n,
+        Expr::Variable(name) => variable(f, name),
+        Expr::Negate(e) => -eval(e, f),
+        Expr::Binary(op, a, b) => {
+            let (a, b) = (eval(a, f), eval(b, f));
+            match op {
+                '+' => a + b,
+                '-' => a - b,
+                '*' => a * b,
+                _ if b == 0.0 => 0.0,
+                _ => a / b,
+            }
+        }
+    }
+}
+
+// Sets `derived` on every function; definitions are already validated.
+pub fn attach<'a>(
+    functions: impl Iterator<Item = &'a mut FunctionMetric>,
+    definitions: &BTreeMap<String, DerivedMetric>,
+) {
+    let parsed: Vec<(&String, Expr)> = definitions
+        .iter()
+        .filter_map(|(name, d)| Some((name, parse(&d.expression).ok()?)))
+        .collect();
+    if parsed.is_empty() {
+        return;
+    }
+    for f in functions {
+        f.derived = parsed
+            .iter()
+            .map(|(name, expr)| (name.to_string(), evaluate(expr, f)))
+            .collect();
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct DerivedValue {
+    pub file: String,
+    pub function: String,
+    pub line: usize,
+    pub value: f64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DerivedSummary {
+    pub name: String,
+    pub expression: String,
+    pub max: Option<f64>,
+    pub functions_over: usize,
+    pub top: Vec<DerivedValue>, // highest values first
+}
+
+pub fn summarize(
+    files: &[FileMetrics],
+    definitions: &BTreeMap<String, DerivedMetric>,
+    limit: usize,
+) -> Vec<DerivedSummary> {
+    definitions
+        .iter()
+        .map(|(name, d)| {
+            let mut values: Vec<DerivedValue> = files
+                .iter()
+                .flat_map(|file| &file.functions)
+                .filter_map(|f| {
+                    Some(DerivedValue {
+                        file: f.file.clone(),
+                        function: f.function.clone(),
+                        line: f.line,
+                        value: *f.derived.get(name)?,
+                    })
+                })
+                .collect();
+            values.sort_by(|a, b| {
+                b.value
+                    .total_cmp(&a.value)
+                    .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+            });
+            let functions_over = d
+                .max
+                .map_or(0, |max| values.iter().filter(|v| v.value > max).count());
+            values.truncate(limit);
+            DerivedSummary {
+                name: name.clone(),
+                expression: d.expression.clone(),
+                max: d.max,
+                functions_over,
+                top: values,
+            }
+        })
+        .collect()
+}
+
+// The worst function per metric with a `max`, mirroring `max-complexity`.
+pub fn gates(
+    files: &[FileMetrics],
+    definitions: &BTreeMap<String, DerivedMetric>,
+) -> Vec<GateFailure> {
+    let mut failures = Vec::new();
+    for (name, max) in definitions.iter().filter_map(|(n, d)| Some((n, d.max?))) {
+        let worst = files
+            .iter()
+            .flat_map(|file| &file.functions)
+            .filter_map(|f| Some((f, *f.derived.get(name)?)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((f, value)) = worst.filter(|&(_, v)| v > max) {
+            failures.push(GateFailure {
+                gate: format!("derived:{}", name),
+                value: value.ceil() as usize,
+                threshold: max.floor() as usize,
+                location: format!("{}::{}", f.file, f.function),
+                message: format!(
+                    "Derived metric {} ({:.2}) exceeds threshold ({}).",
+                    name, value, max
+                ),
+            });
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTREMES: [usize; 4] = [0, 1, usize::MAX / 2, usize::MAX];
+
+    fn function(value: usize) -> FunctionMetric {
+        FunctionMetric {
+            complexity: value,
+            loc: value,
+            nesting: value,
+            tokens: value,
+            ..FunctionMetric::default()
+        }
+    }
+
+    fn value(text: &str, f: &FunctionMetric) -> f64 {
+        evaluate(&parse(text).unwrap(), f)
+    }
+
+    #[test]
+    fn division_by_zero_yields_zero() {
+        let f = function(7);
+        assert_eq!(value("cc / 0", &f), 0.0);
+        assert_eq!(value("cc / (loc - loc)", &f), 0.0);
+        assert_eq!(value("params / params", &f), 0.0);
+    }
+
+    #[test]
+    fn never_infinite_or_nan_at_extremes() {
+        let power = vec!["loc"; 20].join(" * ");
+        let expressions = [
+            "cc / 0".to_string(),
+            "0.5*cc + 0.3*loc/10 + 0.2*nesting".to_string(),
+            "-(cc * loc * tokens) / (nesting - cc)".to_string(),
+            power.clone(),
+            format!("-{}", power),
+            format!("{} - {}", power, power),
+            format!("({}) / ({})", power, power),
+            format!("1 / ({})", power),
+        ];
+        for n in EXTREMES {
+            let f = function(n);
+            for text in &expressions {
+                let v = value(text, &f);
+                assert!(v.is_finite(), "{} with metrics at {} gave {}", text, n, v);
+            }
+        }
+    }
+
+    #[test]
+    fn nan_input_does_not_leak() {
+        let f = FunctionMetric {
+            coverage: Some(f64::NAN),
+            ..FunctionMetric::default()
+        };
+        assert_eq!(value("coverage * 2", &f), 0.0);
+    }
+}
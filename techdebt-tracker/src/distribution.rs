@@ -0,0 +1,63 @@
+// --- Percentiles and histograms of per-function metrics ---
+use crate::FileMetrics;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Default, Clone)]
+pub struct MetricDistribution {
+    pub count: usize,
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    pub p50: usize,
+    pub p75: usize,
+    pub p90: usize,
+    pub p95: usize,
+    pub p99: usize,
+    pub histogram: BTreeMap<usize, usize>, // value -> number of functions
+}
+
+#[derive(Serialize, Default, Clone)]
+pub struct FunctionDistributions {
+    pub complexity: MetricDistribution,
+    pub loc: MetricDistribution,
+    pub nesting: MetricDistribution,
+}
+
+// Nearest-rank percentile over sorted values.
+fn percentile(sorted: &[usize], p: f64) -> usize {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+pub fn describe(mut values: Vec<usize>) -> MetricDistribution {
+    values.sort_unstable();
+    let mut histogram = BTreeMap::new();
+    for &v in &values {
+        *histogram.entry(v).or_insert(0) += 1;
+    }
+    MetricDistribution {
+        count: values.len(),
+        min: values.first().copied().unwrap_or(0),
+        max: values.last().copied().unwrap_or(0),
+        mean: values.iter().sum::<usize>() as f64 / values.len().max(1) as f64,
+        p50: percentile(&values, 50.0),
+        p75: percentile(&values, 75.0),
+        p90: percentile(&values, 90.0),
+        p95: percentile(&values, 95.0),
+        p99: percentile(&values, 99.0),
+        histogram,
+    }
+}
+
+pub fn summarize(files: &[FileMetrics]) -> FunctionDistributions {
+    let functions = || files.iter().flat_map(|f| &f.functions);
+    FunctionDistributions {
+        complexity: describe(functions().map(|f| f.complexity).collect()),
+        loc: describe(functions().map(|f| f.loc).collect()),
+        nesting: describe(functions().map(|f| f.nesting).collect()),
+    }
+}
@@ -0,0 +1,166 @@
+// --- Rust embedded in documentation: mdBook/markdown fences and doctests ---
+use crate::analyze_source;
+use crate::config::ComplexityModel;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Clone)]
+pub struct DocCodeFile {
+    pub file: String,
+    pub kind: String, // "markdown" or "doc-comment"
+    pub blocks: usize,
+    pub loc: usize,
+    pub complexity: usize,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct DocCode {
+    pub blocks: usize,
+    pub unparsed: usize, // blocks that are not valid Rust even as a fn body
+    pub loc: usize,
+    pub complexity: usize,
+    pub files: Vec<DocCodeFile>,
+}
+
+// Doc comments default to Rust; rustdoc attributes don't change that.
+fn is_rust_fence(info: &str, default_rust: bool) -> bool {
+    let lang = info.split([',', ' ']).next().unwrap_or("").trim();
+    match lang {
+        "" => default_rust,
+        "rust" => true,
+        "ignore" | "no_run" | "should_panic" | "compile_fail" | "test_harness" => default_rust,
+        _ => default_rust && lang.starts_with("edition"),
+    }
+}
+
+// Bodies of Rust fences in `lines`.
+fn fenced_blocks<'a>(lines: impl Iterator<Item = &'a str>, default_rust: bool) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(&str, bool, Vec<&str>)> = None;
+    for line in lines {
+        let trimmed = line.trim_start();
+        match &mut open {
+            Some((fence, rust, body)) => {
+                if trimmed.starts_with(*fence) {
+                    if *rust {
+                        blocks.push(body.join("\n"));
+                    }
+                    open = None;
+                } else {
+                    body.push(line);
+                }
+            }
+            None => {
+                let fence = ["```", "~~~"].into_iter().find(|f| trimmed.starts_with(f));
+                if let Some(fence) = fence {
+                    let rust = is_rust_fence(&trimmed[fence.len()..], default_rust);
+                    open = Some((fence, rust, Vec::new()));
+                }
+            }
+        }
+    }
+    blocks
+}
+
+// `///` and `//!` text with the marker and one following space removed.
+fn doc_comment_text(source: &str) -> Vec<&str> {
+    source
+        .lines()
+        .filter_map(|l| {
+            let l = l.trim_start();
+            let doc = l.strip_prefix("///").or_else(|| l.strip_prefix("//!"))?;
+            Some(doc.strip_prefix(' ').unwrap_or(doc))
+        })
+        .collect()
+}
+
+// rustdoc hides `# ` lines from readers but still compiles them.
+fn unhide(block: &str) -> String {
+    block
+        .lines()
+        .map(|l| match l.trim_start() {
+            "#" => "",
+            t => t.strip_prefix("# ").unwrap_or(l),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Snippets without items are statements; doctests wrap those in `fn main`.
+fn analyze_block(block: &str, file: &str, model: ComplexityModel) -> Option<(usize, usize)> {
+    let code = unhide(block);
+    let source = if syn::parse_file(&code).is_ok() {
+        code
+    } else if syn::parse_str::<syn::Block>(&format!("{{{}}}", code)).is_ok() {
+        format!("fn main() {{\n{}\n}}", code)
+    } else {
+        return None;
+    };
+    let (metrics, _) = analyze_source(&source, file, model);
+    Some((block.lines().count(), metrics.cyclomatic_complexity))
+}
+
+fn summarize_file(
+    doc: &mut DocCode,
+    file: &str,
+    kind: &str,
+    blocks: Vec<String>,
+    model: ComplexityModel,
+) {
+    if blocks.is_empty() {
+        return;
+    }
+    let mut row = DocCodeFile {
+        file: file.to_string(),
+        kind: kind.to_string(),
+        blocks: blocks.len(),
+        loc: 0,
+        complexity: 0,
+    };
+    for block in &blocks {
+        match analyze_block(block, file, model) {
+            Some((loc, complexity)) => {
+                row.loc += loc;
+                row.complexity += complexity;
+            }
+            None => doc.unparsed += 1,
+        }
+    }
+    doc.blocks += row.blocks;
+    doc.loc += row.loc;
+    doc.complexity += row.complexity;
+    doc.files.push(row);
+}
+
+// Markdown only counts fences labelled `rust`, since unlabelled ones are
+// usually shell or output; in doc comments unlabelled fences are Rust.
+pub fn collect(rust_files: &[&str], markdown: &[PathBuf], model: ComplexityModel) -> DocCode {
+    let mut doc = DocCode::default();
+    for path in markdown {
+        let Ok(text) = fs::read_to_string(path) else {
+            continue;
+        };
+        let file = path.to_string_lossy();
+        summarize_file(
+            &mut doc,
+            &file,
+            "markdown",
+            fenced_blocks(text.lines(), false),
+            model,
+        );
+    }
+    for file in rust_files {
+        let Ok(text) = fs::read_to_string(Path::new(file)) else {
+            continue;
+        };
+        let blocks = fenced_blocks(doc_comment_text(&text).into_iter(), true);
+        summarize_file(&mut doc, file, "doc-comment", blocks, model);
+    }
+    doc.files.sort_by(|a, b| {
+        b.complexity
+            .cmp(&a.complexity)
+            .then_with(|| a.file.cmp(&b.file))
+    });
+    doc
+}
@@ -0,0 +1,116 @@
+// --- Error-handling style metrics (Result plumbing vs panics) ---
+use crate::FileMetrics;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use syn::visit::{self, Visit};
+
+// Per-function counts of `?` versus `.unwrap()` / `.expect()`.
+#[derive(Default)]
+pub struct FunctionErrorVisitor {
+    pub try_ops: usize,
+    pub unwraps: usize,
+}
+
+impl<'ast> Visit<'ast> for FunctionErrorVisitor {
+    fn visit_expr_try(&mut self, node: &'ast syn::ExprTry) {
+        self.try_ops += 1;
+        visit::visit_expr_try(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        if node.method == "unwrap" || node.method == "expect" {
+            self.unwraps += 1;
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+}
+
+// File-wide usage of type-erased errors.
+#[derive(Default)]
+pub struct ErrorTypeVisitor {
+    pub box_dyn_error: usize,
+    pub anyhow_uses: usize,
+}
+
+impl<'ast> Visit<'ast> for ErrorTypeVisitor {
+    fn visit_type_path(&mut self, node: &'ast syn::TypePath) {
+        let last = node.path.segments.last();
+        if let Some(seg) = last.filter(|s| s.ident == "Box") {
+            if let syn::PathArguments::AngleBracketed(args) = &seg.arguments {
+                let dyn_error = args.args.iter().any(|arg| {
+                    matches!(arg, syn::GenericArgument::Type(syn::Type::TraitObject(obj))
+                        if obj.bounds.iter().any(|b| matches!(b,
+                            syn::TypeParamBound::Trait(t)
+                                if t.path.segments.last().is_some_and(|s| s.ident == "Error"))))
+                });
+                if dyn_error {
+                    self.box_dyn_error += 1;
+                }
+            }
+        }
+        visit::visit_type_path(self, node);
+    }
+
+    fn visit_path(&mut self, node: &'ast syn::Path) {
+        if node.segments.first().is_some_and(|s| s.ident == "anyhow") {
+            self.anyhow_uses += 1;
+        }
+        visit::visit_path(self, node);
+    }
+
+    fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+        if matches!(&node.tree, syn::UseTree::Path(p) if p.ident == "anyhow") {
+            self.anyhow_uses += 1;
+        }
+        visit::visit_item_use(self, node);
+    }
+}
+
+pub fn returns_result(sig: &syn::Signature) -> bool {
+    match &sig.output {
+        syn::ReturnType::Type(_, ty) => matches!(&**ty, syn::Type::Path(p)
+            if p.path.segments.last().is_some_and(|s| s.ident == "Result")),
+        syn::ReturnType::Default => false,
+    }
+}
+
+#[derive(Serialize, Default, Clone)]
+pub struct CrateErrorHandling {
+    pub crate_name: String,
+    pub functions: usize,
+    pub functions_returning_result: usize,
+    pub try_ops: usize,
+    pub unwraps: usize,
+    pub try_to_unwrap_ratio: f64, // `?` per unwrap/expect; higher is healthier
+    pub box_dyn_error: usize,
+    pub anyhow_uses: usize,
+}
+
+pub fn summarize(files: &[FileMetrics]) -> Vec<CrateErrorHandling> {
+    let mut by_crate: BTreeMap<&str, CrateErrorHandling> = BTreeMap::new();
+    for file in files {
+        let entry =
+            by_crate
+                .entry(file.crate_name.as_str())
+                .or_insert_with(|| CrateErrorHandling {
+                    crate_name: file.crate_name.clone(),
+                    ..Default::default()
+                });
+        entry.box_dyn_error += file.box_dyn_error;
+        entry.anyhow_uses += file.anyhow_uses;
+        for f in &file.functions {
+            entry.functions += 1;
+            entry.functions_returning_result += usize::from(f.returns_result);
+            entry.try_ops += f.try_ops;
+            entry.unwraps += f.unwraps;
+        }
+    }
+
+    by_crate
+        .into_values()
+        .map(|mut c| {
+            c.try_to_unwrap_ratio = c.try_ops as f64 / c.unwraps.max(1) as f64;
+            c
+        })
+        .collect()
+}
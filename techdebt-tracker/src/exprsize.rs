@@ -0,0 +1,34 @@
+// --- Expression size: syntax nodes in the largest single statement-level expression ---
+// Statement-count LOC sees a 300-line `match` written as the function's tail
+// expression as one line; counting its expression nodes does not.
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+#[derive(Default)]
+struct NodeCounter {
+    nodes: usize,
+}
+
+impl<'ast> Visit<'ast> for NodeCounter {
+    fn visit_expr(&mut self, node: &'ast syn::Expr) {
+        self.nodes += 1;
+        syn::visit::visit_expr(self, node);
+    }
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
+// (nodes, line) of the largest expression among the body's statements;
+// a `let` counts its initializer (and `else` block).
+pub fn largest(block: &syn::Block) -> (usize, usize) {
+    block
+        .stmts
+        .iter()
+        .map(|stmt| {
+            let mut counter = NodeCounter::default();
+            counter.visit_stmt(stmt);
+            (counter.nodes, stmt.span().start().line)
+        })
+        .max_by_key(|&(nodes, line)| (nodes, std::cmp::Reverse(line)))
+        .unwrap_or_default()
+}
@@ -0,0 +1,80 @@
+// --- `--fail-fast`: stop scanning once a gate can no longer pass ---
+// Only gates that more files can never un-fail are checked: a file over a
+// size or complexity limit, or more over-complex functions than allowed.
+// Percentages can still recover, so those wait for the full scan.
+use crate::config::Thresholds;
+use crate::{vendor, FileMetrics, GateFailure, DEFAULT_OUTLIER_COMPLEXITY};
+
+pub struct Tripwire<'a> {
+    thresholds: &'a Thresholds,
+    vendored: Option<vendor::VendorDirs<'a>>, // None with --include-vendored
+    over: usize,                              // functions over the outlier level so far
+}
+
+impl<'a> Tripwire<'a> {
+    pub fn new(thresholds: &'a Thresholds, roots: &'a [String], include_vendored: bool) -> Self {
+        Tripwire {
+            thresholds,
+            vendored: (!include_vendored).then(|| vendor::VendorDirs::new(roots)),
+            over: 0,
+        }
+    }
+
+    // The first gate this file makes certain to fail, if any.
+    pub fn check(&mut self, file: &FileMetrics) -> Option<GateFailure> {
+        if self
+            .vendored
+            .as_ref()
+            .is_some_and(|v| v.contains(&file.file))
+        {
+            return None;
+        }
+        let t = self.thresholds;
+        for (gate, value, limit) in [
+            ("max-file-loc", file.loc, t.max_file_loc),
+            ("max-items-per-module", file.items, t.max_items_per_module),
+        ] {
+            if let Some(limit) = limit.filter(|&l| value > l) {
+                return Some(GateFailure {
+                    gate: gate.to_string(),
+                    value,
+                    threshold: limit,
+                    location: file.file.clone(),
+                    message: format!("File exceeds {} ({} > {}).", gate, value, limit),
+                });
+            }
+        }
+        if let Some(th) = t
+            .max_complexity
+            .filter(|&th| file.total_complexity as u32 > th)
+        {
+            return Some(GateFailure {
+                gate: "max-complexity".to_string(),
+                value: file.total_complexity,
+                threshold: th as usize,
+                location: file.file.clone(),
+                message: format!(
+                    "Maximum cyclomatic complexity ({}) exceeds threshold ({}).",
+                    file.total_complexity, th
+                ),
+            });
+        }
+        let level = t.outlier_complexity.unwrap_or(DEFAULT_OUTLIER_COMPLEXITY);
+        self.over += file
+            .functions
+            .iter()
+            .filter(|f| f.complexity > level)
+            .count();
+        let limit = t.max_functions_over_complexity?;
+        (self.over > limit).then(|| GateFailure {
+            gate: "max-functions-over-complexity".to_string(),
+            value: self.over,
+            threshold: limit,
+            location: file.file.clone(),
+            message: format!(
+                "{} functions exceed complexity {} (allowed: {}).",
+                self.over, level, limit
+            ),
+        })
+    }
+}
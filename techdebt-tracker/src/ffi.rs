@@ -0,0 +1,156 @@
+// --- FFI boundary: extern blocks, exported symbols and unsafe foreign calls per crate ---
+use crate::FileMetrics;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use syn::visit::Visit;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct FileFfi {
+    pub extern_blocks: usize,
+    pub exported_functions: usize,      // `#[no_mangle]`
+    pub foreign_functions: Vec<String>, // declared in extern blocks
+    // Callee name -> calls inside `unsafe` blocks and fns; only the ones to
+    // foreign functions count, which is known once the crate is complete.
+    pub unsafe_calls: BTreeMap<String, usize>,
+    pub sys_calls: usize, // `libc::` / `*_sys::` calls inside `unsafe`
+}
+
+#[derive(Default)]
+struct FfiVisitor {
+    ffi: FileFfi,
+    unsafe_depth: usize,
+}
+
+fn no_mangle(attrs: &[syn::Attribute]) -> bool {
+    // `#[no_mangle]` and the 2024-edition `#[unsafe(no_mangle)]`.
+    attrs.iter().any(|a| {
+        a.path().is_ident("no_mangle")
+            || (a.path().is_ident("unsafe")
+                && a.parse_args::<syn::Path>()
+                    .is_ok_and(|p| p.is_ident("no_mangle")))
+    })
+}
+
+fn is_sys_path(path: &syn::Path) -> bool {
+    path.segments.len() > 1
+        && path.segments.first().is_some_and(|s| {
+            let name = s.ident.to_string();
+            name == "libc" || name.ends_with("_sys")
+        })
+}
+
+impl<'ast> Visit<'ast> for FfiVisitor {
+    fn visit_item_foreign_mod(&mut self, node: &'ast syn::ItemForeignMod) {
+        self.ffi.extern_blocks += 1;
+        for item in &node.items {
+            if let syn::ForeignItem::Fn(f) = item {
+                self.ffi.foreign_functions.push(f.sig.ident.to_string());
+            }
+        }
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.ffi.exported_functions += usize::from(no_mangle(&node.attrs));
+        let unsafe_fn = node.sig.unsafety.is_some();
+        self.unsafe_depth += usize::from(unsafe_fn);
+        syn::visit::visit_item_fn(self, node);
+        self.unsafe_depth -= usize::from(unsafe_fn);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.ffi.exported_functions += usize::from(no_mangle(&node.attrs));
+        let unsafe_fn = node.sig.unsafety.is_some();
+        self.unsafe_depth += usize::from(unsafe_fn);
+        syn::visit::visit_impl_item_fn(self, node);
+        self.unsafe_depth -= usize::from(unsafe_fn);
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &'ast syn::ExprUnsafe) {
+        self.unsafe_depth += 1;
+        syn::visit::visit_expr_unsafe(self, node);
+        self.unsafe_depth -= 1;
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if self.unsafe_depth > 0 {
+            if let syn::Expr::Path(p) = &*node.func {
+                if is_sys_path(&p.path) {
+                    self.ffi.sys_calls += 1;
+                } else if let Some(last) = p.path.segments.last() {
+                    *self
+                        .ffi
+                        .unsafe_calls
+                        .entry(last.ident.to_string())
+                        .or_insert(0) += 1;
+                }
+            }
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+}
+
+pub fn scan(syntax: &syn::File) -> FileFfi {
+    let mut visitor = FfiVisitor::default();
+    visitor.visit_file(syntax);
+    visitor.ffi
+}
+
+#[derive(Serialize, Default)]
+pub struct CrateFfi {
+    pub crate_name: String,
+    pub extern_blocks: usize,
+    pub foreign_functions: usize,
+    pub exported_functions: usize,
+    pub unsafe_call_sites: usize, // declared foreign functions plus libc/*_sys calls
+    pub files: Vec<String>,       // files with any FFI
+}
+
+// Crates with any FFI, most unsafe call sites first.
+pub fn summarize(files: &[FileMetrics]) -> Vec<CrateFfi> {
+    let mut by_crate: BTreeMap<&str, Vec<&FileMetrics>> = BTreeMap::new();
+    for file in files {
+        by_crate
+            .entry(file.crate_name.as_str())
+            .or_default()
+            .push(file);
+    }
+
+    let mut rows: Vec<CrateFfi> = by_crate
+        .into_iter()
+        .filter_map(|(name, files)| {
+            let foreign: BTreeSet<&str> = files
+                .iter()
+                .flat_map(|f| &f.ffi.foreign_functions)
+                .map(String::as_str)
+                .collect();
+            let mut row = CrateFfi {
+                crate_name: name.to_string(),
+                foreign_functions: foreign.len(),
+                ..Default::default()
+            };
+            for file in files {
+                let ffi = &file.ffi;
+                let calls = ffi.sys_calls
+                    + ffi
+                        .unsafe_calls
+                        .iter()
+                        .filter(|(callee, _)| foreign.contains(callee.as_str()))
+                        .map(|(_, n)| n)
+                        .sum::<usize>();
+                row.extern_blocks += ffi.extern_blocks;
+                row.exported_functions += ffi.exported_functions;
+                row.unsafe_call_sites += calls;
+                if ffi.extern_blocks + ffi.exported_functions + calls > 0 {
+                    row.files.push(file.file.clone());
+                }
+            }
+            (!row.files.is_empty()).then_some(row)
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.unsafe_call_sites
+            .cmp(&a.unsafe_call_sites)
+            .then_with(|| a.crate_name.cmp(&b.crate_name))
+    });
+    rows
+}
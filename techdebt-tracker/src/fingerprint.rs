@@ -0,0 +1,45 @@
+// --- Stable finding identities that survive line-number movement ---
+use crate::Finding;
+use std::collections::HashMap;
+
+// Rule, normalized path and function, plus the finding's structural context
+// (e.g. the shape of the offending condition) for rules that can fire
+// several times in one function, so fixing one leaves the others' ids
+// alone. Findings without a context, or sharing one, fall back to an
+// occurrence index; findings must already be in report order (file, line,
+// rule) so the index follows source order.
+pub fn identities(findings: &[Finding]) -> Vec<String> {
+    let mut seen: HashMap<(&str, &str, &str, &str), usize> = HashMap::new();
+    findings
+        .iter()
+        .map(|f| {
+            let n = seen
+                .entry((&f.rule, &f.file, &f.function, &f.context))
+                .or_default();
+            *n += 1;
+            match (f.context.as_str(), *n) {
+                ("", n) => format!("{}|{}|{}|{}", f.rule, f.file, f.function, n),
+                (context, 1) => format!("{}|{}|{}|{}", f.rule, f.file, f.function, context),
+                (context, n) => format!("{}|{}|{}|{}|{}", f.rule, f.file, f.function, context, n),
+            }
+        })
+        .collect()
+}
+
+// FNV-1a, spelled out because std's hashers may change between releases.
+pub fn fnv1a(text: &str) -> u64 {
+    fnv1a_bytes(text.as_bytes())
+}
+
+pub fn fnv1a_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+pub fn assign(findings: &mut [Finding]) {
+    let ids = identities(findings);
+    for (finding, id) in findings.iter_mut().zip(ids) {
+        finding.fingerprint = format!("{:016x}", fnv1a(&id));
+    }
+}
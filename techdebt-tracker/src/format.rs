@@ -0,0 +1,109 @@
+// --- Formatting drift: lines rustfmt would change, as a hygiene metric ---
+use crate::{diagnostic, Diagnostic, FileMetrics};
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(Serialize, Clone)]
+pub struct FileDrift {
+    pub file: String,
+    pub lines: usize,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct FormatDrift {
+    pub files_checked: usize,
+    pub files_deviating: usize,
+    pub lines_deviating: usize,
+    pub files: Vec<FileDrift>, // most drift first
+}
+
+// A hunk that rewrites 1 line as 3 deviates by 3; one that drops 2 lines by 2.
+fn deviating_lines(diff: &str) -> usize {
+    let mut total = 0;
+    let (mut removed, mut added) = (0, 0);
+    for line in diff.lines() {
+        if line.starts_with("Diff in ") {
+            total += removed.max(added);
+            (removed, added) = (0, 0);
+        } else if line.starts_with('-') {
+            removed += 1;
+        } else if line.starts_with('+') {
+            added += 1;
+        }
+    }
+    total + removed.max(added)
+}
+
+// Sources go through stdin so rustfmt neither rewrites them nor follows
+// `mod` declarations into other files. It runs from the file's directory to
+// pick up the nearest rustfmt.toml.
+fn check_file(path: &str) -> Result<usize, String> {
+    let source = std::fs::read(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let dir = Path::new(path)
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut child = Command::new("rustfmt")
+        .args(["--check", "--color", "never", "--edition", "2021"])
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run rustfmt: {}", e))?;
+    // rustfmt reads all of stdin before writing, so this cannot deadlock.
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(&source)
+            .map_err(|e| format!("failed to run rustfmt: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("failed to run rustfmt: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr
+            .lines()
+            .next()
+            .unwrap_or("rustfmt failed")
+            .to_string());
+    }
+    Ok(deviating_lines(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// A missing rustfmt fails the whole check; a file it cannot handle is
+// reported as a diagnostic and left out of the totals.
+pub fn check(
+    files: &[FileMetrics],
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<FormatDrift, String> {
+    Command::new("rustfmt")
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("failed to run rustfmt: {}", e))?;
+    let mut drift = FormatDrift::default();
+    for file in files {
+        let lines = match check_file(&file.file) {
+            Ok(lines) => lines,
+            Err(err) => {
+                diagnostics.push(diagnostic(&file.file, "rustfmt", err));
+                continue;
+            }
+        };
+        drift.files_checked += 1;
+        if lines > 0 {
+            drift.files_deviating += 1;
+            drift.lines_deviating += lines;
+            drift.files.push(FileDrift {
+                file: file.file.clone(),
+                lines,
+            });
+        }
+    }
+    drift
+        .files
+        .sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.file.cmp(&b.file)));
+    Ok(drift)
+}
@@ -0,0 +1,335 @@
+// --- Git integration: changed-line mapping and remote checkouts ---
+use crate::logging;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// `git hash-object -t tree /dev/null`, used when nothing predates a date.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+pub fn run_git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Directory to run git in for a scan path that may be a file.
+pub fn work_dir(scan_path: &str) -> PathBuf {
+    let path = Path::new(scan_path);
+    if path.is_file() {
+        path.parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf()
+    } else {
+        path.to_path_buf()
+    }
+}
+
+// A `YYYY-MM-DD` date resolves to the last commit before it; anything else
+// is handed to git as a revision.
+pub fn resolve_since(dir: &Path, since: &str) -> Result<String, String> {
+    if logging::is_date(since) {
+        let before = format!("--before={}", since);
+        let rev = run_git(dir, &["rev-list", "-1", &before, "HEAD"])?;
+        let rev = rev.trim();
+        return Ok(if rev.is_empty() {
+            EMPTY_TREE.to_string()
+        } else {
+            rev.to_string()
+        });
+    }
+    let rev = run_git(
+        dir,
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", since)],
+    )?;
+    Ok(rev.trim().to_string())
+}
+
+// Lines added or modified in the working tree relative to a base revision,
+// across every repository the scan roots live in.
+pub struct ChangedLines {
+    repos: Vec<RepoChanges>,
+}
+
+struct RepoChanges {
+    root: PathBuf,
+    base: String,
+    ranges: HashMap<PathBuf, Vec<(usize, usize)>>,
+    untracked: HashSet<PathBuf>,
+}
+
+impl ChangedLines {
+    // One diff per distinct git toplevel, so no root's files drop out of
+    // new-code gates. `since` is resolved in each repository separately.
+    pub fn since(scan_paths: &[String], since: &str) -> Result<ChangedLines, String> {
+        let mut repos: Vec<RepoChanges> = Vec::new();
+        for scan_path in scan_paths {
+            let dir = work_dir(scan_path);
+            let root = PathBuf::from(run_git(&dir, &["rev-parse", "--show-toplevel"])?.trim());
+            if !repos.iter().any(|r| r.root == root) {
+                repos.push(RepoChanges::since(root, since)?);
+            }
+        }
+        Ok(ChangedLines { repos })
+    }
+
+    // True when any line in `start..=end` of `file` changed since the base.
+    pub fn touches(&self, file: &str, start: usize, end: usize) -> bool {
+        let Ok(path) = Path::new(file).canonicalize() else {
+            return false;
+        };
+        self.repos.iter().any(|repo| {
+            repo.untracked.contains(&path)
+                || repo
+                    .ranges
+                    .get(&path)
+                    .is_some_and(|r| r.iter().any(|&(a, b)| a <= end && b >= start))
+        })
+    }
+
+    // `file` as of the base revision; None when it is new since then.
+    pub fn base_content(&self, file: &str) -> Option<String> {
+        let path = Path::new(file).canonicalize().ok()?;
+        let repo = self.repos.iter().find(|r| path.starts_with(&r.root))?;
+        if repo.untracked.contains(&path) {
+            return None;
+        }
+        let rel = path.strip_prefix(&repo.root).ok()?;
+        file_at(&repo.root, &repo.base, &rel.to_string_lossy()).ok()
+    }
+}
+
+impl RepoChanges {
+    fn since(root: PathBuf, since: &str) -> Result<RepoChanges, String> {
+        let base = resolve_since(&root, since)?;
+        let diff = run_git(&root, &["diff", "-U0", "--no-color", "--no-renames", &base])?;
+        // Keys are canonicalized so they compare equal to scanned paths;
+        // files deleted since the base drop out here.
+        let ranges = parse_unified_diff(&diff)
+            .into_iter()
+            .filter_map(|(file, r)| Some((root.join(file).canonicalize().ok()?, r)))
+            .collect();
+
+        let others = run_git(&root, &["ls-files", "--others", "--exclude-standard"])?;
+        let untracked = others
+            .lines()
+            .filter_map(|l| root.join(l).canonicalize().ok())
+            .collect();
+
+        Ok(RepoChanges {
+            root,
+            base,
+            ranges,
+            untracked,
+        })
+    }
+}
+
+// Changed `(start, end)` line ranges per repo-relative path.
+pub type LineRanges = HashMap<String, Vec<(usize, usize)>>;
+
+// Staged (index) state of the repository containing `scan_path`: its
+// toplevel, and the changed line ranges of each added or modified file,
+// keyed by repo-relative path.
+pub fn staged_changes(scan_path: &str) -> Result<(PathBuf, LineRanges), String> {
+    let root =
+        PathBuf::from(run_git(&work_dir(scan_path), &["rev-parse", "--show-toplevel"])?.trim());
+    let diff = run_git(
+        &root,
+        &[
+            "diff",
+            "--cached",
+            "-U0",
+            "--no-color",
+            "--no-renames",
+            "--diff-filter=AM",
+        ],
+    )?;
+    Ok((root, parse_unified_diff(&diff)))
+}
+
+// File content as staged, which may differ from the working tree.
+pub fn staged_content(root: &Path, file: &str) -> Result<String, String> {
+    run_git(root, &["show", &format!(":{}", file)])
+}
+
+// Blob ids of the tracked files under `scan_path`'s repository whose working
+// tree matches the index, keyed by canonical path. Modified, unmerged and
+// untracked files are left out, since their content has no blob id yet.
+pub fn blob_ids(scan_path: &str) -> Result<HashMap<PathBuf, String>, String> {
+    let root =
+        PathBuf::from(run_git(&work_dir(scan_path), &["rev-parse", "--show-toplevel"])?.trim());
+    let root = root.canonicalize().unwrap_or(root);
+    let dirty: HashSet<String> = run_git(&root, &["diff", "--name-only", "-z"])?
+        .split('\0')
+        .map(str::to_string)
+        .collect();
+    let mut ids = HashMap::new();
+    // `<mode> <oid> <stage>\t<path>`, NUL-terminated.
+    for entry in run_git(&root, &["ls-files", "-s", "-z"])?.split('\0') {
+        let Some((meta, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        let mut meta = meta.split(' ');
+        let (Some(_), Some(oid), Some("0")) = (meta.next(), meta.next(), meta.next()) else {
+            continue;
+        };
+        if !dirty.contains(path) {
+            ids.insert(root.join(path), oid.to_string());
+        }
+    }
+    Ok(ids)
+}
+
+// A commit touching a file, with the file's path at that commit.
+pub struct FileCommit {
+    pub hash: String,
+    pub date: String, // YYYY-MM-DD
+    pub subject: String,
+    pub path: String,
+}
+
+// Commits that touched repo-relative `file`, newest first, following renames.
+pub fn file_log(root: &Path, file: &str) -> Result<Vec<FileCommit>, String> {
+    let log = run_git(
+        root,
+        &[
+            "log",
+            "--follow",
+            "--name-only",
+            "--date=short",
+            "--format=%x00%H%x09%ad%x09%s",
+            "--",
+            file,
+        ],
+    )?;
+    Ok(log
+        .split('\0')
+        .filter_map(|entry| {
+            let mut lines = entry.lines().filter(|l| !l.is_empty());
+            let mut header = lines.next()?.splitn(3, '\t');
+            Some(FileCommit {
+                hash: header.next()?.to_string(),
+                date: header.next()?.to_string(),
+                subject: header.next().unwrap_or_default().to_string(),
+                path: lines.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+// File content as of a revision.
+pub fn file_at(root: &Path, rev: &str, file: &str) -> Result<String, String> {
+    run_git(root, &["show", &format!("{}:{}", rev, file)])
+}
+
+// Collect `+start,count` hunk ranges per new-side file path.
+pub fn parse_unified_diff(diff: &str) -> LineRanges {
+    let mut result = LineRanges::new();
+    let mut current: Option<String> = None;
+
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            current = path.strip_prefix("b/").map(str::to_string);
+        } else if let (Some(file), Some(hunk)) = (&current, line.strip_prefix("@@ ")) {
+            let Some(new) = hunk.split_whitespace().find(|t| t.starts_with('+')) else {
+                continue;
+            };
+            let mut parts = new[1..].splitn(2, ',');
+            let start: usize = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+            let count: usize = parts.next().and_then(|n| n.parse().ok()).unwrap_or(1);
+            // Pure deletions (count 0) still modify the line they follow.
+            let end = start + count.max(1) - 1;
+            result
+                .entry(file.clone())
+                .or_default()
+                .push((start.max(1), end.max(1)));
+        }
+    }
+
+    result
+}
+
+// --- Remote repositories: shallow clone into a private temp dir ---
+static CHECKOUTS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+static CHECKOUT_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+pub fn is_remote_url(path: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "file://", "git@"]
+        .iter()
+        .any(|scheme| path.starts_with(scheme))
+}
+
+// A fresh directory under the system temp dir, removed by
+// `cleanup_checkouts`. The name includes pid, time and a counter so
+// concurrent runs never collide.
+pub fn temp_workspace() -> Result<PathBuf, String> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let dir = std::env::temp_dir().join(format!(
+        "codehealth-{}-{}-{}",
+        std::process::id(),
+        nanos,
+        CHECKOUT_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir).map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+    CHECKOUTS.lock().unwrap().push(dir.clone());
+    Ok(dir)
+}
+
+// Clones `url[#ref]` at depth 1 and returns the checkout directory.
+pub fn clone_remote(spec: &str) -> Result<PathBuf, String> {
+    let (url, rev) = match spec.rsplit_once('#') {
+        Some((url, rev)) if !rev.is_empty() => (url, rev),
+        _ => (spec, "HEAD"),
+    };
+    // A leading `-` would reach git as an option (`--upload-pack=...`).
+    if url.starts_with('-') || rev.starts_with('-') {
+        return Err(format!("invalid remote spec: {}", spec));
+    }
+    let dir = temp_workspace()?;
+
+    // init + fetch works for branches, tags and (where allowed) commit ids alike.
+    run_git(&dir, &["init", "-q"])?;
+    run_git(
+        &dir,
+        &[
+            "-c",
+            "protocol.ext.allow=never",
+            "fetch",
+            "-q",
+            "--depth",
+            "1",
+            "--",
+            url,
+            rev,
+        ],
+    )?;
+    run_git(&dir, &["checkout", "-q", "FETCH_HEAD"])?;
+    Ok(dir)
+}
+
+// Removes every temp workspace made by this process; call before exiting.
+pub fn cleanup_checkouts() {
+    for dir in CHECKOUTS.lock().unwrap().drain(..) {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,166 @@
+// --- Letter grades (A–F) per function, file, crate and project ---
+// A function's score is the classic maintainability index on its own
+// numbers (natural logs, rescaled to 0-100 as Visual Studio does), with
+// tokens standing in for Halstead length. Files, crates and the project
+// take the LOC-weighted mean of their functions' scores, so a large
+// codebase is not marked down for its size alone the way the project-wide
+// index is.
+use crate::config::GradeBoundaries;
+use crate::{FileMetrics, FunctionMetric};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub const LETTERS: [&str; 5] = ["A", "B", "C", "D", "F"];
+
+fn ln_or_zero(x: f64) -> f64 {
+    if x > 0.0 {
+        x.ln()
+    } else {
+        0.0
+    }
+}
+
+pub fn score(f: &FunctionMetric) -> f64 {
+    let tokens = f.tokens as f64;
+    let volume = tokens * ln_or_zero(tokens) / std::f64::consts::LN_2;
+    let index = 171.0
+        - 5.2 * ln_or_zero(volume)
+        - 0.23 * f.complexity as f64
+        - 16.2 * ln_or_zero(f.loc as f64);
+    (index * 100.0 / 171.0).clamp(0.0, 100.0)
+}
+
+pub fn letter(score: f64, b: &GradeBoundaries) -> &'static str {
+    match score {
+        s if s >= b.a => "A",
+        s if s >= b.b => "B",
+        s if s >= b.c => "C",
+        s if s >= b.d => "D",
+        _ => "F",
+    }
+}
+
+// LOC-weighted; `None` without functions to grade.
+fn weighted<'a>(functions: impl Iterator<Item = &'a FunctionMetric>) -> Option<f64> {
+    let (sum, weight) = functions.fold((0.0, 0usize), |(s, w), f| {
+        let loc = f.loc.max(1);
+        (s + f.maintainability * loc as f64, w + loc)
+    });
+    (weight > 0).then(|| sum / weight as f64)
+}
+
+// Sets function and file grades; files without functions stay ungraded.
+pub fn attach(files: &mut [FileMetrics], boundaries: &GradeBoundaries) {
+    for file in files {
+        for f in &mut file.functions {
+            f.maintainability = score(f);
+            f.grade = letter(f.maintainability, boundaries).to_string();
+        }
+        if let Some(s) = weighted(file.functions.iter()) {
+            file.maintainability = s;
+            file.grade = letter(s, boundaries).to_string();
+        }
+    }
+}
+
+// Copies of functions kept elsewhere, e.g. the top-functions list.
+pub fn attach_functions<'a>(
+    functions: impl Iterator<Item = &'a mut FunctionMetric>,
+    boundaries: &GradeBoundaries,
+) {
+    for f in functions {
+        f.maintainability = score(f);
+        f.grade = letter(f.maintainability, boundaries).to_string();
+    }
+}
+
+#[derive(Serialize)]
+pub struct CrateGrade {
+    pub crate_name: String,
+    pub grade: String,
+    pub score: f64,
+    pub files: usize,
+    pub functions: usize,
+}
+
+#[derive(Serialize, Default)]
+pub struct Grades {
+    pub grade: String, // project; empty without any function
+    pub score: f64,
+    pub boundaries: GradeBoundaries,
+    pub crates: Vec<CrateGrade>,            // worst first
+    pub functions: BTreeMap<String, usize>, // per letter, all letters present
+    pub files: BTreeMap<String, usize>,     // graded files per letter
+}
+
+pub fn summarize(files: &[FileMetrics], boundaries: &GradeBoundaries) -> Grades {
+    let mut grades = Grades {
+        boundaries: *boundaries,
+        functions: LETTERS.iter().map(|l| (l.to_string(), 0)).collect(),
+        files: LETTERS.iter().map(|l| (l.to_string(), 0)).collect(),
+        ..Grades::default()
+    };
+    let all = files.iter().flat_map(|file| &file.functions);
+    if let Some(s) = weighted(all.clone()) {
+        grades.score = s;
+        grades.grade = letter(s, boundaries).to_string();
+    }
+    for f in all {
+        *grades.functions.entry(f.grade.clone()).or_default() += 1;
+    }
+    for file in files.iter().filter(|f| !f.grade.is_empty()) {
+        *grades.files.entry(file.grade.clone()).or_default() += 1;
+    }
+    let mut crates: BTreeMap<&str, Vec<&FileMetrics>> = BTreeMap::new();
+    for file in files {
+        crates.entry(&file.crate_name).or_default().push(file);
+    }
+    for (name, members) in crates {
+        let functions = members.iter().flat_map(|file| &file.functions);
+        let Some(s) = weighted(functions.clone()) else {
+            continue;
+        };
+        grades.crates.push(CrateGrade {
+            crate_name: name.to_string(),
+            grade: letter(s, boundaries).to_string(),
+            score: s,
+            files: members.len(),
+            functions: functions.count(),
+        });
+    }
+    grades.crates.sort_by(|a, b| {
+        a.score
+            .total_cmp(&b.score)
+            .then_with(|| a.crate_name.cmp(&b.crate_name))
+    });
+    grades
+}
+
+// A shields.io-style flat badge, "codehealth | B".
+pub fn badge_svg(grade: &str) -> String {
+    let (value, color) = match grade {
+        "A" => ("A", "#4c1"),
+        "B" => ("B", "#97ca00"),
+        "C" => ("C", "#dfb317"),
+        "D" => ("D", "#fe7d37"),
+        "F" => ("F", "#e05d44"),
+        _ => ("n/a", "#9f9f9f"),
+    };
+    let label_width = 76;
+    let value_width = if value.len() > 1 { 34 } else { 22 };
+    let width = label_width + value_width;
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="codehealth: {value}">
+<title>codehealth: {value}</title>
+<rect width="{label_width}" height="20" fill="#555"/>
+<rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/>
+<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+<text x="{label_x}" y="14">codehealth</text>
+<text x="{value_x}" y="14">{value}</text>
+</g>
+</svg>
+"##,
+        label_x = label_width / 2,
+        value_x = label_width + value_width / 2,
+    )
+}
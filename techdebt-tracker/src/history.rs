@@ -0,0 +1,267 @@
+// --- History store: one JSON snapshot per line, optionally tagged ---
+use crate::term::Align::{Left, Right};
+use crate::term::Table;
+use crate::{api, Report};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+pub const DEFAULT_HISTORY_FILE: &str = ".codehealth/history.jsonl";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FileSnapshot {
+    pub file: String,
+    pub loc: usize,
+    pub complexity: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FunctionSnapshot {
+    pub file: String,
+    pub function: String,
+    pub complexity: usize,
+    pub loc: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<String>, // carried forward, see age.rs
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Snapshot {
+    pub timestamp: String,
+    pub tag: Option<String>,
+    pub commit: Option<String>,
+    pub loc: usize,
+    pub functions: usize,
+    pub cyclomatic_complexity: usize,
+    pub maintainability_index: f64,
+    pub findings: usize,
+    pub files: Vec<FileSnapshot>,
+    pub function_metrics: Vec<FunctionSnapshot>,
+    #[serde(default)]
+    pub public_api: Vec<api::CrateApi>,
+    #[serde(default)]
+    pub api_signatures: Vec<api::ApiSignature>,
+}
+
+impl Snapshot {
+    pub fn from_report(report: &Report, tag: Option<String>, commit: Option<String>) -> Snapshot {
+        Snapshot {
+            timestamp: crate::logging::rfc3339_now(),
+            tag,
+            commit,
+            loc: report.metrics.loc,
+            functions: report.metrics.functions,
+            cyclomatic_complexity: report.metrics.cyclomatic_complexity,
+            // NaN would serialize as null and make the line unreadable.
+            maintainability_index: if report.maintainability_index.is_finite() {
+                report.maintainability_index
+            } else {
+                0.0
+            },
+            findings: report.findings.len(),
+            files: report
+                .files
+                .iter()
+                .map(|f| FileSnapshot {
+                    file: f.file.clone(),
+                    loc: f.loc,
+                    complexity: f.total_complexity,
+                })
+                .collect(),
+            function_metrics: report
+                .files
+                .iter()
+                .flat_map(|f| &f.functions)
+                .map(|f| FunctionSnapshot {
+                    file: f.file.clone(),
+                    function: f.function.clone(),
+                    complexity: f.complexity,
+                    loc: f.loc,
+                    first_seen: f.first_seen.clone(),
+                })
+                .collect(),
+            public_api: report.public_api.clone(),
+            api_signatures: api::public_signatures(&report.files),
+        }
+    }
+}
+
+pub fn append(path: &str, snapshot: &Snapshot) -> Result<(), String> {
+    if let Some(dir) = Path::new(path)
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+    {
+        fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+    }
+    let line = serde_json::to_string(snapshot).map_err(|e| e.to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("cannot open {}: {}", path, e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("cannot write {}: {}", path, e))
+}
+
+pub fn load(path: &str) -> Result<Vec<Snapshot>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            serde_json::from_str(l)
+                .map_err(|e| format!("{}:{}: invalid snapshot: {}", path, i + 1, e))
+        })
+        .collect()
+}
+
+// The most recent snapshot carrying `tag` (re-tagging supersedes).
+pub fn find_tagged<'a>(snapshots: &'a [Snapshot], tag: &str) -> Option<&'a Snapshot> {
+    snapshots
+        .iter()
+        .rev()
+        .find(|s| s.tag.as_deref() == Some(tag))
+}
+
+#[derive(Serialize)]
+pub struct Contribution {
+    pub name: String,
+    pub before: usize,
+    pub after: usize,
+    pub delta: i64,
+}
+
+#[derive(Serialize)]
+pub struct Comparison {
+    pub from: String,
+    pub to: String,
+    pub loc_delta: i64,
+    pub functions_delta: i64,
+    pub complexity_delta: i64,
+    pub maintainability_delta: f64,
+    pub public_api_delta: i64,
+    pub api_diff: api::ApiDiff,
+    pub top_functions: Vec<Contribution>,
+    pub top_files: Vec<Contribution>,
+}
+
+// Largest complexity growth first; removed items count as shrinking to 0.
+fn contributions(
+    before: BTreeMap<String, usize>,
+    after: BTreeMap<String, usize>,
+) -> Vec<Contribution> {
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+    let mut rows: Vec<Contribution> = names
+        .into_iter()
+        .map(|name| {
+            let b = before.get(name).copied().unwrap_or(0);
+            let a = after.get(name).copied().unwrap_or(0);
+            Contribution {
+                name: name.clone(),
+                before: b,
+                after: a,
+                delta: a as i64 - b as i64,
+            }
+        })
+        .filter(|c| c.delta != 0)
+        .collect();
+    rows.sort_by(|x, y| y.delta.cmp(&x.delta).then_with(|| x.name.cmp(&y.name)));
+    rows
+}
+
+pub fn compare(from: &Snapshot, to: &Snapshot, limit: usize) -> Comparison {
+    let functions = |s: &Snapshot| -> BTreeMap<String, usize> {
+        s.function_metrics
+            .iter()
+            .map(|f| (format!("{}::{}", f.file, f.function), f.complexity))
+            .collect()
+    };
+    let files = |s: &Snapshot| -> BTreeMap<String, usize> {
+        s.files
+            .iter()
+            .map(|f| (f.file.clone(), f.complexity))
+            .collect()
+    };
+    let label = |s: &Snapshot| s.tag.clone().unwrap_or_else(|| s.timestamp.clone());
+
+    let mut top_functions = contributions(functions(from), functions(to));
+    let mut top_files = contributions(files(from), files(to));
+    top_functions.truncate(limit);
+    top_files.truncate(limit);
+
+    Comparison {
+        from: label(from),
+        to: label(to),
+        loc_delta: to.loc as i64 - from.loc as i64,
+        functions_delta: to.functions as i64 - from.functions as i64,
+        complexity_delta: to.cyclomatic_complexity as i64 - from.cyclomatic_complexity as i64,
+        maintainability_delta: to.maintainability_index - from.maintainability_index,
+        public_api_delta: api::public_total(&to.public_api) as i64
+            - api::public_total(&from.public_api) as i64,
+        api_diff: api::diff(&from.api_signatures, &to.api_signatures),
+        top_functions,
+        top_files,
+    }
+}
+
+pub fn print_snapshots(snapshots: &[Snapshot]) {
+    let mut table = Table::new(&[Left, Left, Right, Right, Right]);
+    table.row(
+        ["Timestamp", "Tag", "LOC", "Functions", "Complexity"]
+            .map(str::to_string)
+            .to_vec(),
+    );
+    for s in snapshots {
+        table.row(vec![
+            s.timestamp.clone(),
+            s.tag.clone().unwrap_or_else(|| "-".to_string()),
+            s.loc.to_string(),
+            s.functions.to_string(),
+            s.cyclomatic_complexity.to_string(),
+        ]);
+    }
+    table.print();
+}
+
+pub fn print_comparison(c: &Comparison) {
+    println!("Metric changes {} → {}:", c.from, c.to);
+    println!("LOC: {:+}", c.loc_delta);
+    println!("Functions: {:+}", c.functions_delta);
+    println!("Cyclomatic Complexity: {:+}", c.complexity_delta);
+    println!("Maintainability Index: {:+.2}", c.maintainability_delta);
+    println!("Public API items: {:+}", c.public_api_delta);
+
+    if !c.api_diff.changes.is_empty() {
+        println!(
+            "\n🔌 Public API Changes (suggested semver bump: {}):",
+            c.api_diff.semver
+        );
+        for change in &c.api_diff.changes {
+            let marker = if change.breaking {
+                " ⚠️ breaking"
+            } else {
+                ""
+            };
+            println!(
+                "{} {} {}{}",
+                change.change, change.kind, change.name, marker
+            );
+            if change.change == "signature" {
+                println!("    - {}", change.before.as_deref().unwrap_or_default());
+                println!("    + {}", change.after.as_deref().unwrap_or_default());
+            }
+        }
+    }
+
+    println!("\n📈 Top Contributing Functions:");
+    for f in &c.top_functions {
+        println!("{}: {} → {} ({:+})", f.name, f.before, f.after, f.delta);
+    }
+    println!("\n📈 Top Contributing Files:");
+    for f in &c.top_files {
+        println!("{}: {} → {} ({:+})", f.name, f.before, f.after, f.delta);
+    }
+}
@@ -0,0 +1,89 @@
+// --- Whitespace hygiene: line endings, indentation style, trailing blanks ---
+use crate::FileMetrics;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Hygiene {
+    pub crlf_lines: usize,
+    pub lf_lines: usize,
+    pub tab_indented_lines: usize,
+    pub space_indented_lines: usize,
+    pub mixed_indent_lines: usize, // tabs and spaces in one indent
+    pub trailing_whitespace_lines: usize,
+}
+
+impl Hygiene {
+    pub fn mixes_indentation(&self) -> bool {
+        self.mixed_indent_lines > 0
+            || (self.tab_indented_lines > 0 && self.space_indented_lines > 0)
+    }
+
+    fn has_issues(&self) -> bool {
+        self.crlf_lines > 0 || self.trailing_whitespace_lines > 0 || self.mixes_indentation()
+    }
+}
+
+// Counts on the raw text, so `\r\n` is still visible.
+pub fn scan(content: &str) -> Hygiene {
+    let mut h = Hygiene::default();
+    for raw in content.split_inclusive('\n') {
+        let line = match raw.strip_suffix("\r\n") {
+            Some(line) => {
+                h.crlf_lines += 1;
+                line
+            }
+            None => {
+                h.lf_lines += usize::from(raw.ends_with('\n'));
+                raw.strip_suffix('\n').unwrap_or(raw)
+            }
+        };
+        if line.trim().is_empty() {
+            h.trailing_whitespace_lines += usize::from(!line.is_empty());
+            continue;
+        }
+        if line.ends_with([' ', '\t']) {
+            h.trailing_whitespace_lines += 1;
+        }
+        let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        match (indent.contains('\t'), indent.contains(' ')) {
+            (true, true) => h.mixed_indent_lines += 1,
+            (true, false) => h.tab_indented_lines += 1,
+            (false, true) => h.space_indented_lines += 1,
+            (false, false) => {}
+        }
+    }
+    h
+}
+
+#[derive(Serialize)]
+pub struct FileHygiene {
+    pub file: String,
+    #[serde(flatten)]
+    pub hygiene: Hygiene,
+}
+
+#[derive(Serialize, Default)]
+pub struct HygieneSummary {
+    pub files_with_crlf: usize,
+    pub files_with_mixed_line_endings: usize,
+    pub files_mixing_indentation: usize,
+    pub trailing_whitespace_lines: usize,
+    pub files: Vec<FileHygiene>, // only files with something to clean up
+}
+
+pub fn summarize(files: &[FileMetrics]) -> HygieneSummary {
+    let mut summary = HygieneSummary::default();
+    for f in files.iter().filter(|f| f.hygiene.has_issues()) {
+        let h = &f.hygiene;
+        summary.files_with_crlf += usize::from(h.crlf_lines > 0);
+        summary.files_with_mixed_line_endings += usize::from(h.crlf_lines > 0 && h.lf_lines > 0);
+        summary.files_mixing_indentation += usize::from(h.mixes_indentation());
+        summary.trailing_whitespace_lines += h.trailing_whitespace_lines;
+        summary.files.push(FileHygiene {
+            file: f.file.clone(),
+            hygiene: h.clone(),
+        });
+    }
+    summary.files.sort_by(|a, b| a.file.cmp(&b.file));
+    summary
+}
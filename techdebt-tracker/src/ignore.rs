@@ -0,0 +1,61 @@
+// --- `.codehealth-ignore`: accepted findings by fingerprint, with expiry ---
+// One entry per line: a finding fingerprint, optionally an expiry date, then
+// an optional reason. `#` starts a comment line.
+//
+//     # fingerprint     expires     reason
+//     3f2a9c0d1e4b5a67  2025-06-01  legacy parser, rewrite tracked in #123
+//     91c4e2aa07d3f518              generated code
+//
+// Entries are fingerprint suppressions, so they expire the same way: from
+// their date on the finding counts against gates again, see rules.rs.
+use crate::config::Suppression;
+use crate::logging;
+use std::fs;
+use std::path::Path;
+
+pub const DEFAULT_IGNORE_FILE: &str = ".codehealth-ignore";
+
+fn parse_line(line: &str) -> Result<Option<Suppression>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let (fingerprint, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    if fingerprint.len() != 16 || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not a finding fingerprint", fingerprint));
+    }
+    let rest = rest.trim_start();
+    let (expires, rest) = match rest.split_once(char::is_whitespace) {
+        Some((date, reason)) if logging::is_date(date) => (Some(date), reason),
+        None if logging::is_date(rest) => (Some(rest), ""),
+        _ => (None, rest),
+    };
+    let reason = rest.trim().trim_start_matches('#').trim();
+    Ok(Some(Suppression {
+        fingerprint: Some(fingerprint.to_lowercase()),
+        expires: expires.map(str::to_string),
+        reason: (!reason.is_empty()).then(|| reason.to_string()),
+        ..Suppression::default()
+    }))
+}
+
+// Entries as fingerprint suppressions, each tagged with its `file:line`.
+// An explicit `--ignore-file` must exist; the default file is optional.
+pub fn load(path: Option<&str>) -> Result<Vec<Suppression>, String> {
+    let path = match path {
+        Some(p) => p,
+        None if Path::new(DEFAULT_IGNORE_FILE).is_file() => DEFAULT_IGNORE_FILE,
+        None => return Ok(Vec::new()),
+    };
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let mut entries = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let origin = format!("{}:{}", path, n + 1);
+        let entry = parse_line(line).map_err(|e| format!("{}: {}", origin, e))?;
+        entries.extend(entry.map(|s| Suppression {
+            origin: Some(origin),
+            ..s
+        }));
+    }
+    Ok(entries)
+}
@@ -0,0 +1,163 @@
+// --- `include!`, `include_str!` and `include_bytes!`: resolved, sized, attributed ---
+// An `include!`d file is compiled as part of the including module, so its
+// functions are attributed there; one the walk never saw (e.g. a `.in`
+// file) is analyzed here instead of being missed. Only string-literal
+// paths resolve; `concat!(env!("OUT_DIR"), ...)` is build output.
+use crate::config::ComplexityModel;
+use crate::{analyze_file, merge_metrics, modtree, CodeMetrics, Diagnostic, FileMetrics};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+const MACROS: [&str; 3] = ["include", "include_str", "include_bytes"];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Include {
+    pub kind: String,   // the macro name
+    pub target: String, // the path as written
+    pub line: usize,
+    #[serde(default)]
+    pub path: String, // resolved against the including file, once found
+    #[serde(default)]
+    pub lines: usize,
+    #[serde(default)]
+    pub bytes: usize,
+}
+
+#[derive(Default)]
+struct IncludeVisitor {
+    includes: Vec<Include>,
+}
+
+impl<'ast> Visit<'ast> for IncludeVisitor {
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        let kind = node.path.segments.last().map(|s| s.ident.to_string());
+        if let Some(kind) = kind.filter(|k| MACROS.contains(&k.as_str())) {
+            if let Ok(target) = node.parse_body::<syn::LitStr>() {
+                self.includes.push(Include {
+                    kind,
+                    target: target.value(),
+                    line: node.span().start().line,
+                    path: String::new(),
+                    lines: 0,
+                    bytes: 0,
+                });
+            }
+        }
+        syn::visit::visit_macro(self, node);
+    }
+}
+
+pub fn scan(syntax: &syn::File) -> Vec<Include> {
+    let mut visitor = IncludeVisitor::default();
+    visitor.visit_file(syntax);
+    visitor.includes
+}
+
+// Resolves and sizes every include, attributes `include!`d files to the
+// including module (analyzing the ones not yet scanned) and returns the
+// paths of files that are only ever included, which are not orphans.
+pub fn resolve(
+    files: &mut Vec<FileMetrics>,
+    total: &mut CodeMetrics,
+    model: ComplexityModel,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<String> {
+    let mut index: HashMap<PathBuf, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (modtree::normalize(Path::new(&f.file)), i))
+        .collect();
+    let mut included = Vec::new();
+    let mut i = 0;
+    // Newly analyzed files are appended and scanned in turn, so nested
+    // includes resolve too.
+    while i < files.len() {
+        let dir = Path::new(&files[i].file)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_path_buf();
+        let mut includes = std::mem::take(&mut files[i].includes);
+        for inc in &mut includes {
+            let path = modtree::normalize(&dir.join(&inc.target));
+            let Ok(bytes) = fs::read(&path) else {
+                continue;
+            };
+            inc.path = path.to_string_lossy().into_owned();
+            inc.bytes = bytes.len();
+            inc.lines = if inc.kind == "include_bytes" {
+                0
+            } else {
+                String::from_utf8_lossy(&bytes).lines().count()
+            };
+            if inc.kind != "include" {
+                continue;
+            }
+            let child = match index.get(&path) {
+                Some(&child) => child,
+                None => match analyze_file(&path, model, None) {
+                    Ok((metrics, detail, warnings)) => {
+                        merge_metrics(total, &metrics);
+                        diagnostics.extend(warnings);
+                        files.push(detail);
+                        index.insert(path.clone(), files.len() - 1);
+                        files.len() - 1
+                    }
+                    Err(diagnostic) => {
+                        diagnostics.push(diagnostic);
+                        continue;
+                    }
+                },
+            };
+            if child == i || !files[child].included_by.is_empty() {
+                continue;
+            }
+            files[child].included_by = files[i].file.clone();
+            files[child].module = files[i].module.clone();
+            files[child].crate_name = files[i].crate_name.clone();
+            files[child].target_kind = files[i].target_kind.clone();
+            files[child].proc_macro = files[i].proc_macro;
+            included.push(files[child].file.clone());
+        }
+        files[i].includes = includes;
+        i += 1;
+    }
+    included
+}
+
+#[derive(Serialize)]
+pub struct IncludeSite {
+    pub file: String, // the including file
+    pub line: usize,
+    pub kind: String,
+    pub target: String,
+    pub path: String, // empty when the target does not exist
+    pub lines: usize,
+    pub bytes: usize,
+}
+
+// Every include, largest first.
+pub fn summarize(files: &[FileMetrics]) -> Vec<IncludeSite> {
+    let mut sites: Vec<IncludeSite> = files
+        .iter()
+        .flat_map(|f| f.includes.iter().map(move |inc| (f, inc)))
+        .map(|(f, inc)| IncludeSite {
+            file: f.file.clone(),
+            line: inc.line,
+            kind: inc.kind.clone(),
+            target: inc.target.clone(),
+            path: inc.path.clone(),
+            lines: inc.lines,
+            bytes: inc.bytes,
+        })
+        .collect();
+    sites.sort_by(|a, b| {
+        b.bytes
+            .cmp(&a.bytes)
+            .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+    });
+    sites
+}
@@ -0,0 +1,258 @@
+// --- `issues`: file the worst findings in GitHub Issues or Jira ---
+// Each issue body carries the finding's fingerprint, and reruns search for
+// it first, so an issue is never filed twice.
+use crate::{git, lexical_absolute, relative_path, Report};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::env;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+const MARKER: &str = "codehealth-fingerprint:";
+
+pub struct IssueDraft {
+    pub title: String,
+    pub body: String,
+    pub fingerprint: String,
+}
+
+pub enum Tracker {
+    // Through the `gh` CLI, so its login is reused; `None` means the repo
+    // of the current directory.
+    GitHub { repo: Option<String> },
+    // REST API v2; JIRA_URL, JIRA_USER and JIRA_TOKEN come from the environment.
+    Jira { project: String },
+}
+
+// `owner/repo` of the `origin` remote when it points at GitHub.
+fn github_slug(dir: &Path) -> Option<String> {
+    let url = git::run_git(dir, &["remote", "get-url", "origin"]).ok()?;
+    let rest = url
+        .trim()
+        .strip_prefix("git@github.com:")
+        .or_else(|| url.trim().split_once("github.com/").map(|(_, r)| r))?;
+    Some(
+        rest.trim_end_matches('/')
+            .trim_end_matches(".git")
+            .to_string(),
+    )
+}
+
+// Links point at the analyzed commit, so they stay valid as the file changes.
+pub struct SpanLinker {
+    base: Option<String>, // https://github.com/owner/repo/blob/<commit>
+    top: String,          // git toplevel, to re-root report paths
+    path_root: String,
+}
+
+impl SpanLinker {
+    pub fn new(scan_path: &str, path_root: &str, repo: Option<&str>) -> SpanLinker {
+        let dir = git::work_dir(scan_path);
+        let top = git::run_git(&dir, &["rev-parse", "--show-toplevel"])
+            .map(|t| t.trim().to_string())
+            .unwrap_or_else(|_| path_root.to_string());
+        let commit = git::run_git(&dir, &["rev-parse", "HEAD"]).map(|c| c.trim().to_string());
+        let slug = repo.map(str::to_string).or_else(|| github_slug(&dir));
+        let base = match (slug, commit) {
+            (Some(slug), Ok(commit)) => {
+                Some(format!("https://github.com/{}/blob/{}", slug, commit))
+            }
+            _ => None,
+        };
+        SpanLinker {
+            base,
+            top,
+            path_root: path_root.to_string(),
+        }
+    }
+
+    pub fn link(&self, file: &str, line: usize) -> String {
+        let absolute = lexical_absolute(&self.path_root)
+            .map(|root| root.join(file).to_string_lossy().into_owned())
+            .unwrap_or_else(|_| file.to_string());
+        let in_repo = relative_path(&absolute, &self.top);
+        match &self.base {
+            Some(base) => format!("{}/{}#L{}", base, in_repo, line),
+            None => format!("{}:{}", in_repo, line),
+        }
+    }
+}
+
+// Worst first: findings in the most complex functions, then by location.
+pub fn drafts(report: &Report, top: usize, linker: &SpanLinker) -> Vec<IssueDraft> {
+    let complexity: HashMap<(&str, &str), usize> = report
+        .files
+        .iter()
+        .flat_map(|f| &f.functions)
+        .map(|f| ((f.file.as_str(), f.function.as_str()), f.complexity))
+        .collect();
+    let mut findings: Vec<_> = report.findings.iter().collect();
+    findings.sort_by_key(|f| {
+        let c = complexity
+            .get(&(f.file.as_str(), f.function.as_str()))
+            .copied()
+            .unwrap_or(0);
+        std::cmp::Reverse(c)
+    });
+    findings
+        .into_iter()
+        .take(top)
+        .map(|f| IssueDraft {
+            title: format!("[codehealth] {} in {} ({})", f.rule, f.function, f.file),
+            body: format!(
+                "**Rule:** `{}`\n**Location:** {}\n\n{}: {}\n\n<!-- {} {} -->\n",
+                f.rule,
+                linker.link(&f.file, f.line),
+                f.function,
+                f.message,
+                MARKER,
+                f.fingerprint
+            ),
+            fingerprint: f.fingerprint.clone(),
+        })
+        .collect()
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+    finish(program, output)
+}
+
+fn finish(program: &str, output: Output) -> Result<String, String> {
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn jira_env() -> Result<(String, String), String> {
+    let var = |name: &str| env::var(name).map_err(|_| format!("{} is not set", name));
+    Ok((
+        var("JIRA_URL")?.trim_end_matches('/').to_string(),
+        format!("{}:{}", var("JIRA_USER")?, var("JIRA_TOKEN")?),
+    ))
+}
+
+// Backslash-escaped, for double-quoted strings in curl config and JQL.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// The credentials go to curl as a config file on stdin (`-K -`), since
+// command lines are visible to every local user.
+fn curl(auth: &str, args: &[&str]) -> Result<String, String> {
+    let mut child = Command::new("curl")
+        .args(["-K", "-"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run curl: {}", e))?;
+    let config = format!("user = {}\n", quote(auth));
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(config.as_bytes())
+            .map_err(|e| format!("failed to pass credentials to curl: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("failed to run curl: {}", e))?;
+    finish("curl", output)
+}
+
+fn parse(text: &str) -> Result<Value, String> {
+    serde_json::from_str(text).map_err(|e| format!("invalid tracker response: {}", e))
+}
+
+// Closed issues count too: a fixed-then-reopened finding should not refile.
+pub fn exists(tracker: &Tracker, fingerprint: &str) -> Result<bool, String> {
+    let needle = format!("{} {}", MARKER, fingerprint);
+    match tracker {
+        Tracker::GitHub { repo } => {
+            let search = format!("\"{}\" in:body", needle);
+            let mut args = vec!["issue", "list", "--state", "all", "--search", &search];
+            args.extend(["--json", "number"]);
+            if let Some(repo) = repo {
+                args.extend(["--repo", repo]);
+            }
+            let found = parse(&run("gh", &args)?)?;
+            Ok(found.as_array().is_some_and(|a| !a.is_empty()))
+        }
+        Tracker::Jira { project } => {
+            let (url, auth) = jira_env()?;
+            let jql = format!(
+                "jql=project = {} AND text ~ {}",
+                quote(project),
+                quote(fingerprint)
+            );
+            let found = parse(&curl(
+                &auth,
+                &[
+                    "-sSf",
+                    "--get",
+                    "--data-urlencode",
+                    &jql,
+                    "--data-urlencode",
+                    "fields=key",
+                    &format!("{}/rest/api/2/search", url),
+                ],
+            )?)?;
+            Ok(found["total"].as_u64().unwrap_or(0) > 0)
+        }
+    }
+}
+
+// Returns the new issue's URL or key.
+pub fn create(tracker: &Tracker, draft: &IssueDraft) -> Result<String, String> {
+    match tracker {
+        Tracker::GitHub { repo } => {
+            let mut args = vec![
+                "issue",
+                "create",
+                "--title",
+                &draft.title,
+                "--body",
+                &draft.body,
+            ];
+            if let Some(repo) = repo {
+                args.extend(["--repo", repo]);
+            }
+            Ok(run("gh", &args)?.trim().to_string())
+        }
+        Tracker::Jira { project } => {
+            let (url, auth) = jira_env()?;
+            let payload = json!({
+                "fields": {
+                    "project": { "key": project },
+                    "summary": draft.title,
+                    "description": draft.body,
+                    "issuetype": { "name": "Task" }
+                }
+            });
+            let created = parse(&curl(
+                &auth,
+                &[
+                    "-sSf",
+                    "-H",
+                    "Content-Type: application/json",
+                    "-d",
+                    &payload.to_string(),
+                    &format!("{}/rest/api/2/issue", url),
+                ],
+            )?)?;
+            created["key"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| "Jira did not return an issue key".to_string())
+        }
+    }
+}
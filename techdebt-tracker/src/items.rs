@@ -0,0 +1,55 @@
+// --- Item counts by kind, so big files of data read apart from big logic ---
+use serde::{Deserialize, Serialize};
+
+// Module-level items, including those of inline `mod { }` blocks; items
+// inside function bodies are not counted. `functions` are free functions,
+// methods belong to their `impls`; `macros` are `macro_rules!` definitions.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+pub struct ItemCounts {
+    pub functions: usize,
+    pub structs: usize,
+    pub enums: usize,
+    pub unions: usize,
+    pub traits: usize,
+    pub impls: usize,
+    pub type_aliases: usize,
+    pub consts: usize,
+    pub statics: usize,
+    pub macros: usize,
+}
+
+impl ItemCounts {
+    // Items that hold data rather than behaviour.
+    pub fn data(&self) -> usize {
+        self.consts + self.statics
+    }
+
+    fn add(&mut self, items: &[syn::Item]) {
+        for item in items {
+            match item {
+                syn::Item::Fn(_) => self.functions += 1,
+                syn::Item::Struct(_) => self.structs += 1,
+                syn::Item::Enum(_) => self.enums += 1,
+                syn::Item::Union(_) => self.unions += 1,
+                syn::Item::Trait(_) | syn::Item::TraitAlias(_) => self.traits += 1,
+                syn::Item::Impl(_) => self.impls += 1,
+                syn::Item::Type(_) => self.type_aliases += 1,
+                syn::Item::Const(_) => self.consts += 1,
+                syn::Item::Static(_) => self.statics += 1,
+                syn::Item::Macro(m) if m.ident.is_some() => self.macros += 1,
+                syn::Item::Mod(m) => {
+                    if let Some((_, inner)) = &m.content {
+                        self.add(inner);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub fn count(syntax: &syn::File) -> ItemCounts {
+    let mut counts = ItemCounts::default();
+    counts.add(&syntax.items);
+    counts
+}
@@ -0,0 +1,134 @@
+// --- Lifetime annotation density (API ergonomics signal) ---
+use crate::FileMetrics;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+// Declared lifetime parameters (`<'a>`) and every lifetime written in a
+// signature or body (`&'a T`, `'a: 'b`, `'static`). Loop labels are skipped.
+#[derive(Default)]
+pub struct LifetimeVisitor {
+    pub params: usize,
+    pub annotations: usize,
+}
+
+impl<'ast> Visit<'ast> for LifetimeVisitor {
+    fn visit_lifetime_param(&mut self, node: &'ast syn::LifetimeParam) {
+        self.params += 1;
+        visit::visit_lifetime_param(self, node);
+    }
+
+    fn visit_lifetime(&mut self, _: &'ast syn::Lifetime) {
+        self.annotations += 1;
+    }
+
+    fn visit_label(&mut self, _: &'ast syn::Label) {}
+
+    fn visit_expr_break(&mut self, node: &'ast syn::ExprBreak) {
+        if let Some(expr) = &node.expr {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn visit_expr_continue(&mut self, _: &'ast syn::ExprContinue) {}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TypeLifetimes {
+    pub name: String,
+    pub kind: String,
+    pub line: usize,
+    pub lifetime_params: usize,
+    pub lifetime_annotations: usize,
+}
+
+fn type_name(ty: &syn::Type) -> String {
+    match ty {
+        syn::Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_default(),
+        syn::Type::Reference(r) => type_name(&r.elem),
+        _ => String::new(),
+    }
+}
+
+// Structs, enums, unions, traits, type aliases and impl blocks, which is
+// where lifetimes leak into a crate's public surface.
+pub fn type_lifetimes(file: &syn::File) -> Vec<TypeLifetimes> {
+    let mut types = Vec::new();
+    for item in &file.items {
+        let (name, kind) = match item {
+            syn::Item::Struct(s) => (s.ident.to_string(), "struct"),
+            syn::Item::Enum(e) => (e.ident.to_string(), "enum"),
+            syn::Item::Union(u) => (u.ident.to_string(), "union"),
+            syn::Item::Trait(t) => (t.ident.to_string(), "trait"),
+            syn::Item::Type(t) => (t.ident.to_string(), "type"),
+            syn::Item::Impl(i) => (type_name(&i.self_ty), "impl"),
+            _ => continue,
+        };
+        let mut visitor = LifetimeVisitor::default();
+        visitor.visit_item(item);
+        types.push(TypeLifetimes {
+            name,
+            kind: kind.to_string(),
+            line: item.span().start().line,
+            lifetime_params: visitor.params,
+            lifetime_annotations: visitor.annotations,
+        });
+    }
+    types
+}
+
+#[derive(Serialize, Default, Clone)]
+pub struct ModuleLifetimes {
+    pub crate_name: String,
+    pub module: String,
+    pub items: usize, // functions + types
+    pub lifetime_params: usize,
+    pub lifetime_annotations: usize,
+    pub density: f64, // annotations per item
+    pub high_density: bool,
+}
+
+// A module is flagged when its density is at least twice the project's and
+// it averages one or more lifetimes per item.
+pub fn summarize(files: &[FileMetrics]) -> Vec<ModuleLifetimes> {
+    let mut by_module: BTreeMap<(&str, &str), ModuleLifetimes> = BTreeMap::new();
+    for file in files {
+        let entry = by_module
+            .entry((file.crate_name.as_str(), file.module.as_str()))
+            .or_insert_with(|| ModuleLifetimes {
+                crate_name: file.crate_name.clone(),
+                module: file.module.clone(),
+                ..Default::default()
+            });
+        for f in &file.functions {
+            entry.items += 1;
+            entry.lifetime_params += f.lifetime_params;
+            entry.lifetime_annotations += f.lifetime_annotations;
+        }
+        for t in &file.types {
+            entry.items += 1;
+            entry.lifetime_params += t.lifetime_params;
+            entry.lifetime_annotations += t.lifetime_annotations;
+        }
+    }
+
+    let items: usize = by_module.values().map(|m| m.items).sum();
+    let annotations: usize = by_module.values().map(|m| m.lifetime_annotations).sum();
+    let project_density = annotations as f64 / items.max(1) as f64;
+
+    by_module
+        .into_values()
+        .filter(|m| m.items > 0)
+        .map(|mut m| {
+            m.density = m.lifetime_annotations as f64 / m.items as f64;
+            m.high_density = m.density >= 1.0 && m.density >= 2.0 * project_density;
+            m
+        })
+        .collect()
+}
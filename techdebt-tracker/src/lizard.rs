@@ -0,0 +1,123 @@
+// --- lizard-compatible text output, for scripts that parse lizard reports ---
+use crate::Report;
+use proc_macro2::{TokenStream, TokenTree};
+use std::fmt::Write;
+
+// lizard's default warning threshold.
+const CCN_WARNING: usize = 15;
+
+// lizard counts tokens, not statements; groups count their delimiters.
+pub fn token_count(tokens: TokenStream) -> usize {
+    tokens
+        .into_iter()
+        .map(|t| match t {
+            TokenTree::Group(g) => 2 + token_count(g.stream()),
+            _ => 1,
+        })
+        .sum()
+}
+
+// Non-blank lines that aren't `//` comments.
+pub fn nloc<'a>(lines: impl Iterator<Item = &'a str>) -> usize {
+    lines
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("//"))
+        .count()
+}
+
+pub fn render(report: &Report) -> String {
+    let mut out = String::new();
+    let function_row = |out: &mut String, f: &crate::FunctionMetric| {
+        let _ = writeln!(
+            out,
+            "{:>8}{:>7}{:>7}{:>7}{:>8} {}@{}-{}@{}",
+            f.nloc,
+            f.complexity,
+            f.tokens,
+            f.params,
+            f.end_line + 1 - f.line,
+            f.function,
+            f.line,
+            f.end_line,
+            f.file
+        );
+    };
+
+    out.push_str("================================================\n");
+    out.push_str("  NLOC    CCN   token  PARAM  length  location  \n");
+    out.push_str("------------------------------------------------\n");
+    for f in report.files.iter().flat_map(|file| &file.functions) {
+        function_row(&mut out, f);
+    }
+    let _ = writeln!(out, "{} file analyzed.", report.files.len());
+
+    out.push_str("==============================================================\n");
+    out.push_str("NLOC    Avg.NLOC  AvgCCN  Avg.token  function_cnt    file\n");
+    out.push_str("--------------------------------------------------------------\n");
+    for file in &report.files {
+        let n = file.functions.len().max(1) as f64;
+        let sum = |value: fn(&crate::FunctionMetric) -> usize| {
+            file.functions.iter().map(value).sum::<usize>() as f64 / n
+        };
+        let _ = writeln!(
+            out,
+            "{:>7}{:>10.1}{:>10.1}{:>10.1}{:>10}     {}",
+            file.nloc,
+            sum(|f| f.nloc),
+            sum(|f| f.complexity),
+            sum(|f| f.tokens),
+            file.functions.len(),
+            file.file
+        );
+    }
+
+    let functions: Vec<_> = report.files.iter().flat_map(|f| &f.functions).collect();
+    let warnings: Vec<_> = functions
+        .iter()
+        .filter(|f| f.complexity > CCN_WARNING)
+        .collect();
+    if warnings.is_empty() {
+        let _ = writeln!(
+            out,
+            "\nNo thresholds exceeded (cyclomatic_complexity > {})",
+            CCN_WARNING
+        );
+    } else {
+        let _ = writeln!(
+            out,
+            "\n!!!! Warnings (cyclomatic_complexity > {}) !!!!",
+            CCN_WARNING
+        );
+        out.push_str("================================================\n");
+        out.push_str("  NLOC    CCN   token  PARAM  length  location  \n");
+        out.push_str("------------------------------------------------\n");
+        for f in &warnings {
+            function_row(&mut out, f);
+        }
+    }
+
+    let total_nloc: usize = report.files.iter().map(|f| f.nloc).sum();
+    let n = functions.len().max(1) as f64;
+    let avg = |value: fn(&crate::FunctionMetric) -> usize| {
+        functions.iter().map(|f| value(f)).sum::<usize>() as f64 / n
+    };
+    let warned_nloc: usize = warnings.iter().map(|f| f.nloc).sum();
+    out.push_str("==========================================================================================\n");
+    out.push_str(
+        "Total nloc   Avg.NLOC  AvgCCN  Avg.token   Fun Cnt  Warning cnt   Fun Rt   nloc Rt\n",
+    );
+    out.push_str("------------------------------------------------------------------------------------------\n");
+    let _ = writeln!(
+        out,
+        "{:>10}{:>11.1}{:>8.1}{:>11.1}{:>10}{:>13}{:>9.2}{:>10.2}",
+        total_nloc,
+        avg(|f| f.nloc),
+        avg(|f| f.complexity),
+        avg(|f| f.tokens),
+        functions.len(),
+        warnings.len(),
+        warnings.len() as f64 / n,
+        warned_nloc as f64 / total_nloc.max(1) as f64
+    );
+    out
+}
@@ -0,0 +1,382 @@
+// --- Report text localization: built-in catalogs plus a custom labels file ---
+// Text and HTML reports look their strings up by key. `{}` placeholders are
+// filled in order; numbers are formatted by the caller, so a translation can
+// reorder words but not placeholders. A labels file (TOML, `key = "text"`)
+// overrides any entry of the chosen language.
+use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
+
+pub const LANGS: [&str; 2] = ["en", "de"];
+
+// (key, English, German)
+const MESSAGES: &[(&str, &str, &str)] = &[
+    ("grade", "Grade: {} (score {} of 100)", "Note: {} ({} von 100 Punkten)"),
+    ("code-metrics", "Code Metrics:", "Code-Metriken:"),
+    ("loc", "Lines of Code (LOC): {}", "Codezeilen (LOC): {}"),
+    ("kloc", "KLOC: {}", "KLOC: {}"),
+    ("complexity", "Cyclomatic Complexity: {}", "Zyklomatische Komplexität: {}"),
+    (
+        "avg-complexity",
+        "Average Cyclomatic Complexity per Function: {}",
+        "Durchschnittliche zyklomatische Komplexität pro Funktion: {}",
+    ),
+    (
+        "distribution",
+        "Cyclomatic Complexity Distribution: [{}]",
+        "Verteilung der zyklomatischen Komplexität: [{}]",
+    ),
+    ("functions", "Number of Functions: {}", "Anzahl Funktionen: {}"),
+    ("longest-function", "Longest Function (LOC): {}", "Längste Funktion (LOC): {}"),
+    ("max-nesting", "Maximum Nesting Depth: {}", "Maximale Verschachtelungstiefe: {}"),
+    (
+        "comment-density",
+        "Comment Density: {}% (doc {} / inline {} lines; {} license-header lines excluded)",
+        "Kommentardichte: {}% (Doku {} / Inline {} Zeilen; {} Lizenzkopfzeilen ausgenommen)",
+    ),
+    ("commented-out", "Commented-out Code (lines): {}", "Auskommentierter Code (Zeilen): {}"),
+    ("maintainability", "Maintainability Index: {} (0-100)", "Wartbarkeitsindex: {} (0-100)"),
+    ("max-complexity-file", "File with Maximum Complexity: {}", "Datei mit höchster Komplexität: {}"),
+    (
+        "max-file-complexity",
+        "Maximum Cyclomatic Complexity in a File: {}",
+        "Höchste zyklomatische Komplexität einer Datei: {}",
+    ),
+    (
+        "percentiles",
+        "Function {} Percentiles: p50={} p75={} p90={} p95={} p99={} max={}",
+        "Perzentile {} pro Funktion: p50={} p75={} p90={} p95={} p99={} max={}",
+    ),
+    ("label-complexity", "Complexity", "Komplexität"),
+    ("label-loc", "LOC", "LOC"),
+    ("label-nesting", "Nesting", "Verschachtelung"),
+    ("label-functions", "Functions", "Funktionen"),
+    ("top-functions", "Top 5 Most Complex Functions:", "Die 5 komplexesten Funktionen:"),
+    (
+        "dangerous-functions",
+        "Dangerous Functions (complexity>{} and coverage<{}%):",
+        "Gefährliche Funktionen (Komplexität>{} und Abdeckung<{}%):",
+    ),
+    (
+        "weakly-tested",
+        "Complex and Weakly Tested (surviving mutants, by complexity × survival rate):",
+        "Komplex und schwach getestet (überlebende Mutanten, nach Komplexität × Überlebensrate):",
+    ),
+    (
+        "debt-velocity",
+        "Debt Velocity (since {}, {} weeks): complexity {}/week, violations {}/week, debt {} min ({} min/week)",
+        "Schuldengeschwindigkeit (seit {}, {} Wochen): Komplexität {}/Woche, Verstöße {}/Woche, Schulden {} min ({} min/Woche)",
+    ),
+    (
+        "cfg-matrix",
+        "Feature Matrix ({} combinations, min .. max):",
+        "Feature-Matrix ({} Kombinationen, min .. max):",
+    ),
+    (
+        "orphaned-hotspots",
+        "Orphaned Hotspots (owners inactive for {} months, since {}):",
+        "Verwaiste Hotspots (Autoren seit {} Monaten inaktiv, seit {}):",
+    ),
+    (
+        "compile-time-debt",
+        "Compile-Time Debt (build seconds vs complexity; * = complex and slow):",
+        "Kompilierzeit-Schulden (Build-Sekunden vs. Komplexität; * = komplex und langsam):",
+    ),
+    (
+        "risk-ranking",
+        "Highest-Risk Functions (complexity × references × (1 + lints)):",
+        "Funktionen mit höchstem Risiko (Komplexität × Referenzen × (1 + Lints)):",
+    ),
+    (
+        "format-drift",
+        "Formatting Drift: {} of {} files, {} lines differ from rustfmt",
+        "Formatierungsabweichung: {} von {} Dateien, {} Zeilen weichen von rustfmt ab",
+    ),
+    (
+        "doc-code",
+        "Rust in Documentation: {} blocks, {} LOC, complexity {} ({} unparsed)",
+        "Rust in der Dokumentation: {} Blöcke, {} LOC, Komplexität {} ({} nicht geparst)",
+    ),
+    (
+        "heaviest-types",
+        "Heaviest Types (weighted methods per class):",
+        "Schwerste Typen (gewichtete Methoden pro Klasse):",
+    ),
+    ("clippy-lints", "Clippy Lints by File:", "Clippy-Lints nach Datei:"),
+    ("macro-definitions", "Largest macro_rules! Definitions:", "Größte macro_rules!-Definitionen:"),
+    (
+        "magic-values",
+        "Magic Value Density (literals per 100 LOC):",
+        "Dichte magischer Werte (Literale pro 100 LOC):",
+    ),
+    (
+        "vendored",
+        "Vendored Code ({}): {} files, {} LOC, {} functions, complexity {} (max {})",
+        "Fremdcode ({}): {} Dateien, {} LOC, {} Funktionen, Komplexität {} (max {})",
+    ),
+    ("vendored-gated", "gated", "in Gates"),
+    ("vendored-not-gated", "not gated", "nicht in Gates"),
+    (
+        "sample",
+        "Sample: {}% (seed {}), {} of {} files analyzed; extrapolated totals (95% CI):",
+        "Stichprobe: {}% (Seed {}), {} von {} Dateien analysiert; hochgerechnete Summen (95%-KI):",
+    ),
+    ("roots", "Roots:", "Wurzelverzeichnisse:"),
+    ("components", "Components:", "Komponenten:"),
+    (
+        "grades",
+        "Grades (A >= {}, B >= {}, C >= {}, D >= {}, else F):",
+        "Noten (A >= {}, B >= {}, C >= {}, D >= {}, sonst F):",
+    ),
+    ("compare-grade", "Grade: {}", "Note: {}"),
+    (
+        "compile-time",
+        "Compile-Time Code: {} const fn, complexity {} ({}% of all), avg {} vs {} at runtime",
+        "Code zur Kompilierzeit: {} const fn, Komplexität {} ({}% der gesamten), Ø {} vs. {} zur Laufzeit",
+    ),
+    (
+        "hygiene",
+        "Whitespace Hygiene: {} CRLF files ({} mixed endings), {} mixing tabs/spaces, {} trailing-whitespace lines",
+        "Leerraum-Hygiene: {} CRLF-Dateien ({} gemischte Zeilenenden), {} mischen Tabs/Leerzeichen, {} Zeilen mit Leerraum am Ende",
+    ),
+    (
+        "security-surface",
+        "Security-Sensitive Surface: {} process commands, {} SQL concatenations, {} transmutes, {} secret env reads, {} FFI declarations",
+        "Sicherheitsrelevante Oberfläche: {} Prozessaufrufe, {} SQL-Verkettungen, {} Transmutes, {} Geheimnis-Umgebungsvariablen, {} FFI-Deklarationen",
+    ),
+    (
+        "test-quality",
+        "Test Quality: {} tests, {} assertions per test, {} lines on average, {} without assertions",
+        "Testqualität: {} Tests, {} Assertions pro Test, durchschnittlich {} Zeilen, {} ohne Assertions",
+    ),
+    ("ffi-surface", "FFI Surface:", "FFI-Oberfläche:"),
+    ("error-handling", "Error Handling Health:", "Zustand der Fehlerbehandlung:"),
+    (
+        "changed-functions",
+        "Changed Functions ({}, {} worsened):",
+        "Geänderte Funktionen ({}, {} verschlechtert):",
+    ),
+    ("oversized-files", "Oversized Files ({}):", "Übergroße Dateien ({}):"),
+    (
+        "auto-thresholds",
+        "Auto Thresholds ({}, from {} functions):",
+        "Automatische Schwellenwerte ({}, aus {} Funktionen):",
+    ),
+    (
+        "includes",
+        "Included Files ({} includes, {} lines):",
+        "Eingebundene Dateien ({} Einbindungen, {} Zeilen):",
+    ),
+    (
+        "orphan-files",
+        "Orphan Files ({}, not reached by any `mod` declaration):",
+        "Verwaiste Dateien ({}, von keiner `mod`-Deklaration erreicht):",
+    ),
+    ("directories", "Directories by Risk:", "Verzeichnisse nach Risiko:"),
+    ("public-api", "Public API Surface:", "Öffentliche API-Oberfläche:"),
+    ("lifetime-density", "Lifetime-Heavy Modules:", "Module mit vielen Lifetimes:"),
+    (
+        "codegen-functions",
+        "Largest Code-Generation Functions (proc-macro crates):",
+        "Größte Codegenerierungs-Funktionen (Proc-Macro-Crates):",
+    ),
+    (
+        "largest-expressions",
+        "Largest Single Expressions (syntax nodes in one statement):",
+        "Größte Einzelausdrücke (Syntaxknoten in einer Anweisung):",
+    ),
+    ("self-profile", "Analyzer Profile ({} ms total):", "Analyseprofil ({} ms gesamt):"),
+    (
+        "diagnostics",
+        "Diagnostics ({}, {} binary file(s) skipped):",
+        "Diagnosen ({}, {} Binärdatei(en) übersprungen):",
+    ),
+    ("rule-statistics", "Rule Statistics:", "Regelstatistik:"),
+    ("findings", "Findings ({}):", "Befunde ({}):"),
+    ("expired-debt", "Expired Debt Items ({}):", "Abgelaufene Schuldposten ({}):"),
+    // Text report: summary, meta line, targets and table cells
+    ("cell-coverage", "coverage={}%", "Abdeckung={}%"),
+    ("cell-mutants-caught", "mutants-caught={}%", "Mutanten-gefangen={}%"),
+    ("cell-complexity", "complexity={}", "Komplexität={}"),
+    ("cell-cognitive", "cognitive={}", "kognitiv={}"),
+    ("cell-loc", "LOC={}", "LOC={}"),
+    ("cell-exits", "exits={}", "Ausgänge={}"),
+    ("cell-mutants", "mutants={}", "Mutanten={}"),
+    ("cell-survived", "survived={}", "überlebt={}"),
+    ("cell-caught", "caught={}%", "gefangen={}%"),
+    ("cell-priority", "priority={}", "Priorität={}"),
+    ("cell-last-commit", "{} (last {})", "{} (zuletzt {})"),
+    ("cell-complexity-max", "complexity={} (max {})", "Komplexität={} (max {})"),
+    ("cell-inactive-share", "{}% of lines by inactive authors", "{}% der Zeilen von inaktiven Autoren"),
+    ("cell-recent-commits", "{} recent commits", "{} neue Commits"),
+    ("cell-features", "features={}", "Features={}"),
+    ("cell-functions", "functions={}", "Funktionen={}"),
+    ("cell-nloc", "nloc={}", "nloc={}"),
+    ("cell-max", "max={}", "max={}"),
+    ("cell-findings", "findings={}", "Befunde={}"),
+    ("cell-seconds", "{}s", "{} s"),
+    ("cell-seconds-per-kloc", "{}s/kLOC", "{} s/kLOC"),
+    ("cell-avg-complexity", "avg complexity={}", "Ø Komplexität={}"),
+    ("cell-risk", "risk={}", "Risiko={}"),
+    ("cell-references", "references={}", "Referenzen={}"),
+    ("cell-lints", "lints={}", "Lints={}"),
+    ("cell-lines", "{} lines", "{} Zeilen"),
+    ("cell-blocks", "{} blocks", "{} Blöcke"),
+    ("cell-n-loc", "{} LOC", "{} LOC"),
+    ("cell-complexity-total", "complexity {}", "Komplexität {}"),
+    ("cell-wmc", "WMC={}", "WMC={}"),
+    ("cell-methods", "methods={}", "Methoden={}"),
+    ("cell-fields", "fields={}", "Felder={}"),
+    ("cell-lcom", "LCOM={}", "LCOM={}"),
+    ("cell-tokens", "{} tokens", "{} Tokens"),
+    ("cell-rules", "{} rules", "{} Regeln"),
+    ("cell-depth", "depth {}", "Tiefe {}"),
+    ("cell-magic-counts", "({} numbers, {} long strings)", "({} Zahlen, {} lange Zeichenketten)"),
+    ("derived", "{} = {}{}:", "{} = {}{}:"),
+    ("derived-over", ", {} over {}", ", {} über {}"),
+    ("cell-files", "{} files", "{} Dateien"),
+    ("cell-n-functions", "{} functions", "{} Funktionen"),
+    ("cell-complexity-total-max", "complexity {} (max {})", "Komplexität {} (max {})"),
+    ("cell-complexity-avg-max", "complexity {} (avg {}, max {})", "Komplexität {} (Ø {}, max {})"),
+    ("cell-n-findings", "{} findings", "{} Befunde"),
+    ("cell-crlf", "crlf={}", "CRLF={}"),
+    ("cell-tabs", "tabs={}", "Tabs={}"),
+    ("cell-spaces", "spaces={}", "Leerzeichen={}"),
+    ("cell-mixed", "mixed={}", "gemischt={}"),
+    ("cell-trailing", "trailing={}", "am Zeilenende={}"),
+    ("cell-commands", "commands={}", "Prozesse={}"),
+    ("cell-sql", "sql={}", "SQL={}"),
+    ("cell-transmute", "transmute={}", "Transmute={}"),
+    ("cell-secrets", "secrets={}", "Geheimnisse={}"),
+    ("cell-ffi", "ffi={}", "FFI={}"),
+    ("cell-no-assertions", "({} lines, no assertions)", "({} Zeilen, keine Assertions)"),
+    ("cell-extern-blocks", "extern blocks={}", "extern-Blöcke={}"),
+    ("cell-foreign-fns", "foreign fns={}", "Fremdfunktionen={}"),
+    ("cell-unsafe-ffi-calls", "unsafe FFI calls={}", "unsichere FFI-Aufrufe={}"),
+    ("cell-in-files", "in {} file(s)", "in {} Datei(en)"),
+    ("cell-result-fns", "Result fns={}/{}", "Result-Funktionen={}/{}"),
+    ("cell-unwraps", "unwrap/expect={}", "unwrap/expect={}"),
+    ("cell-ratio", "(ratio {})", "(Verhältnis {})"),
+    ("cell-cc-cognitive", "cc/cognitive {} → {}/{}", "zykl./kogn. {} → {}/{}"),
+    ("cell-item-counts", "[{} fns, {} impls, {} types, {} consts/statics]", "[{} Funktionen, {} impls, {} Typen, {} consts/statics]"),
+    ("cell-median-spread", "(median {}, {} {})", "(Median {}, {} {})"),
+    ("cell-outliers", "→ {} outlier(s)", "→ {} Ausreißer"),
+    ("cell-bytes", "{} bytes", "{} Bytes"),
+    ("cell-worst", "worst={}", "schlechteste={}"),
+    ("cell-lifetimes-per-item", "{} lifetimes/item", "{} Lifetimes/Element"),
+    ("cell-lifetime-counts", "({} params, {} annotations over {} items)", "({} Parameter, {} Annotationen über {} Elemente)"),
+    ("cell-quote-tokens", "{} quote! tokens", "{} quote!-Tokens"),
+    ("cell-in-blocks", "in {} block(s)", "in {} Block/Blöcken"),
+    ("cell-expression-nodes", "{} expression nodes", "{} Ausdrucksknoten"),
+    ("cell-statements", "{} statements", "{} Anweisungen"),
+    ("cell-ms", "{} ms", "{} ms"),
+    ("cell-phase-times", "(read {}, parse {}, visit {})", "(lesen {}, parsen {}, besuchen {})"),
+    ("cell-suppressed", "{} suppressed", "{} unterdrückt"),
+    ("cell-new", "{} new", "{} neu"),
+    ("cell-expired", "expired {}", "abgelaufen {}"),
+    ("cell-count-again", "{} finding(s) count again", "{} Befund(e) zählen wieder"),
+    ("target", "Target: {}", "Ziel: {}"),
+    ("all-targets", "All targets ({})", "Alle Ziele ({})"),
+    ("meta-commit", "commit {}", "Commit {}"),
+    ("meta-config", "config {} ({})", "Konfiguration {} ({})"),
+    ("cell-recursive", "(recursive)", "(rekursiv)"),
+    ("grade-functions", "functions:", "Funktionen:"),
+    ("grade-files", "files:", "Dateien:"),
+    ("grade-none", "n/a", "k. A."),
+    ("no-crate", "(no crate)", "(kein Crate)"),
+    ("target-pass", "pass", "bestanden"),
+    ("target-fail", "FAIL", "FEHLGESCHLAGEN"),
+    ("meta-dirty", "dirty", "geändert"),
+    ("status-unchanged", "unchanged", "unverändert"),
+    ("age-new", "new", "neu"),
+    ("age-days", "{}d", "{} T"),
+    ("age-months", "{}mo", "{} Mon."),
+    ("age-years", "{}y", "{} J."),
+    ("summary", "codehealth: grade {}, {} files, {} LOC, {} functions, complexity {} (avg {}), MI {}", "codehealth: Note {}, {} Dateien, {} LOC, {} Funktionen, Komplexität {} (Ø {}), MI {}"),
+    ("summary-counts", "{} finding(s), {} gate failure(s)", "{} Befund(e), {} Gate-Verstoß/-Verstöße"),
+    // `compare`, text and HTML
+    ("compare-title", "Report changes {} → {}:", "Berichtsänderungen {} → {}:"),
+    ("compare-loc", "LOC: {}", "LOC: {}"),
+    ("compare-mi", "Maintainability Index: {}", "Wartbarkeitsindex: {}"),
+    ("compare-review", "Estimated Review Time: {} min", "Geschätzte Reviewzeit: {} min"),
+    (
+        "compare-drift",
+        "Distribution Drift: KS {} (p={}), {} moved to a higher bucket, {} to a lower one",
+        "Verteilungsdrift: KS {} (p={}), {} in einen höheren Bereich, {} in einen niedrigeren",
+    ),
+    ("compare-status", "{} functions ({})", "Funktionen {} ({})"),
+    ("status-added", "added", "hinzugefügt"),
+    ("status-removed", "removed", "entfernt"),
+    ("status-worsened", "worsened", "verschlechtert"),
+    ("status-improved", "improved", "verbessert"),
+    ("html-metric", "Metric", "Metrik"),
+    ("html-complexity", "Cyclomatic complexity", "Zyklomatische Komplexität"),
+    ("html-mi", "Maintainability index", "Wartbarkeitsindex"),
+    ("html-grade", "Grade", "Note"),
+    ("html-review", "Estimated review minutes", "Geschätzte Reviewminuten"),
+    ("html-function", "Function", "Funktion"),
+    ("html-complexity-before", "Complexity before", "Komplexität vorher"),
+    ("html-complexity-after", "Complexity after", "Komplexität nachher"),
+    ("html-loc-before", "LOC before", "LOC vorher"),
+    ("html-loc-after", "LOC after", "LOC nachher"),
+    ("html-review-min", "Review min", "Review-Min."),
+];
+
+static CATALOG: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+
+fn builtin(lang: &str) -> HashMap<&'static str, String> {
+    let column = LANGS.iter().position(|&l| l == lang).unwrap_or(0);
+    MESSAGES
+        .iter()
+        .map(|&(key, en, de)| (key, [en, de][column].to_string()))
+        .collect()
+}
+
+// Unknown keys in the labels file are an error, so typos don't go unnoticed.
+pub fn init(lang: &str, labels: Option<&str>) -> Result<(), String> {
+    let mut catalog = builtin(lang);
+    if let Some(path) = labels {
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        let custom: HashMap<String, String> =
+            toml::from_str(&text).map_err(|e| format!("invalid labels {}: {}", path, e))?;
+        for (key, value) in custom {
+            let Some(&(known, _, _)) = MESSAGES.iter().find(|(k, _, _)| *k == key) else {
+                return Err(format!("invalid labels {}: unknown key '{}'", path, key));
+            };
+            catalog.insert(known, value);
+        }
+    }
+    let _ = CATALOG.set(catalog);
+    Ok(())
+}
+
+pub fn text(key: &str, args: &[String]) -> String {
+    let template = CATALOG
+        .get()
+        .and_then(|c| c.get(key).cloned())
+        .or_else(|| {
+            MESSAGES
+                .iter()
+                .find(|m| m.0 == key)
+                .map(|m| m.1.to_string())
+        })
+        .unwrap_or_else(|| key.to_string());
+    let mut out = String::new();
+    let mut args = args.iter();
+    let mut rest = template.as_str();
+    while let Some(at) = rest.find("{}") {
+        out.push_str(&rest[..at]);
+        out.push_str(args.next().map_or("", String::as_str));
+        rest = &rest[at + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// `tr!("key", a, b)`: look up `key` and fill its placeholders with a, b.
+macro_rules! tr {
+    ($key:expr $(, $arg:expr)* $(,)?) => {
+        $crate::locale::text($key, &[$($arg.to_string()),*])
+    };
+}
+pub(crate) use tr;
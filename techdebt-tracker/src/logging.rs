@@ -0,0 +1,148 @@
+// --- Diagnostics logging (stderr only; the report always goes to stdout) ---
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+}
+
+static FORMAT: OnceLock<LogFormat> = OnceLock::new();
+
+pub fn init(format: LogFormat) {
+    let _ = FORMAT.set(format);
+}
+
+fn format() -> LogFormat {
+    *FORMAT.get().unwrap_or(&LogFormat::Text)
+}
+
+#[derive(Serialize)]
+struct LogRecord<'a> {
+    timestamp: String,
+    level: &'a str,
+    message: &'a str,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+// Emit one diagnostic. `fields` are only rendered in JSON mode, so text
+// messages must be self-contained.
+pub fn log(level: Level, message: &str, fields: &[(&str, Value)]) {
+    match format() {
+        LogFormat::Text => match level {
+            Level::Info => eprintln!("{}", message),
+            Level::Warn => eprintln!("⚠️  {}", message),
+            Level::Error => eprintln!("error: {}", message),
+        },
+        LogFormat::Json => {
+            let record = LogRecord {
+                timestamp: rfc3339_now(),
+                level: level.as_str(),
+                message,
+                fields: fields
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.clone()))
+                    .collect(),
+            };
+            if let Ok(line) = serde_json::to_string(&record) {
+                eprintln!("{}", line);
+            }
+        }
+    }
+}
+
+pub fn info(message: &str, fields: &[(&str, Value)]) {
+    log(Level::Info, message, fields);
+}
+
+pub fn warn(message: &str, fields: &[(&str, Value)]) {
+    log(Level::Warn, message, fields);
+}
+
+pub fn error(message: &str, fields: &[(&str, Value)]) {
+    log(Level::Error, message, fields);
+}
+
+// UTC timestamp without pulling in a date crate (civil-from-days algorithm).
+pub fn rfc3339_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    rfc3339(secs)
+}
+
+pub fn rfc3339(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (h, m, s) = (rem / 3600, (rem % 3600) / 60, rem % 60);
+
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, h, m, s
+    )
+}
+
+// Today's UTC date as YYYY-MM-DD, the form expiry dates are written in.
+pub fn today() -> String {
+    rfc3339_now()[..10].to_string()
+}
+
+pub fn is_date(text: &str) -> bool {
+    let b = text.as_bytes();
+    b.len() == 10
+        && b[4] == b'-'
+        && b[7] == b'-'
+        && b.iter()
+            .enumerate()
+            .all(|(i, c)| i == 4 || i == 7 || c.is_ascii_digit())
+}
+
+// Inverse of `rfc3339` (days-from-civil); only the UTC form it writes.
+pub fn parse_rfc3339(text: &str) -> Option<u64> {
+    let (date, time) = text.strip_suffix('Z')?.split_once('T')?;
+    let mut d = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (d.next()??, d.next()??, d.next()??);
+    let mut t = time.splitn(3, ':').map(|p| p.parse::<i64>().ok());
+    let (h, m, s) = (t.next()??, t.next()??, t.next()??);
+
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    u64::try_from(days * 86_400 + h * 3600 + m * 60 + s).ok()
+}
@@ -0,0 +1,271 @@
+// --- `lsp`: findings as editor diagnostics, with triage code actions ---
+// A minimal language server over stdio. Open documents are analyzed on open,
+// change and save; each finding offers two quick fixes: insert a
+// `// codehealth-allow:` comment above its function, or explain the rule.
+// As in `precommit`, suppressions keyed by fingerprint need the whole
+// project and don't apply here.
+use crate::config::{ComplexityModel, Suppression, Thresholds};
+use crate::{analyze_source, collect_findings, relative_path, rules, sarif};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+const EXPLAIN_COMMAND: &str = "codehealth.explainRule";
+const METHOD_NOT_FOUND: i64 = -32601;
+
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(n) = header.strip_prefix("Content-Length:") {
+            length = n.trim().parse::<usize>().ok();
+        }
+    }
+    let length =
+        length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn send(out: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(out, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    out.flush()
+}
+
+// `file:///a%20b/c.rs` -> `/a b/c.rs`.
+fn uri_to_path(uri: &str) -> String {
+    let raw = uri.strip_prefix("file://").unwrap_or(uri).as_bytes();
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let hex = raw
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (raw[i], hex) {
+            (b'%', Some(b)) => {
+                bytes.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                bytes.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+struct Server<'a> {
+    scan_path: &'a str,
+    thresholds: &'a Thresholds,
+    model: ComplexityModel,
+    suppressions: &'a [Suppression],
+    documents: HashMap<String, String>, // uri -> current text
+}
+
+impl Server<'_> {
+    fn diagnostics(&self, uri: &str) -> Value {
+        let Some(text) = self.documents.get(uri) else {
+            return json!({ "uri": uri, "diagnostics": [] });
+        };
+        let file = relative_path(&uri_to_path(uri), self.scan_path);
+        let (_, detail) = analyze_source(text, &file, self.model);
+        let line_range = |line: usize| {
+            let line = line.saturating_sub(1);
+            json!({
+                "start": { "line": line, "character": 0 },
+                "end": { "line": line + 1, "character": 0 }
+            })
+        };
+
+        let mut diagnostics = Vec::new();
+        if let Some(err) = &detail.parse_error {
+            diagnostics.push(json!({
+                "range": line_range(1),
+                "severity": 1,
+                "source": "codehealth",
+                "message": format!("does not parse: {}", err),
+            }));
+        }
+        for finding in collect_findings(std::slice::from_ref(&detail), self.thresholds) {
+            let function = detail.functions.iter().find(|f| {
+                f.function == finding.function && (f.line..=f.end_line).contains(&finding.line)
+            });
+            let allowed =
+                function.is_some_and(|f| f.allows.iter().any(|a| *a == finding.rule || a == "all"));
+            if allowed
+                || self
+                    .suppressions
+                    .iter()
+                    .any(|s| rules::matches(s, &finding))
+            {
+                continue;
+            }
+            diagnostics.push(json!({
+                "range": line_range(finding.line),
+                "severity": 2,
+                "source": "codehealth",
+                "code": finding.rule,
+                "message": format!("{}: {}", finding.function, finding.message),
+                "data": { "function_line": function.map_or(finding.line, |f| f.line) },
+            }));
+        }
+        json!({ "uri": uri, "diagnostics": diagnostics })
+    }
+
+    fn code_actions(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let text = self.documents.get(uri).map_or("", String::as_str);
+        let lines: Vec<&str> = text.lines().collect();
+        let ours = params["context"]["diagnostics"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|d| d["source"] == "codehealth");
+
+        let mut actions = Vec::new();
+        for diagnostic in ours {
+            let Some(rule) = diagnostic["code"].as_str() else {
+                continue;
+            };
+            // The allow comment goes right above the function, indented like it.
+            let line = diagnostic["data"]["function_line"]
+                .as_u64()
+                .map(|l| l.saturating_sub(1))
+                .or_else(|| diagnostic["range"]["start"]["line"].as_u64())
+                .unwrap_or(0);
+            let target = lines.get(line as usize).copied().unwrap_or_default();
+            let indent = &target[..target.len() - target.trim_start().len()];
+            let at = json!({ "line": line, "character": 0 });
+            actions.push(json!({
+                "title": format!("Allow {} in this function (codehealth-allow comment)", rule),
+                "kind": "quickfix",
+                "diagnostics": [diagnostic],
+                "edit": { "changes": { uri: [{
+                    "range": { "start": at, "end": at },
+                    "newText": format!("{}{} {}\n", indent, rules::ALLOW_MARKER, rule),
+                }] } },
+            }));
+            actions.push(json!({
+                "title": format!("Explain rule {}", rule),
+                "kind": "quickfix",
+                "diagnostics": [diagnostic],
+                "command": {
+                    "title": format!("Explain rule {}", rule),
+                    "command": EXPLAIN_COMMAND,
+                    "arguments": [rule],
+                },
+            }));
+        }
+        Value::Array(actions)
+    }
+}
+
+// Serves until the client sends `exit` or closes stdin.
+pub fn serve(
+    scan_path: &str,
+    thresholds: &Thresholds,
+    model: ComplexityModel,
+    suppressions: &[Suppression],
+) -> Result<(), String> {
+    let mut server = Server {
+        scan_path,
+        thresholds,
+        model,
+        suppressions,
+        documents: HashMap::new(),
+    };
+    let mut input = io::stdin().lock();
+    let mut out = io::stdout().lock();
+    let io_err = |e: io::Error| format!("lsp: {}", e);
+
+    while let Some(message) = read_message(&mut input).map_err(io_err)? {
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let result = match message["method"].as_str().unwrap_or_default() {
+            "initialize" => Some(json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": true, "change": 1, "save": { "includeText": true } },
+                    "codeActionProvider": { "codeActionKinds": ["quickfix"] },
+                    "executeCommandProvider": { "commands": [EXPLAIN_COMMAND] },
+                },
+                "serverInfo": { "name": "codehealth-analyzer", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => Some(Value::Null),
+            "exit" => return Ok(()),
+            "textDocument/didOpen" | "textDocument/didChange" | "textDocument/didSave" => {
+                // Full sync: the last change carries the whole document.
+                let text = params["textDocument"]["text"]
+                    .as_str()
+                    .or_else(|| params["contentChanges"].as_array()?.last()?["text"].as_str())
+                    .or_else(|| params["text"].as_str());
+                if let Some(text) = text {
+                    server.documents.insert(uri.clone(), text.to_string());
+                }
+                let diagnostics = server.diagnostics(&uri);
+                send(
+                    &mut out,
+                    &notification("textDocument/publishDiagnostics", diagnostics),
+                )
+                .map_err(io_err)?;
+                None
+            }
+            "textDocument/didClose" => {
+                server.documents.remove(&uri);
+                let cleared = json!({ "uri": uri, "diagnostics": [] });
+                send(
+                    &mut out,
+                    &notification("textDocument/publishDiagnostics", cleared),
+                )
+                .map_err(io_err)?;
+                None
+            }
+            "textDocument/codeAction" => Some(server.code_actions(params)),
+            "workspace/executeCommand" if params["command"] == EXPLAIN_COMMAND => {
+                let rule = params["arguments"][0].as_str().unwrap_or_default();
+                let text = format!("{}: {}", rule, sarif::rule_description(rule));
+                send(
+                    &mut out,
+                    &notification("window/showMessage", json!({ "type": 3, "message": text })),
+                )
+                .map_err(io_err)?;
+                Some(Value::Null)
+            }
+            _ => None,
+        };
+
+        // Requests carry an id and always get an answer; notifications don't.
+        let Some(id) = message.get("id").cloned() else {
+            continue;
+        };
+        let reply = match result {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": METHOD_NOT_FOUND, "message": "method not supported" },
+            }),
+        };
+        send(&mut out, &reply).map_err(io_err)?;
+    }
+    Ok(())
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
@@ -0,0 +1,78 @@
+// --- `macro_rules!` definitions: rule count, token-tree depth and size ---
+// Macro bodies are opaque token streams to every other metric, so they are
+// measured here on the raw tokens.
+use crate::{lizard, FileMetrics};
+use proc_macro2::{TokenStream, TokenTree};
+use serde::{Deserialize, Serialize};
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MacroMetrics {
+    pub file: String,
+    pub name: String,
+    pub line: usize,
+    pub end_line: usize,
+    pub rules: usize,  // `(matcher) => { transcriber }` arms
+    pub depth: usize,  // deepest nesting of delimited groups
+    pub tokens: usize, // as lizard counts them, delimiters included
+}
+
+// Rules are the top-level `=>` arrows; `=>` inside a transcriber is nested
+// in its group and not seen here.
+fn rule_count(tokens: TokenStream) -> usize {
+    let mut rules = 0;
+    let mut after_eq = false;
+    for t in tokens {
+        let is_gt = matches!(&t, TokenTree::Punct(p) if p.as_char() == '>');
+        rules += usize::from(after_eq && is_gt);
+        after_eq = matches!(&t, TokenTree::Punct(p) if p.as_char() == '=');
+    }
+    rules
+}
+
+fn depth(tokens: TokenStream) -> usize {
+    tokens
+        .into_iter()
+        .map(|t| match t {
+            TokenTree::Group(g) => 1 + depth(g.stream()),
+            _ => 0,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+pub struct MacroVisitor<'a> {
+    pub file: &'a str,
+    pub macros: Vec<MacroMetrics>,
+}
+
+impl<'ast> Visit<'ast> for MacroVisitor<'_> {
+    fn visit_item_macro(&mut self, node: &'ast syn::ItemMacro) {
+        if let (true, Some(name)) = (node.mac.path.is_ident("macro_rules"), &node.ident) {
+            let tokens = node.mac.tokens.clone();
+            self.macros.push(MacroMetrics {
+                file: self.file.to_string(),
+                name: name.to_string(),
+                line: node.span().start().line,
+                end_line: node.span().end().line,
+                rules: rule_count(tokens.clone()),
+                depth: depth(tokens.clone()),
+                tokens: lizard::token_count(tokens),
+            });
+        }
+        visit::visit_item_macro(self, node);
+    }
+}
+
+// Every definition in the project, largest first.
+pub fn rank(files: &[FileMetrics], limit: usize) -> Vec<MacroMetrics> {
+    let mut macros: Vec<MacroMetrics> = files.iter().flat_map(|f| f.macros.clone()).collect();
+    macros.sort_by(|a, b| {
+        b.tokens
+            .cmp(&a.tokens)
+            .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+    });
+    macros.truncate(limit);
+    macros
+}
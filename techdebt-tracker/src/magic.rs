@@ -0,0 +1,85 @@
+// --- Magic values: numeric and long string literals in function bodies ---
+use crate::FileMetrics;
+use serde::{Deserialize, Serialize};
+use syn::visit::Visit;
+
+// Strings at least this long are treated as embedded data, not labels.
+const LONG_STRING: usize = 30;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MagicValue {
+    pub line: usize,
+    pub function: String,
+    pub kind: String, // "number" or "string"
+    pub value: String,
+}
+
+// 0 and 1 are idioms, not magic. Literals inside macro invocations are not
+// parsed, so format strings never count.
+pub struct MagicValueVisitor<'a> {
+    pub function: &'a str,
+    pub values: Vec<MagicValue>,
+}
+
+impl MagicValueVisitor<'_> {
+    fn push(&mut self, lit: &syn::Lit, kind: &str, value: String) {
+        self.values.push(MagicValue {
+            line: lit.span().start().line,
+            function: self.function.to_string(),
+            kind: kind.to_string(),
+            value,
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for MagicValueVisitor<'_> {
+    fn visit_lit(&mut self, node: &'ast syn::Lit) {
+        match node {
+            syn::Lit::Int(i) if !matches!(i.base10_digits(), "0" | "1") => {
+                self.push(node, "number", i.base10_digits().to_string())
+            }
+            syn::Lit::Float(f) if !matches!(f.base10_digits(), "0.0" | "1.0" | "0" | "1") => {
+                self.push(node, "number", f.base10_digits().to_string())
+            }
+            syn::Lit::Str(s) if s.value().chars().count() >= LONG_STRING => {
+                let text: String = s.value().chars().take(LONG_STRING).collect();
+                self.push(node, "string", format!("{}…", text))
+            }
+            _ => {}
+        }
+    }
+
+    // Nested items are measured on their own.
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
+#[derive(Serialize, Clone)]
+pub struct FileMagicValues {
+    pub file: String,
+    pub numbers: usize,
+    pub strings: usize,
+    pub per_100_loc: f64,
+}
+
+// Files with any magic values, densest first.
+pub fn summarize(files: &[FileMetrics]) -> Vec<FileMagicValues> {
+    let mut rows: Vec<FileMagicValues> = files
+        .iter()
+        .filter(|f| !f.magic_values.is_empty())
+        .map(|f| {
+            let numbers = f.magic_values.iter().filter(|v| v.kind == "number").count();
+            FileMagicValues {
+                file: f.file.clone(),
+                numbers,
+                strings: f.magic_values.len() - numbers,
+                per_100_loc: f.magic_values.len() as f64 * 100.0 / f.nloc.max(1) as f64,
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.per_100_loc
+            .total_cmp(&a.per_100_loc)
+            .then_with(|| a.file.cmp(&b.file))
+    });
+    rows
+}
@@ -1,18 +1,107 @@
-use clap::{Arg, Command};
-use serde::Serialize;
-use walkdir::WalkDir;
-use syn::{visit::Visit, Stmt};
+use clap::{Arg, ArgMatches, Command};
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use syn::{spanned::Spanned, visit::Visit, Stmt};
+use walkdir::WalkDir;
+
+mod age;
+mod annotate;
+mod api;
+mod artifact;
+mod buildtime;
+mod cache;
+mod callgraph;
+mod cfgmatrix;
+mod chains;
+mod changerisk;
+mod classes;
+mod clippy;
+mod cognitive;
+mod comments;
+mod compare;
+mod components;
+mod config;
+mod constfn;
+mod coverage;
+mod crates;
+#[cfg(unix)]
+mod daemon;
+mod derived;
+mod distribution;
+mod doccode;
+mod error_handling;
+mod exprsize;
+mod failfast;
+mod ffi;
+mod fingerprint;
+mod format;
+mod git;
+mod grades;
+mod history;
+mod hygiene;
+mod ignore;
+mod includes;
+mod issues;
+mod items;
+mod lifetimes;
+mod lizard;
+mod locale;
+mod logging;
+mod lsp;
+mod macros;
+mod magic;
+mod meta;
+mod metadata;
+mod modtree;
+mod mutants;
+mod openmetrics;
+mod outliers;
+mod ownership;
+mod paths;
+mod precommit;
+mod profile;
+mod query;
+mod rank;
+mod registry;
+mod rollup;
+mod roots;
+mod rules;
+mod rustdoc;
+mod sample;
+mod sarif;
+mod security;
+mod shard;
+mod simulate;
+mod snippet;
+mod stats;
+mod symbols;
+mod template;
+mod term;
+mod tests;
+mod timeline;
+mod treemap;
+mod velocity;
+mod vendor;
+use config::{ComplexityModel, Thresholds};
+use locale::tr;
+use logging::LogFormat;
+use serde_json::json;
 
 // --- Code metrics struct (overall totals) ---
-#[derive(Default, Serialize, Clone)]
+#[derive(Default, Serialize, Deserialize, Clone)]
 struct CodeMetrics {
     loc: usize,
     kloc: f64,
     cyclomatic_complexity: usize,
     functions: usize,
-    comments: usize,
+    comments: usize, // doc + inline comment lines; license headers excluded
+    comment_lines: comments::CommentLines,
+    commented_out_code: usize, // comment lines that parse as Rust code
     longest_function_loc: usize,
     max_nesting_depth: usize,
     file_with_max_complexity: String,
@@ -21,57 +110,388 @@ struct CodeMetrics {
     halstead_operands: usize,
     halstead_unique_operators: usize,
     halstead_unique_operands: usize,
-    cyclomatic_distribution: [usize; 3], // [<=5, 6-10, >10]
+    cyclomatic_distribution: Vec<usize>, // one count per bucket, see bounds
+    cyclomatic_distribution_bounds: Vec<usize>, // inclusive upper edges, e.g. [5, 10]
 }
 
 // --- New: per-function and per-file details ---
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 struct FunctionMetric {
     file: String,
     function: String,
     complexity: usize,
+    cognitive_complexity: usize, // includes +1 per recursive call
+    is_recursive: bool,          // directly or mutually
+    recursion_cycle: usize,      // functions in the cycle, 1 = calls itself
     loc: usize,
+    nesting: usize,
+    exit_points: usize, // `return` statements + `?` early exits
+    line: usize,
+    end_line: usize,
+    returns_result: bool,
+    try_ops: usize,                // `?` operators
+    unwraps: usize,                // `.unwrap()` / `.expect()` calls
+    max_condition_operands: usize, // most `&&`/`||` operands in one condition
+    #[serde(default)]
+    max_call_chain: usize, // most method calls in one chain
+    #[serde(default)]
+    call_chain_lines: (usize, usize), // where that chain starts and ends
+    #[serde(default)]
+    max_expression_nodes: usize, // syntax nodes in the largest statement-level expression
+    #[serde(default)]
+    max_expression_line: usize,
+    nloc: usize,   // non-blank, non-comment lines
+    tokens: usize, // lexical tokens, as lizard counts them
+    params: usize,
+    quote_blocks: usize,         // `quote!`-family invocations
+    quote_tokens: usize,         // tokens inside them, i.e. generated-code size
+    lifetime_params: usize,      // declared `<'a>` parameters
+    lifetime_annotations: usize, // lifetimes written anywhere in the fn
+    #[serde(default)]
+    is_const: bool, // `const fn`, see constfn.rs
+    #[serde(default)]
+    lints: usize, // clippy lints inside the fn, see --clippy-json
+    #[serde(default)]
+    coverage: Option<f64>, // % of instrumented lines run, see --coverage
+    #[serde(default)]
+    mutation_score: Option<f64>, // % of mutants caught, see --mutants
+    #[serde(default)]
+    maintainability: f64, // 0-100, see grades.rs
+    #[serde(default)]
+    grade: String, // A-F from `maintainability`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    first_seen: Option<String>, // RFC 3339, from the history store, see age.rs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    allows: Vec<String>, // rules silenced by `// codehealth-allow:` comments
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    derived: BTreeMap<String, f64>, // config-defined metrics, see derived.rs
+    #[serde(skip)]
+    conditions: Vec<(usize, usize, String)>, // (line, operands, shape) of each boolean chain
+    #[serde(skip)]
+    calls: Vec<String>, // callee names, one per call site
+    #[serde(skip)]
+    own_module_calls: usize, // resolved calls within the function's module
+    #[serde(skip)]
+    foreign_module_calls: BTreeMap<String, usize>, // other module -> calls into it
 }
 
-#[derive(Serialize, Clone, Default)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 struct FileMetrics {
     file: String,
+    crate_name: String,
+    module: String,
+    target_kind: String,
+    proc_macro: bool,
+    #[serde(default)]
+    vendored: bool, // under vendor/, third_party/ or a [patch] path
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    root: String, // the --path it was found under, with several
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    component: String, // from `[components]` path globs
+    #[serde(default)]
+    maintainability: f64, // LOC-weighted over its functions, see grades.rs
+    #[serde(default)]
+    grade: String, // empty for files without functions
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    included_by: String, // the file whose `include!` compiles this one
+    loc: usize,
+    nloc: usize,
+    items: usize, // top-level items
+    #[serde(default)]
+    item_counts: items::ItemCounts, // by kind, see items.rs
+    #[serde(default)]
+    risk: usize, // summed function risk, see symbols.rs
     total_complexity: usize,
+    commented_out_code: usize,
+    box_dyn_error: usize,
+    anyhow_uses: usize,
     functions: Vec<FunctionMetric>,
+    types: Vec<lifetimes::TypeLifetimes>,
+    classes: Vec<classes::TypeMetrics>, // impl-level metrics per type
+    macros: Vec<macros::MacroMetrics>,  // `macro_rules!` definitions
+    magic_values: Vec<magic::MagicValue>,
+    hygiene: hygiene::Hygiene,
+    #[serde(default)]
+    security: security::SecurityCounts,
+    #[serde(default)]
+    ffi: ffi::FileFfi,
+    #[serde(default)]
+    includes: Vec<includes::Include>, // `include!`-family macros, see includes.rs
+    #[serde(default)]
+    tests: Vec<tests::TestMetric>, // `#[test]` functions at any depth
+    #[serde(default)]
+    lints: BTreeMap<String, usize>, // clippy lint -> count
+    #[serde(skip)]
+    api_items: Vec<api::ApiItem>,
+    #[serde(skip)]
+    references: BTreeMap<String, usize>, // name -> uses in this file
+    #[serde(skip)]
+    mod_decls: Vec<modtree::ModDecl>, // `mod foo;` declarations, see modtree.rs
+    #[serde(skip)]
+    timing: profile::FileTiming,
+    #[serde(skip)]
+    parse_error: Option<String>, // reported as a `parse-error` diagnostic
 }
 
 // --- Report structure for JSON export ---
 #[derive(Serialize)]
 struct Report {
+    meta: meta::Meta,
+    complexity_model: ComplexityModel,
     metrics: CodeMetrics,
     maintainability_index: f64,
+    grades: grades::Grades,
     files: Vec<FileMetrics>,
     top_functions: Vec<FunctionMetric>,
+    findings: Vec<Finding>,
+    suppressed_findings: Vec<Finding>,
+    expired_debt: Vec<rules::ExpiredDebt>, // suppressions past their date
+    rule_statistics: Vec<rules::RuleStats>,
+    gate_failures: Vec<GateFailure>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    changed_functions: Vec<changerisk::ChangedFunction>, // only with --new-code-since
+    error_handling: Vec<error_handling::CrateErrorHandling>,
+    oversized_files: Vec<OversizedFile>,
+    orphan_files: Vec<String>, // crate files no `mod` declaration reaches
+    auto_thresholds: Option<outliers::AutoThresholds>, // only with --auto-threshold
+    includes: Vec<includes::IncludeSite>, // largest first
+    function_distributions: distribution::FunctionDistributions,
+    lifetime_density: Vec<lifetimes::ModuleLifetimes>,
+    compile_time: Option<constfn::CompileTime>, // only when there is a `const fn`
+    public_api: Vec<api::CrateApi>,
+    public_symbols: Vec<symbols::SymbolReferences>,
+    magic_values: Vec<magic::FileMagicValues>,
+    directory_tree: treemap::TreeNode, // built once paths are canonical
+    macro_definitions: Vec<macros::MacroMetrics>, // largest first
+    hygiene: hygiene::HygieneSummary,
+    security_surface: security::SecuritySurface,
+    test_quality: tests::TestQuality,
+    ffi_surface: Vec<ffi::CrateFfi>, // crates with extern blocks or exports
+    vendored: vendor::VendoredSummary,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    roots: Vec<roots::RootSummary>, // only with several --path roots
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    components: Vec<components::ComponentSummary>, // only with `[components]`
+    derived_metrics: Vec<derived::DerivedSummary>,
+    risk_ranking: Vec<symbols::RiskEntry>,
+    class_metrics: Vec<classes::TypeMetrics>,
+    lints: Vec<clippy::FileLints>,
+    format_drift: Option<format::FormatDrift>, // only with --check-format
+    dangerous_functions: Vec<coverage::DangerousFunction>, // only with --coverage
+    weakly_tested: Vec<mutants::WeaklyTested>, // only with --mutants
+    compile_time_debt: Vec<buildtime::CompileDebt>, // only with --build-timings
+    debt_velocity: Option<velocity::Velocity>, // only with a history store
+    orphaned_hotspots: Option<ownership::OrphanedHotspots>, // only with --orphaned-hotspots
+    cfg_matrix: Option<cfgmatrix::CfgMatrix>,  // only with --cfg-matrix
+    doc_code: Option<doccode::DocCode>,        // None with --exclude-doc-code
+    self_profile: Option<profile::SelfProfile>, // only with --profile-self
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shard: Option<shard::Partial>, // only with --shard, input to `merge --shards`
+    sample: Option<sample::SampleSummary>,     // only with --sample
+    diagnostics: Vec<Diagnostic>,
+    skipped_binary_files: usize,
+}
+
+// --- File exceeding the per-file LOC or item-count limit ---
+#[derive(Serialize, Clone)]
+struct OversizedFile {
+    file: String,
+    module: String,
+    loc: usize,
+    items: usize,
+    item_counts: items::ItemCounts,
+    reasons: Vec<String>,
+}
+
+// --- Multi-target report for `run` ---
+#[derive(Serialize)]
+struct MultiTargetReport {
+    targets: BTreeMap<String, Report>,
+    totals: CodeMetrics,
+}
+
+// --- Exit codes, also listed in `--help` ---
+const EXIT_OK: i32 = 0;
+const EXIT_ERROR: i32 = 1; // bad usage, unreadable input, internal error
+const EXIT_GATE_FAILURE: i32 = 2;
+const EXIT_PARTIAL: i32 = 3; // no gate failed, but some files were not (fully) analyzed
+
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  analysis complete, all gates passed
+  1  tool error (bad arguments or config, unreadable input, internal error)
+  2  one or more gates failed
+  3  gates passed but the analysis was partial (files unparsable, unreadable, skipped or timed out)";
+
+// Diagnostic kinds that mean a file's metrics are missing or incomplete.
+const PARTIAL_KINDS: [&str; 4] = ["timeout", "skipped", "unreadable", "parse-error"];
+
+fn partial_files(report: &Report) -> usize {
+    report
+        .diagnostics
+        .iter()
+        .filter(|d| PARTIAL_KINDS.contains(&d.kind.as_str()))
+        .count()
+}
+
+// Gate failures win over partial analysis: they hold whatever was missed.
+fn exit_code(reports: &[&Report], enforce_gates: bool) -> i32 {
+    let partial: usize = reports.iter().map(|r| partial_files(r)).sum();
+    if enforce_gates && reports.iter().any(|r| !r.gate_failures.is_empty()) {
+        EXIT_GATE_FAILURE
+    } else if partial > 0 {
+        logging::warn(
+            &format!(
+                "analysis incomplete: {} file(s) not fully analyzed, see diagnostics",
+                partial
+            ),
+            &[("partial_files", json!(partial))],
+        );
+        EXIT_PARTIAL
+    } else {
+        EXIT_OK
+    }
+}
+
+// --- Files skipped or only partly understood by the analysis ---
+#[derive(Serialize, Deserialize, Clone)]
+struct Diagnostic {
+    file: String,
+    kind: String, // "timeout", "skipped", "binary", "unreadable", "parse-error", "lossy-utf8", "bom" or "rustfmt"
+    message: String,
+}
+
+fn diagnostic(file: &str, kind: &str, message: String) -> Diagnostic {
+    logging::warn(
+        &format!("{}: {}", file, message),
+        &[("file", json!(file)), ("kind", json!(kind))],
+    );
+    Diagnostic {
+        file: file.to_string(),
+        kind: kind.to_string(),
+        message,
+    }
+}
+
+// --- Rule violations flagged against configurable limits ---
+#[derive(Serialize, Clone, Default)]
+struct Finding {
+    rule: String,
+    file: String,
+    function: String,
+    line: usize,
+    message: String,
+    fingerprint: String, // stable id, assigned once paths are canonical
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<String>, // `--with-snippets` source excerpt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_seen: Option<String>, // YYYY-MM-DD the function first appeared
+    #[serde(skip_serializing_if = "Option::is_none")]
+    age_days: Option<u64>,
+    #[serde(skip)]
+    context: String, // tells apart findings of one rule in one function, see fingerprint.rs
+}
+
+impl Finding {
+    // Fingerprint, snippet and age are filled in once the report is complete.
+    fn new(rule: &str, file: &str, function: &str, line: usize, message: String) -> Finding {
+        Finding {
+            rule: rule.to_string(),
+            file: file.to_string(),
+            function: function.to_string(),
+            line,
+            message,
+            ..Finding::default()
+        }
+    }
+
+    fn with_context(mut self, context: &str) -> Finding {
+        self.context = context.to_string();
+        self
+    }
+}
+
+// --- CI/CD gate that failed (drives exit code 2) ---
+#[derive(Serialize, Clone)]
+struct GateFailure {
+    gate: String,
+    value: usize,
+    threshold: usize,
+    location: String,
+    message: String,
 }
 
 // --- Cyclomatic complexity visitor ---
 struct CyclomaticComplexityVisitor {
+    model: ComplexityModel,
     complexity: usize,
     max_nesting: usize,
     current_nesting: usize,
 }
 
 impl CyclomaticComplexityVisitor {
-    fn new() -> Self {
+    fn new(model: ComplexityModel) -> Self {
         Self {
+            model,
             complexity: 1,
             max_nesting: 0,
             current_nesting: 0,
         }
     }
+
+    // Branch-based models count expressions wherever they appear.
+    fn counts_branches(&self) -> bool {
+        self.model != ComplexityModel::Statements
+    }
 }
 
 impl<'ast> Visit<'ast> for CyclomaticComplexityVisitor {
+    fn visit_expr_if(&mut self, node: &'ast syn::ExprIf) {
+        if self.counts_branches() {
+            self.complexity += 1;
+        }
+        syn::visit::visit_expr_if(self, node);
+    }
+
+    fn visit_expr_while(&mut self, node: &'ast syn::ExprWhile) {
+        if self.counts_branches() {
+            self.complexity += 1;
+        }
+        syn::visit::visit_expr_while(self, node);
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        if self.counts_branches() {
+            self.complexity += 1;
+        }
+        syn::visit::visit_expr_for_loop(self, node);
+    }
+
+    fn visit_expr_match(&mut self, node: &'ast syn::ExprMatch) {
+        let guards = node.arms.iter().filter(|a| a.guard.is_some()).count();
+        self.complexity += match self.model {
+            ComplexityModel::Statements => 0,
+            ComplexityModel::Modified => 1 + guards,
+            ComplexityModel::Mccabe | ComplexityModel::Strict => {
+                node.arms.len().saturating_sub(1) + guards
+            }
+        };
+        syn::visit::visit_expr_match(self, node);
+    }
+
+    fn visit_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
+        if self.model == ComplexityModel::Strict && is_logical(&node.op) {
+            self.complexity += 1;
+        }
+        syn::visit::visit_expr_binary(self, node);
+    }
+
     fn visit_stmt(&mut self, stmt: &'ast Stmt) {
-        if matches!(stmt, Stmt::Expr(expr, _) if matches!(
-            expr,
-            syn::Expr::If(_) | syn::Expr::Match(_) | syn::Expr::While(_) | syn::Expr::ForLoop(_)
-        )) {
+        if !self.counts_branches()
+            && matches!(stmt, Stmt::Expr(expr, _) if matches!(
+                expr,
+                syn::Expr::If(_) | syn::Expr::Match(_) | syn::Expr::While(_) | syn::Expr::ForLoop(_)
+            ))
+        {
             self.complexity += 1;
         }
 
@@ -88,218 +508,4463 @@ impl<'ast> Visit<'ast> for CyclomaticComplexityVisitor {
     }
 }
 
+// --- Exit point visitor (return + `?`, not descending into closures/items) ---
+#[derive(Default)]
+struct ExitPointVisitor {
+    exit_points: usize,
+}
+
+impl<'ast> Visit<'ast> for ExitPointVisitor {
+    fn visit_expr_return(&mut self, node: &'ast syn::ExprReturn) {
+        self.exit_points += 1;
+        syn::visit::visit_expr_return(self, node);
+    }
+
+    fn visit_expr_try(&mut self, node: &'ast syn::ExprTry) {
+        self.exit_points += 1;
+        syn::visit::visit_expr_try(self, node);
+    }
+
+    // A `return` inside a closure or nested fn exits that, not the function.
+    fn visit_expr_closure(&mut self, _: &'ast syn::ExprClosure) {}
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
+// --- Boolean condition visitor (operands per `&&`/`||` chain) ---
+#[derive(Default)]
+struct ConditionVisitor {
+    conditions: Vec<(usize, usize, String)>,
+}
+
+fn is_logical(op: &syn::BinOp) -> bool {
+    matches!(op, syn::BinOp::And(_) | syn::BinOp::Or(_))
+}
+
+// Leaves of a `&&`/`||` chain; parentheses don't start a new condition.
+fn condition_operands<'a>(expr: &'a syn::Expr, out: &mut Vec<&'a syn::Expr>) {
+    match expr {
+        syn::Expr::Binary(bin) if is_logical(&bin.op) => {
+            condition_operands(&bin.left, out);
+            condition_operands(&bin.right, out);
+        }
+        syn::Expr::Paren(paren) => condition_operands(&paren.expr, out),
+        _ => out.push(expr),
+    }
+}
+
+impl<'ast> Visit<'ast> for ConditionVisitor {
+    fn visit_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
+        if !is_logical(&node.op) {
+            return syn::visit::visit_expr_binary(self, node);
+        }
+        let mut operands = Vec::new();
+        condition_operands(&node.left, &mut operands);
+        condition_operands(&node.right, &mut operands);
+        // The shape, a hash of the chain's tokens, identifies it regardless of
+        // where it sits or how it is formatted.
+        let shape = fingerprint::fnv1a(&node.to_token_stream().to_string());
+        self.conditions.push((
+            node.span().start().line,
+            operands.len(),
+            format!("{:016x}", shape),
+        ));
+        // Closures or blocks inside an operand may hold their own chains.
+        for operand in operands {
+            self.visit_expr(operand);
+        }
+    }
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
+// --- Code-generation visitor (`quote!` blocks in proc-macro code) ---
+#[derive(Default)]
+struct QuoteVisitor {
+    blocks: usize,
+    tokens: usize,
+}
+
+impl<'ast> Visit<'ast> for QuoteVisitor {
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        let name = node.path.segments.last().map(|s| s.ident.to_string());
+        if matches!(
+            name.as_deref(),
+            Some("quote" | "quote_spanned" | "parse_quote" | "parse_quote_spanned")
+        ) {
+            self.blocks += 1;
+            self.tokens += lizard::token_count(node.tokens.clone());
+        }
+    }
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
+// --- Commented-out code detection ---
+// Consecutive `//` lines (doc comments excluded) form a block. Within a block
+// we greedily take the longest run of lines that parses as Rust statements,
+// so prose wrapped around a commented-out snippet doesn't hide it.
+const MAX_COMMENTED_RUN: usize = 40;
+
+fn count_commented_out_code(content: &str) -> usize {
+    let mut total = 0;
+    let mut block: Vec<&str> = Vec::new();
+
+    for line in content.lines().chain(std::iter::once("")) {
+        let trimmed = line.trim_start();
+        let is_plain_comment =
+            trimmed.starts_with("//") && !trimmed.starts_with("///") && !trimmed.starts_with("//!");
+        if is_plain_comment {
+            block.push(trimmed.trim_start_matches('/').trim());
+            continue;
+        }
+        total += commented_code_lines(&block);
+        block.clear();
+    }
+
+    total
+}
+
+fn commented_code_lines(block: &[&str]) -> usize {
+    let mut count = 0;
+    let mut start = 0;
+    while start < block.len() {
+        let longest = (start + 1..=block.len().min(start + MAX_COMMENTED_RUN))
+            .rev()
+            .find(|&end| looks_like_code(&block[start..end].join("\n")));
+        match longest {
+            Some(end) => {
+                count += end - start;
+                start = end;
+            }
+            None => start += 1,
+        }
+    }
+    count
+}
+
+fn looks_like_code(text: &str) -> bool {
+    // Bare words like `TODO` parse as path expressions; require punctuation.
+    text.contains([';', '{', '}', '=', '('])
+        && syn::parse_str::<syn::Block>(&format!("{{\n{}\n}}", text)).is_ok()
+}
+
 // --- Analyze a single file ---
-fn analyze_file(file_path: &Path) -> (CodeMetrics, FileMetrics) {
+// Metrics plus non-fatal decoding warnings for one file.
+type FileAnalysis = (CodeMetrics, FileMetrics, Vec<Diagnostic>);
+
+// Binary and unreadable files are skipped with a diagnostic; a BOM is
+// dropped and invalid UTF-8 decoded lossily, both noted as warnings. With
+// `cfg` features, code their `#[cfg]`s switch off is left out.
+fn analyze_file(
+    file_path: &Path,
+    model: ComplexityModel,
+    cfg: Option<&BTreeSet<String>>,
+) -> Result<FileAnalysis, Diagnostic> {
+    let file = file_path.to_string_lossy().to_string();
+    let (bytes, read_ms) = profile::timed(|| fs::read(file_path));
+    let bytes =
+        bytes.map_err(|e| diagnostic(&file, "unreadable", format!("cannot read: {}", e)))?;
+    if bytes.contains(&0) {
+        return Err(diagnostic(
+            &file,
+            "binary",
+            "binary content, file skipped".to_string(),
+        ));
+    }
+
+    let mut warnings = Vec::new();
+    let body = match bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        Some(rest) => {
+            warnings.push(diagnostic(&file, "bom", "UTF-8 BOM ignored".to_string()));
+            rest
+        }
+        None => &bytes[..],
+    };
+    let content = String::from_utf8_lossy(body);
+    if let std::borrow::Cow::Owned(_) = content {
+        warnings.push(diagnostic(
+            &file,
+            "lossy-utf8",
+            "invalid UTF-8 replaced with U+FFFD".to_string(),
+        ));
+    }
+
+    let stripped = cfg.and_then(|features| cfgmatrix::strip(&content, features));
+    let content = stripped.as_deref().unwrap_or(&content);
+    let (metrics, mut detail) = analyze_source(content, &file, model);
+    detail.timing.read_ms = read_ms;
+    if let Some(err) = &detail.parse_error {
+        warnings.push(diagnostic(
+            &file,
+            "parse-error",
+            format!(
+                "cannot parse, only line-based metrics are included: {}",
+                err
+            ),
+        ));
+    }
+    Ok((metrics, detail, warnings))
+}
+
+fn analyze_source(content: &str, file: &str, model: ComplexityModel) -> (CodeMetrics, FileMetrics) {
     let mut metrics = CodeMetrics::default();
     let mut file_detail = FileMetrics {
-        file: file_path.to_string_lossy().to_string(),
-        total_complexity: 0,
-        functions: Vec::new(),
+        file: file.to_string(),
+        ..Default::default()
     };
 
-    if let Ok(content) = fs::read_to_string(file_path) {
-        metrics.loc = content.lines().count();
-        metrics.comments = content
-            .lines()
-            .filter(|line| line.trim_start().starts_with("//"))
-            .count();
+    metrics.loc = content.lines().count();
+    file_detail.loc = metrics.loc;
+    file_detail.nloc = lizard::nloc(content.lines());
+    metrics.comment_lines = comments::classify(content);
+    metrics.comments = metrics.comment_lines.doc + metrics.comment_lines.inline;
+    metrics.commented_out_code = count_commented_out_code(content);
+    file_detail.commented_out_code = metrics.commented_out_code;
+    file_detail.hygiene = hygiene::scan(content);
 
-        if let Ok(syntax) = syn::parse_file(&content) {
-            for item in syntax.items {
-                if let syn::Item::Fn(func) = item {
-                    metrics.functions += 1;
+    let (parsed, parse_ms) = profile::timed(|| syn::parse_file(content));
+    file_detail.timing.parse_ms = parse_ms;
+    let visit_start = Instant::now();
+    if let Err(err) = &parsed {
+        file_detail.parse_error = Some(err.to_string());
+    }
 
-                    let function_loc = func.block.stmts.len();
-                    metrics.longest_function_loc =
-                        metrics.longest_function_loc.max(function_loc);
+    let lines: Vec<&str> = content.lines().collect();
+    if let Ok(syntax) = parsed {
+        let mut error_types = error_handling::ErrorTypeVisitor::default();
+        error_types.visit_file(&syntax);
+        file_detail.box_dyn_error = error_types.box_dyn_error;
+        file_detail.anyhow_uses = error_types.anyhow_uses;
+        file_detail.items = syntax.items.len();
+        file_detail.item_counts = items::count(&syntax);
+        file_detail.types = lifetimes::type_lifetimes(&syntax);
+        file_detail.classes = classes::type_metrics(&syntax, file, model);
+        let mut macro_defs = macros::MacroVisitor {
+            file,
+            macros: Vec::new(),
+        };
+        macro_defs.visit_file(&syntax);
+        file_detail.macros = macro_defs.macros;
+        file_detail.api_items = api::api_items(&syntax);
+        let mut references = symbols::ReferenceVisitor::default();
+        references.visit_file(&syntax);
+        file_detail.references = references.references;
+        file_detail.mod_decls = modtree::declarations(&syntax);
+        file_detail.security = security::count(&syntax);
+        file_detail.ffi = ffi::scan(&syntax);
+        file_detail.includes = includes::scan(&syntax);
+        file_detail.tests = tests::scan(&syntax);
 
-                    let mut visitor = CyclomaticComplexityVisitor::new();
-                    visitor.visit_item_fn(&func);
+        for item in syntax.items {
+            if let syn::Item::Fn(func) = item {
+                metrics.functions += 1;
 
-                    metrics.cyclomatic_complexity += visitor.complexity;
-                    metrics.max_nesting_depth =
-                        metrics.max_nesting_depth.max(visitor.max_nesting);
+                let function_loc = func.block.stmts.len();
+                metrics.longest_function_loc = metrics.longest_function_loc.max(function_loc);
 
-                    match visitor.complexity {
-                        0..=5 => metrics.cyclomatic_distribution[0] += 1,
-                        6..=10 => metrics.cyclomatic_distribution[1] += 1,
-                        _ => metrics.cyclomatic_distribution[2] += 1,
-                    }
+                let mut visitor = CyclomaticComplexityVisitor::new(model);
+                visitor.visit_item_fn(&func);
 
-                    // Add per-function record
-                    file_detail.total_complexity += visitor.complexity;
-                    let fname = func.sig.ident.to_string();
-                    file_detail.functions.push(FunctionMetric {
-                        file: file_detail.file.clone(),
-                        function: fname,
-                        complexity: visitor.complexity,
-                        loc: function_loc,
-                    });
-                }
+                metrics.cyclomatic_complexity += visitor.complexity;
+                metrics.max_nesting_depth = metrics.max_nesting_depth.max(visitor.max_nesting);
+
+                let mut exits = ExitPointVisitor::default();
+                exits.visit_block(&func.block);
+
+                let mut errors = error_handling::FunctionErrorVisitor::default();
+                errors.visit_block(&func.block);
+
+                let mut conditions = ConditionVisitor::default();
+                conditions.visit_block(&func.block);
+
+                let mut cognitive = cognitive::CognitiveVisitor::default();
+                cognitive.visit_block(&func.block);
+
+                let (chain, chain_start, chain_end) = chains::longest(&func.block);
+                let (expression_nodes, expression_line) = exprsize::largest(&func.block);
+
+                let mut calls = callgraph::CallVisitor::default();
+                calls.visit_block(&func.block);
+
+                let fname = func.sig.ident.to_string();
+                let mut magic_values = magic::MagicValueVisitor {
+                    function: &fname,
+                    values: Vec::new(),
+                };
+                magic_values.visit_block(&func.block);
+                file_detail.magic_values.extend(magic_values.values);
+
+                let mut quotes = QuoteVisitor::default();
+                quotes.visit_block(&func.block);
+
+                let mut lifetime_use = lifetimes::LifetimeVisitor::default();
+                lifetime_use.visit_item_fn(&func);
+
+                // Add per-function record
+                file_detail.total_complexity = file_detail
+                    .total_complexity
+                    .saturating_add(visitor.complexity);
+                file_detail.functions.push(FunctionMetric {
+                    file: file_detail.file.clone(),
+                    function: fname,
+                    complexity: visitor.complexity,
+                    cognitive_complexity: cognitive.score,
+                    is_recursive: false,
+                    recursion_cycle: 0,
+                    loc: function_loc,
+                    nesting: visitor.max_nesting,
+                    exit_points: exits.exit_points,
+                    line: func.span().start().line,
+                    end_line: func.span().end().line,
+                    returns_result: error_handling::returns_result(&func.sig),
+                    try_ops: errors.try_ops,
+                    unwraps: errors.unwraps,
+                    max_condition_operands: conditions
+                        .conditions
+                        .iter()
+                        .map(|c| c.1)
+                        .max()
+                        .unwrap_or(0),
+                    conditions: conditions.conditions,
+                    max_call_chain: chain,
+                    call_chain_lines: (chain_start, chain_end),
+                    max_expression_nodes: expression_nodes,
+                    max_expression_line: expression_line,
+                    lifetime_params: lifetime_use.params,
+                    lifetime_annotations: lifetime_use.annotations,
+                    is_const: func.sig.constness.is_some(),
+                    lints: 0,
+                    coverage: None,
+                    mutation_score: None,
+                    maintainability: 0.0,
+                    grade: String::new(),
+                    first_seen: None,
+                    allows: rules::function_allows(
+                        &lines,
+                        func.span().start().line,
+                        func.span().end().line,
+                    ),
+                    nloc: lizard::nloc(
+                        content
+                            .lines()
+                            .skip(func.span().start().line.saturating_sub(1))
+                            .take(func.span().end().line + 1 - func.span().start().line),
+                    ),
+                    tokens: lizard::token_count(func.to_token_stream()),
+                    params: func.sig.inputs.len(),
+                    quote_blocks: quotes.blocks,
+                    quote_tokens: quotes.tokens,
+                    calls: calls.calls,
+                    own_module_calls: 0,
+                    foreign_module_calls: BTreeMap::new(),
+                    derived: BTreeMap::new(),
+                });
             }
         }
     }
 
+    metrics.max_file_complexity = metrics.cyclomatic_complexity;
+    metrics.file_with_max_complexity = file_detail.file.clone();
+
+    file_detail.timing.visit_ms = visit_start.elapsed().as_secs_f64() * 1000.0;
     (metrics, file_detail)
 }
 
 // --- Analyze a directory ---
-fn calculate_metrics(dir: &str) -> (CodeMetrics, Vec<FileMetrics>, Vec<FunctionMetric>) {
+// Patterns may use either separator; case is folded where the OS does.
+fn is_excluded(path: &Path, root: &Path, excludes: &[String]) -> bool {
+    let rel = paths::strip_prefix(path, root).unwrap_or_else(|| path.to_path_buf());
+    let rel = paths::fold_case(&paths::slash(&rel));
+    excludes.iter().any(|pattern| {
+        let pattern = paths::fold_case(&pattern.replace('\\', "/"));
+        let pattern = pattern.trim_end_matches('/');
+        rel == pattern
+            || rel.starts_with(&format!("{}/", pattern))
+            || (!pattern.contains('/') && rel.split('/').any(|c| c == pattern))
+    })
+}
+
+// Fold one file's (or one target's) metrics into a running total.
+// Sums saturate: a huge scan should pin a counter, not wrap or panic.
+fn merge_metrics(total: &mut CodeMetrics, fm: &CodeMetrics) {
+    let add = |t: &mut usize, n: usize| *t = t.saturating_add(n);
+    add(&mut total.loc, fm.loc);
+    add(&mut total.cyclomatic_complexity, fm.cyclomatic_complexity);
+    add(&mut total.functions, fm.functions);
+    add(&mut total.comments, fm.comments);
+    add(&mut total.comment_lines.doc, fm.comment_lines.doc);
+    add(&mut total.comment_lines.inline, fm.comment_lines.inline);
+    add(&mut total.comment_lines.header, fm.comment_lines.header);
+    add(&mut total.commented_out_code, fm.commented_out_code);
+    total.longest_function_loc = total.longest_function_loc.max(fm.longest_function_loc);
+    total.max_nesting_depth = total.max_nesting_depth.max(fm.max_nesting_depth);
+
+    if total.cyclomatic_distribution.len() < fm.cyclomatic_distribution.len() {
+        total
+            .cyclomatic_distribution
+            .resize(fm.cyclomatic_distribution.len(), 0);
+        total.cyclomatic_distribution_bounds = fm.cyclomatic_distribution_bounds.clone();
+    }
+    for (t, n) in total
+        .cyclomatic_distribution
+        .iter_mut()
+        .zip(&fm.cyclomatic_distribution)
+    {
+        add(t, *n);
+    }
+
+    if fm.max_file_complexity > total.max_file_complexity {
+        total.max_file_complexity = fm.max_file_complexity;
+        total.file_with_max_complexity = fm.file_with_max_complexity.clone();
+    }
+}
+
+// `.rs` files under each root, paired with the root they were found under.
+fn rust_files(roots: &[String], excludes: &[String]) -> Vec<(PathBuf, PathBuf)> {
+    files_with_extension(roots, excludes, "rs")
+}
+
+// An explicit file list (e.g. `git diff --name-only`), narrowed to existing
+// files with `extension` that are not excluded. Each is attributed to the
+// first scan root containing it, for module paths outside any crate.
+fn listed_files(
+    list: &[String],
+    roots: &[String],
+    excludes: &[String],
+    extension: &str,
+) -> Vec<(PathBuf, PathBuf)> {
+    let roots: Vec<(PathBuf, PathBuf)> = roots
+        .iter()
+        .filter_map(|r| Some((PathBuf::from(r), lexical_absolute(r).ok()?)))
+        .collect();
+    let mut found: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    for entry in list {
+        let path = PathBuf::from(entry);
+        if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some(extension) {
+            continue;
+        }
+        let abs = lexical_absolute(entry).unwrap_or_else(|_| path.clone());
+        let (root, abs_root) = roots
+            .iter()
+            .find(|(_, abs_root)| paths::strip_prefix(&abs, abs_root).is_some())
+            .cloned()
+            .unwrap_or_else(|| (PathBuf::from("."), PathBuf::new()));
+        if !is_excluded(&abs, &abs_root, excludes) && seen.insert(paths::key(&abs)) {
+            found.push((path, root));
+        }
+    }
+    found
+}
+
+// Either the explicit list or every matching file under the roots.
+fn source_files(opts: &AnalysisOptions, extension: &str) -> Vec<(PathBuf, PathBuf)> {
+    let mut files = match &opts.files_from {
+        Some(list) => listed_files(list, &opts.roots, &opts.excludes, extension),
+        None => files_with_extension(&opts.roots, &opts.excludes, extension),
+    };
+    if let Some(shard) = opts.shard {
+        files.retain(|(path, root)| shard.selects(path, root));
+    }
+    files
+}
+
+fn files_with_extension(
+    roots: &[String],
+    excludes: &[String],
+    extension: &str,
+) -> Vec<(PathBuf, PathBuf)> {
+    let mut found = Vec::new();
+    // Nested or repeated roots (or one root spelled two ways) would
+    // otherwise yield a file twice.
+    let mut seen: HashSet<String> = HashSet::new();
+    for root in roots {
+        let root = Path::new(root);
+        let walker = WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|e| !is_excluded(e.path(), root, excludes));
+        for entry in walker.filter_map(Result::ok) {
+            let path = entry.path();
+            let abs = lexical_absolute(&path.to_string_lossy());
+            if path.is_file()
+                && path.extension().and_then(|s| s.to_str()) == Some(extension)
+                && seen.insert(paths::key(&abs.unwrap_or_else(|_| path.to_path_buf())))
+            {
+                found.push((path.to_path_buf(), root.to_path_buf()));
+            }
+        }
+    }
+    found
+}
+
+// Per-file and overall time limits. A parse can't be interrupted, so a file
+// over budget is left running on its worker thread and reported as timed out.
+#[derive(Clone, Copy, Default)]
+struct TimeBudget {
+    per_file: Option<Duration>,
+    deadline: Option<Instant>,
+}
+
+impl TimeBudget {
+    fn is_unlimited(&self) -> bool {
+        self.per_file.is_none() && self.deadline.is_none()
+    }
+
+    // Time allowed for the next file, or `None` once the deadline has passed.
+    fn next_file(&self) -> Option<Duration> {
+        let remaining = self
+            .deadline
+            .map(|d| d.saturating_duration_since(Instant::now()));
+        match (self.per_file, remaining) {
+            (_, Some(r)) if r.is_zero() => None,
+            (Some(p), Some(r)) => Some(p.min(r)),
+            (Some(p), None) => Some(p),
+            (None, Some(r)) => Some(r),
+            (None, None) => Some(Duration::MAX),
+        }
+    }
+}
+
+fn analyze_file_within(
+    path: &Path,
+    model: ComplexityModel,
+    cfg: Option<&BTreeSet<String>>,
+    budget: &TimeBudget,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<FileAnalysis> {
+    let outcome = |result: Result<FileAnalysis, Diagnostic>, diagnostics: &mut Vec<Diagnostic>| {
+        result.map_err(|d| diagnostics.push(d)).ok()
+    };
+    if budget.is_unlimited() {
+        return outcome(analyze_file(path, model, cfg), diagnostics);
+    }
+    let file = path.to_string_lossy().to_string();
+    let Some(limit) = budget.next_file() else {
+        diagnostics.push(Diagnostic {
+            file,
+            kind: "skipped".to_string(),
+            message: "overall timeout reached before this file was analyzed".to_string(),
+        });
+        return None;
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let owned = path.to_path_buf();
+    let cfg = cfg.cloned();
+    std::thread::spawn(move || {
+        let _ = tx.send(analyze_file(&owned, model, cfg.as_ref()));
+    });
+    match rx.recv_timeout(limit) {
+        Ok(result) => outcome(result, diagnostics),
+        Err(_) => {
+            let message = format!("analysis did not finish within {:.1?}", limit);
+            diagnostics.push(diagnostic(&file, "timeout", message));
+            None
+        }
+    }
+}
+
+fn calculate_metrics(
+    sources: Vec<(PathBuf, PathBuf)>,
+    opts: &AnalysisOptions,
+    budget: &TimeBudget,
+    diagnostics: &mut Vec<Diagnostic>,
+    mut cache: Option<&mut cache::Cache>,
+    mut tripwire: Option<failfast::Tripwire>,
+    stopwatch: &mut profile::Stopwatch,
+) -> (CodeMetrics, Vec<FileMetrics>) {
     let mut total = CodeMetrics::default();
     let mut files: Vec<FileMetrics> = Vec::new();
-    let mut all_functions: Vec<FunctionMetric> = Vec::new();
-
-    for entry in WalkDir::new(dir).into_iter().filter_map(Result::ok) {
-        let path = entry.path();
-        if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("rs") {
-            let (fm, detail) = analyze_file(path);
-
-            total.loc += fm.loc;
-            total.cyclomatic_complexity += fm.cyclomatic_complexity;
-            total.functions += fm.functions;
-            total.comments += fm.comments;
-            total.longest_function_loc = total.longest_function_loc.max(fm.longest_function_loc);
-            total.max_nesting_depth = total.max_nesting_depth.max(fm.max_nesting_depth);
-
-            for i in 0..3 {
-                total.cyclomatic_distribution[i] += fm.cyclomatic_distribution[i];
-            }
+    let mut resolver = crates::CrateResolver::default();
+    let scheduled = sources.len();
 
-            if fm.cyclomatic_complexity > total.max_file_complexity {
-                total.max_file_complexity = fm.cyclomatic_complexity;
-                total.file_with_max_complexity = detail.file.clone();
-            }
+    for (path, root) in sources {
+        let (version, hit) = match cache.as_deref_mut() {
+            Some(cache) => cache.lookup(&path),
+            None => (String::new(), None),
+        };
+        let result = match hit {
+            Some(result) => Some(result),
+            None => analyze_file_within(
+                &path,
+                opts.complexity_model,
+                opts.cfg_features.as_ref(),
+                budget,
+                diagnostics,
+            ),
+        };
+        let Some((fm, mut detail, warnings)) = result else {
+            continue;
+        };
+        if let Some(cache) = cache.as_deref_mut() {
+            cache.store(&path, version, &fm, &detail, &warnings);
+        }
+        diagnostics.extend(warnings);
+        merge_metrics(&mut total, &fm);
 
-            files.push(detail.clone());
-            all_functions.extend(detail.functions);
+        let location = resolver.locate(&path, &root);
+        detail.crate_name = location.crate_name;
+        detail.module = location.module;
+        detail.target_kind = location.target_kind.to_string();
+        detail.proc_macro = location.proc_macro;
+
+        if let Some(failure) = tripwire.as_mut().and_then(|t| t.check(&detail)) {
+            log_gate_failures(std::slice::from_ref(&failure));
+            logging::warn(
+                &format!(
+                    "fail-fast: stopped after {} of {} files",
+                    files.len() + 1,
+                    scheduled
+                ),
+                &[("gate", json!(failure.gate))],
+            );
+            exit(EXIT_GATE_FAILURE);
         }
+        files.push(detail);
     }
 
     total.kloc = total.loc as f64 / 1000.0;
 
-    all_functions.sort_by(|a, b| b.complexity.cmp(&a.complexity));
-    let top_functions = all_functions.into_iter().take(20).collect();
+    stopwatch.lap("analyze files");
+    (total, files)
+}
 
-    (total, files, top_functions)
+// Cross-file passes, run once every file is parsed; returns the 20 most
+// complex functions.
+fn link_files(files: &mut [FileMetrics]) -> Vec<FunctionMetric> {
+    // Recursion spans files, so it is resolved once everything is parsed.
+    callgraph::mark_recursion(files);
+    callgraph::count_module_calls(files);
+    let mut all_functions: Vec<FunctionMetric> = files
+        .iter()
+        .flat_map(|f| f.functions.iter().cloned())
+        .collect();
+    // Ties broken by location so the cut does not depend on file order.
+    all_functions.sort_by(|a, b| {
+        b.complexity
+            .cmp(&a.complexity)
+            .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+    });
+    all_functions.into_iter().take(20).collect()
 }
 
-// --- Maintainability index ---
-fn calculate_maintainability_index(metrics: &CodeMetrics) -> f64 {
-    if metrics.functions == 0 {
-        return 0.0;
+// --- Complexity distribution buckets ---
+// `bounds` are inclusive upper edges: [5, 10] gives <=5, 6-10 and >10.
+fn bucket_counts(files: &[FileMetrics], bounds: &[usize]) -> Vec<usize> {
+    let mut counts = vec![0; bounds.len() + 1];
+    for f in files.iter().flat_map(|file| &file.functions) {
+        let bucket = bounds
+            .iter()
+            .position(|&b| f.complexity <= b)
+            .unwrap_or(bounds.len());
+        counts[bucket] += 1;
     }
+    counts
+}
 
-    let halstead_volume = (metrics.halstead_unique_operators
-        + metrics.halstead_unique_operands) as f64
-        * ((metrics.halstead_unique_operators + metrics.halstead_unique_operands) as f64).log2();
-    let avg_cyclomatic = metrics.cyclomatic_complexity as f64 / metrics.functions as f64;
-
-    let index = 171.0
-        - 5.2 * halstead_volume.log2()
-        - 0.23 * avg_cyclomatic
-        - 16.2 * (metrics.loc as f64).log2();
-    index.max(0.0).min(100.0)
+fn bucket_labels(bounds: &[usize]) -> Vec<String> {
+    let mut labels = Vec::new();
+    let mut low = 0;
+    for &b in bounds {
+        labels.push(if low == 0 {
+            format!("<={}", b)
+        } else {
+            format!("{}-{}", low, b)
+        });
+        low = b + 1;
+    }
+    labels.push(match bounds.last() {
+        Some(b) => format!(">{}", b),
+        None => "all".to_string(),
+    });
+    labels
 }
 
-// --- MAIN ---
-fn main() {
-    let matches = Command::new("CodeHealth Analyzer")
-        .version("2.0")
-        .author("Your Name <your.email@example.com>")
-        .about("Scans codebases and reports metrics such as cyclomatic complexity, maintainability, and risk factors")
-        .arg(
-            Arg::new("path")
-                .long("path")
-                .default_value(".")
-                .help("Path to the directory or file to analyze"),
-        )
-        .arg(
-            Arg::new("report")
-                .long("report")
-                .value_parser(["text", "json"])
-                .default_value("text")
-                .help("Choose report format"),
-        )
-        .arg(
-            Arg::new("max-complexity")
-                .long("max-complexity")
-                .value_parser(clap::value_parser!(u32))
-                .help("Fail if max cyclomatic complexity exceeds this threshold"),
-        )
-        .get_matches();
+// --- Findings against per-function limits ---
+// Fewer foreign calls than this are never envy, whatever the ratio.
+const FEATURE_ENVY_MIN_CALLS: usize = 3;
 
-    let path = matches.get_one::<String>("path").unwrap();
-    let report = matches.get_one::<String>("report").unwrap();
-    let max_complexity = matches.get_one::<u32>("max-complexity").copied();
+fn collect_findings(files: &[FileMetrics], thresholds: &Thresholds) -> Vec<Finding> {
+    let mut findings = Vec::new();
 
-    let (metrics, files, top_functions) = calculate_metrics(path);
-    let maintainability_index = calculate_maintainability_index(&metrics);
+    for f in files.iter().flat_map(|file| &file.functions) {
+        if let Some(limit) = thresholds.max_exit_points {
+            if f.exit_points > limit {
+                findings.push(Finding::new(
+                    "too-many-exit-points",
+                    &f.file,
+                    &f.function,
+                    f.line,
+                    format!(
+                        "{} exit points (return/?) exceeds limit of {}",
+                        f.exit_points, limit
+                    ),
+                ));
+            }
+        }
+        if let Some(limit) = thresholds.max_call_chain.filter(|&l| f.max_call_chain > l) {
+            let (start, end) = f.call_chain_lines;
+            findings.push(Finding::new(
+                "long-call-chain",
+                &f.file,
+                &f.function,
+                start,
+                format!(
+                    "method-call chain of {} calls (lines {}-{}) exceeds limit of {}",
+                    f.max_call_chain, start, end, limit
+                ),
+            ));
+        }
+        if let Some(limit) = thresholds.max_condition_operands {
+            for (line, operands, shape) in f.conditions.iter().filter(|c| c.1 > limit) {
+                findings.push(
+                    Finding::new(
+                        "complex-conditional",
+                        &f.file,
+                        &f.function,
+                        *line,
+                        format!(
+                            "condition with {} &&/|| operands exceeds limit of {}",
+                            operands, limit
+                        ),
+                    )
+                    .with_context(shape),
+                );
+            }
+        }
+    }
 
-    if report == "json" {
-        let output = Report {
-            metrics: metrics.clone(),
-            maintainability_index,
-            files,
-            top_functions,
-        };
-        println!("{}", serde_json::to_string_pretty(&output).unwrap());
-    } else {
-        println!("Code Metrics:");
-        println!("Lines of Code (LOC): {}", metrics.loc);
-        println!("KLOC: {:.2}", metrics.kloc);
-        println!("Cyclomatic Complexity: {}", metrics.cyclomatic_complexity);
-        println!(
-            "Average Cyclomatic Complexity per Function: {:.2}",
-            metrics.cyclomatic_complexity as f64 / metrics.functions.max(1) as f64
-        );
-        println!(
-            "Cyclomatic Complexity Distribution: [Easy (<=5): {}, Moderate (6-10): {}, High (>10): {}]",
-            metrics.cyclomatic_distribution[0],
-            metrics.cyclomatic_distribution[1],
-            metrics.cyclomatic_distribution[2]
-        );
-        println!("Number of Functions: {}", metrics.functions);
-        println!("Longest Function (LOC): {}", metrics.longest_function_loc);
-        println!("Maximum Nesting Depth: {}", metrics.max_nesting_depth);
-        println!(
-            "Comment Density: {:.2}%",
-            metrics.comments as f64 / metrics.loc.max(1) as f64 * 100.0
-        );
-        println!(
-            "Maintainability Index: {:.2} (0-100)",
-            maintainability_index
-        );
-        println!(
-            "File with Maximum Complexity: {}",
-            metrics.file_with_max_complexity
-        );
-        println!(
-            "Maximum Cyclomatic Complexity in a File: {}",
-            metrics.max_file_complexity
-        );
+    // Feature envy: a function that mostly calls into one other module probably
+    // belongs there.
+    if let Some(ratio) = thresholds.feature_envy_ratio {
+        for f in files.iter().flat_map(|file| &file.functions) {
+            let Some((module, &calls)) = f.foreign_module_calls.iter().max_by_key(|(_, &n)| n)
+            else {
+                continue;
+            };
+            if calls >= FEATURE_ENVY_MIN_CALLS
+                && calls as f64 >= ratio * f.own_module_calls.max(1) as f64
+            {
+                findings.push(Finding::new(
+                    "feature-envy",
+                    &f.file,
+                    &f.function,
+                    f.line,
+                    format!(
+                        "{} calls into {} vs {} within its own module (ratio limit {})",
+                        calls, module, f.own_module_calls, ratio
+                    ),
+                ));
+            }
+        }
+    }
 
-        // --- Top offenders list ---
-        println!("\n⚠️ Top 5 Most Complex Functions:");
-        for (i, f) in top_functions.iter().take(5).enumerate() {
-            println!(
-                "{}. {}::{} → complexity={} LOC={}",
-                i + 1,
-                f.file,
-                f.function,
-                f.complexity,
-                f.loc
-            );
+    // Generated code only matters where it is the product: proc-macro crates.
+    if let Some(limit) = thresholds.max_quote_tokens {
+        let codegen = files
+            .iter()
+            .filter(|file| file.proc_macro)
+            .flat_map(|file| &file.functions);
+        for f in codegen.filter(|f| f.quote_tokens > limit) {
+            findings.push(Finding::new(
+                "giant-codegen-function",
+                &f.file,
+                &f.function,
+                f.line,
+                format!(
+                    "{} tokens across {} quote! block(s) exceeds limit of {}",
+                    f.quote_tokens, f.quote_blocks, limit
+                ),
+            ));
         }
     }
 
-    // Threshold warning for CI/CD
-    if let Some(th) = max_complexity {
-        if metrics.max_file_complexity as u32 > th {
-            eprintln!(
-                "⚠️  Maximum cyclomatic complexity ({}) exceeds threshold ({}).",
-                metrics.max_file_complexity, th
-            );
-            std::process::exit(2);
+    if let Some(limit) = thresholds.max_include_lines {
+        for file in files {
+            for inc in file.includes.iter().filter(|i| i.lines > limit) {
+                findings.push(
+                    Finding::new(
+                        "large-include",
+                        &file.file,
+                        &format!("{}!", inc.kind),
+                        inc.line,
+                        format!(
+                            "{} includes {} lines, exceeding limit of {}",
+                            inc.target, inc.lines, limit
+                        ),
+                    )
+                    .with_context(&inc.target),
+                );
+            }
+        }
+    }
+
+    // A macro has no enclosing function, so `name!` stands in for one.
+    for m in files.iter().flat_map(|file| &file.macros) {
+        let over: Vec<String> = [
+            ("rules", m.rules, thresholds.max_macro_rules),
+            ("depth", m.depth, thresholds.max_macro_depth),
+            ("tokens", m.tokens, thresholds.max_macro_tokens),
+        ]
+        .into_iter()
+        .filter_map(|(what, value, limit)| {
+            let limit = limit.filter(|&l| value > l)?;
+            Some(format!("{} {} > {}", what, value, limit))
+        })
+        .collect();
+        if !over.is_empty() {
+            findings.push(Finding::new(
+                "complex-macro",
+                &m.file,
+                &format!("{}!", m.name),
+                m.line,
+                format!("macro_rules! definition over limits: {}", over.join(", ")),
+            ));
         }
     }
+
+    findings
+}
+
+// --- New-code filter: keep only functions overlapping changed lines ---
+fn new_code_only(files: &[FileMetrics], changed: &git::ChangedLines) -> Vec<FileMetrics> {
+    files
+        .iter()
+        .map(|file| FileMetrics {
+            functions: file
+                .functions
+                .iter()
+                .filter(|f| changed.touches(&f.file, f.line, f.end_line))
+                .cloned()
+                .collect(),
+            macros: file
+                .macros
+                .iter()
+                .filter(|m| changed.touches(&m.file, m.line, m.end_line))
+                .cloned()
+                .collect(),
+            ..file.clone()
+        })
+        .collect()
+}
+
+const DEFAULT_OUTLIER_COMPLEXITY: usize = 10;
+
+// --- Oversized files ---
+fn find_oversized_files(files: &[FileMetrics], thresholds: &Thresholds) -> Vec<OversizedFile> {
+    let mut oversized = Vec::new();
+    for file in files {
+        let mut reasons = Vec::new();
+        if let Some(limit) = thresholds.max_file_loc.filter(|&l| file.loc > l) {
+            reasons.push(format!("{} LOC exceeds limit of {}", file.loc, limit));
+        }
+        if let Some(limit) = thresholds.max_items_per_module.filter(|&l| file.items > l) {
+            reasons.push(format!("{} items exceeds limit of {}", file.items, limit));
+        }
+        if !reasons.is_empty() {
+            oversized.push(OversizedFile {
+                file: file.file.clone(),
+                module: file.module.clone(),
+                loc: file.loc,
+                items: file.items,
+                item_counts: file.item_counts,
+                reasons,
+            });
+        }
+    }
+    oversized
+}
+
+// --- Gates ---
+fn evaluate_gates(
+    files: &[FileMetrics],
+    new_code: Option<&[FileMetrics]>,
+    oversized: &[OversizedFile],
+    thresholds: &Thresholds,
+) -> Vec<GateFailure> {
+    let mut failures = Vec::new();
+
+    // In new-code mode only files with touched functions are size-gated;
+    // otherwise every file in scope (vendored code may be out of it).
+    let touched = |file: &str| match new_code {
+        None => files.iter().any(|f| f.file == file),
+        Some(files) => files
+            .iter()
+            .any(|f| f.file == file && !f.functions.is_empty()),
+    };
+    for o in oversized.iter().filter(|o| touched(&o.file)) {
+        for (gate, value, limit) in [
+            ("max-file-loc", o.loc, thresholds.max_file_loc),
+            (
+                "max-items-per-module",
+                o.items,
+                thresholds.max_items_per_module,
+            ),
+        ] {
+            if let Some(limit) = limit.filter(|&l| value > l) {
+                failures.push(GateFailure {
+                    gate: gate.to_string(),
+                    value,
+                    threshold: limit,
+                    location: o.file.clone(),
+                    message: format!("File exceeds {} ({} > {}).", gate, value, limit),
+                });
+            }
+        }
+    }
+
+    // New-code mode gates per changed function, since file totals mix old and new code.
+    let (max, location) = match new_code {
+        // The first file with the highest total, as merge_metrics picks it.
+        None => files.iter().fold((0, String::new()), |best, f| {
+            if f.total_complexity > best.0 {
+                (f.total_complexity, f.file.clone())
+            } else {
+                best
+            }
+        }),
+        Some(files) => files
+            .iter()
+            .flat_map(|f| &f.functions)
+            .max_by_key(|f| f.complexity)
+            .map(|f| (f.complexity, format!("{}::{}", f.file, f.function)))
+            .unwrap_or_default(),
+    };
+    if let Some(th) = thresholds.max_complexity {
+        if max as u32 > th {
+            failures.push(GateFailure {
+                gate: "max-complexity".to_string(),
+                value: max,
+                threshold: th as usize,
+                location,
+                message: format!(
+                    "Maximum cyclomatic complexity{} ({}) exceeds threshold ({}).",
+                    if new_code.is_some() {
+                        " in new code"
+                    } else {
+                        ""
+                    },
+                    max,
+                    th
+                ),
+            });
+        }
+    }
+
+    // One allowed outlier shouldn't mean unlimited outliers.
+    let level = thresholds
+        .outlier_complexity
+        .unwrap_or(DEFAULT_OUTLIER_COMPLEXITY);
+    let functions: Vec<&FunctionMetric> = new_code
+        .unwrap_or(files)
+        .iter()
+        .flat_map(|f| &f.functions)
+        .collect();
+    let over = functions.iter().filter(|f| f.complexity > level).count();
+    let scope = if new_code.is_some() {
+        "new code"
+    } else {
+        "project"
+    };
+    if let Some(limit) = thresholds.max_functions_over_complexity {
+        if over > limit {
+            failures.push(GateFailure {
+                gate: "max-functions-over-complexity".to_string(),
+                value: over,
+                threshold: limit,
+                location: scope.to_string(),
+                message: format!(
+                    "{} functions exceed complexity {} (allowed: {}).",
+                    over, level, limit
+                ),
+            });
+        }
+    }
+    if let Some(percent) = thresholds.max_percent_functions_over_complexity {
+        // Compared as counts: over / total > percent  <=>  over > floor(total * percent).
+        let allowed = (functions.len() as f64 * percent / 100.0).floor() as usize;
+        if over > allowed {
+            failures.push(GateFailure {
+                gate: "max-percent-functions-over-complexity".to_string(),
+                value: over,
+                threshold: allowed,
+                location: scope.to_string(),
+                message: format!(
+                    "{:.1}% of functions ({} of {}) exceed complexity {} (allowed: {}%).",
+                    over as f64 * 100.0 / functions.len().max(1) as f64,
+                    over,
+                    functions.len(),
+                    level,
+                    percent
+                ),
+            });
+        }
+    }
+
+    failures
+}
+
+fn log_gate_failures(failures: &[GateFailure]) {
+    for f in failures {
+        logging::warn(
+            &format!("{}: {}", f.location, f.message),
+            &[
+                ("gate", json!(f.gate)),
+                ("value", json!(f.value)),
+                ("threshold", json!(f.threshold)),
+                ("file", json!(f.location)),
+            ],
+        );
+    }
+}
+
+// --- Canonical report form: relative '/' paths, stable ordering ---
+// Absolute path with `.`/`..` resolved lexically (no filesystem access, so it
+// also works for `file::function` locations).
+fn lexical_absolute(path: &str) -> std::io::Result<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in paths::strip_verbatim(&std::path::absolute(path)?).components() {
+        match component {
+            std::path::Component::ParentDir => {
+                out.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => out.push(other),
+        }
+    }
+    Ok(out)
+}
+
+fn relative_path(path: &str, base: &str) -> String {
+    let (Ok(abs_path), Ok(abs_base)) = (lexical_absolute(path), lexical_absolute(base)) else {
+        return path.replace('\\', "/");
+    };
+    let rel = match paths::strip_prefix(&abs_path, &abs_base) {
+        // Scanning a single file: keep just its name.
+        Some(rel) if rel.as_os_str().is_empty() => abs_path.file_name().map_or(rel, PathBuf::from),
+        Some(rel) => rel,
+        None => PathBuf::from(path),
+    };
+    paths::slash(&rel)
+}
+
+fn canonicalize_report(report: &mut Report, base: &str) {
+    let rel = |p: &mut String| *p = relative_path(p, base);
+
+    rel(&mut report.metrics.file_with_max_complexity);
+    for file in &mut report.files {
+        rel(&mut file.file);
+        if !file.included_by.is_empty() {
+            rel(&mut file.included_by);
+        }
+        for inc in file.includes.iter_mut().filter(|i| !i.path.is_empty()) {
+            rel(&mut inc.path);
+        }
+        for f in &mut file.functions {
+            rel(&mut f.file);
+        }
+        for c in &mut file.classes {
+            rel(&mut c.file);
+        }
+        for m in &mut file.macros {
+            rel(&mut m.file);
+        }
+        file.functions
+            .sort_by(|a, b| (&a.function, a.line).cmp(&(&b.function, b.line)));
+    }
+    // Module paths are the primary grouping key; files only break ties.
+    report.files.sort_by(|a, b| {
+        (&a.crate_name, &a.module, &a.file).cmp(&(&b.crate_name, &b.module, &b.file))
+    });
+
+    for f in &mut report.top_functions {
+        rel(&mut f.file);
+    }
+    report.top_functions.sort_by(|a, b| {
+        b.complexity
+            .cmp(&a.complexity)
+            .then_with(|| (&a.file, &a.function, a.line).cmp(&(&b.file, &b.function, b.line)))
+    });
+
+    for f in &mut report.findings {
+        rel(&mut f.file);
+    }
+    report
+        .findings
+        .sort_by(|a, b| (&a.file, a.line, &a.rule).cmp(&(&b.file, b.line, &b.rule)));
+    fingerprint::assign(&mut report.findings);
+    for f in &mut report.suppressed_findings {
+        rel(&mut f.file);
+    }
+
+    for g in &mut report.gate_failures {
+        rel(&mut g.location);
+    }
+    for c in &mut report.changed_functions {
+        rel(&mut c.file);
+    }
+    for o in &mut report.oversized_files {
+        rel(&mut o.file);
+    }
+    report
+        .oversized_files
+        .sort_by(|a, b| (&a.module, &a.file).cmp(&(&b.module, &b.file)));
+    for o in &mut report.orphan_files {
+        rel(o);
+    }
+    for i in &mut report.includes {
+        rel(&mut i.file);
+        if !i.path.is_empty() {
+            rel(&mut i.path);
+        }
+    }
+    report.orphan_files.sort();
+    for d in &mut report.diagnostics {
+        rel(&mut d.file);
+    }
+    for s in &mut report.public_symbols {
+        rel(&mut s.file);
+    }
+    for r in &mut report.risk_ranking {
+        rel(&mut r.file);
+    }
+    for m in &mut report.macro_definitions {
+        rel(&mut m.file);
+    }
+    for m in &mut report.magic_values {
+        rel(&mut m.file);
+    }
+    for h in &mut report.hygiene.files {
+        rel(&mut h.file);
+    }
+    for s in &mut report.security_surface.files {
+        rel(&mut s.file);
+    }
+    for t in &mut report.test_quality.assertion_free {
+        rel(&mut t.file);
+    }
+    for f in report.ffi_surface.iter_mut().flat_map(|c| &mut c.files) {
+        rel(f);
+    }
+    for v in &mut report.vendored.files {
+        rel(v);
+    }
+    report.vendored.files.sort();
+    for v in report.derived_metrics.iter_mut().flat_map(|d| &mut d.top) {
+        rel(&mut v.file);
+    }
+    for c in &mut report.class_metrics {
+        rel(&mut c.file);
+    }
+    for l in &mut report.lints {
+        rel(&mut l.file);
+    }
+    for d in report.format_drift.iter_mut().flat_map(|d| &mut d.files) {
+        rel(&mut d.file);
+    }
+    for c in report
+        .compile_time
+        .iter_mut()
+        .flat_map(|c| &mut c.most_complex)
+    {
+        rel(&mut c.file);
+    }
+    for c in &mut report.components {
+        rel(&mut c.most_complex_function);
+    }
+    for d in &mut report.dangerous_functions {
+        rel(&mut d.file);
+    }
+    for w in &mut report.weakly_tested {
+        rel(&mut w.file);
+    }
+    for d in report.doc_code.iter_mut().flat_map(|d| &mut d.files) {
+        rel(&mut d.file);
+    }
+    for p in report
+        .self_profile
+        .iter_mut()
+        .flat_map(|p| &mut p.slowest_files)
+    {
+        rel(&mut p.file);
+    }
+    report.risk_ranking.sort_by(|a, b| {
+        b.risk
+            .cmp(&a.risk)
+            .then_with(|| (&a.file, &a.function).cmp(&(&b.file, &b.function)))
+    });
+    report.diagnostics.sort_by(|a, b| a.file.cmp(&b.file));
+    report.directory_tree = treemap::build(&report.files);
+}
+
+// --- Analysis pipeline shared by the default command and `run` ---
+struct AnalysisOptions {
+    roots: Vec<String>,
+    excludes: Vec<String>,
+    files_from: Option<Vec<String>>, // analyze only these, see --files-from
+    thresholds: Thresholds,
+    discount_commented_code: bool,
+    new_code_since: Option<String>,
+    distribution: Vec<usize>,
+    complexity_model: ComplexityModel,
+    timeout_per_file: Option<Duration>,
+    timeout: Option<Duration>,
+    cache: Option<String>,
+    profile_self: bool,
+    lints: Vec<clippy::Lint>,
+    check_format: bool,
+    doc_code: bool,
+    coverage: Option<coverage::LineCoverage>,
+    mutants: Vec<mutants::Mutant>,
+    derived_metrics: BTreeMap<String, config::DerivedMetric>,
+    components: BTreeMap<String, config::Globs>,
+    grades: config::GradeBoundaries,
+    build_timings: Vec<buildtime::UnitTiming>,
+    shard: Option<shard::Shard>,    // analyze only this shard's files
+    shard_reports: Vec<String>,     // `merge --shards`: results come from these
+    sample: Option<sample::Sample>, // analyze only a seeded subset of files
+    snippet_lines: Option<usize>,   // `--with-snippets`: excerpt length
+    include_vendored: bool,         // gate vendored code like first-party code
+    use_cargo_metadata: bool,       // only files reachable from cargo's targets
+    fail_fast: bool,                // exit 2 as soon as a gate is certain to fail
+    cfg_features: Option<BTreeSet<String>>, // analyze as compiled with these, see --cfg-matrix
+}
+
+fn run_analysis(opts: &AnalysisOptions) -> Result<Report, String> {
+    let mut stopwatch = profile::Stopwatch::start();
+    let budget = TimeBudget {
+        per_file: opts.timeout_per_file,
+        deadline: opts.timeout.map(|t| Instant::now() + t),
+    };
+    let mut diagnostics = Vec::new();
+    let mut cache = opts
+        .cache
+        .as_deref()
+        .filter(|_| opts.shard_reports.is_empty())
+        .map(|path| {
+            let settings = json!({
+                "thresholds": opts.thresholds,
+                "distribution": opts.distribution,
+                "complexity_model": opts.complexity_model,
+                "discount_commented_code": opts.discount_commented_code,
+            });
+            cache::Cache::open(path, cache::key(&settings), &opts.roots)
+        });
+    let mut population = 0;
+    let (mut metrics, mut files) = if opts.shard_reports.is_empty() {
+        let mut sources = source_files(opts, "rs");
+        if opts.use_cargo_metadata {
+            let compiled = metadata::reachable(&opts.roots)?;
+            sources.retain(|(path, _)| path.canonicalize().is_ok_and(|p| compiled.contains(&p)));
+        }
+        population = sources.len();
+        if let Some(sample) = opts.sample {
+            sources.retain(|(path, root)| sample.selects(path, root));
+        }
+        // Largest files first: they are the likeliest to trip a gate.
+        let tripwire = opts.fail_fast.then(|| {
+            sources.sort_by_key(|(path, _)| {
+                std::cmp::Reverse(fs::metadata(path).map_or(0, |m| m.len()))
+            });
+            failfast::Tripwire::new(&opts.thresholds, &opts.roots, opts.include_vendored)
+        });
+        stopwatch.lap("discover");
+        calculate_metrics(
+            sources,
+            opts,
+            &budget,
+            &mut diagnostics,
+            cache.as_mut(),
+            tripwire,
+            &mut stopwatch,
+        )
+    } else {
+        shard::combine(&opts.shard_reports, &mut diagnostics)?
+    };
+    if let Some(cache) = cache {
+        cache.save()?;
+    }
+    vendor::mark(&mut files, &opts.roots);
+    if opts.roots.len() > 1 {
+        roots::attribute(&mut files, &opts.roots);
+    }
+    let partial = opts
+        .shard
+        .map(|s| shard::Partial::capture(s, &metrics, &files, &diagnostics));
+    let mut orphan_files = modtree::resolve(&mut files);
+    let included = includes::resolve(
+        &mut files,
+        &mut metrics,
+        opts.complexity_model,
+        &mut diagnostics,
+    );
+    orphan_files.retain(|f| !included.contains(f));
+    components::assign(&mut files, &opts.roots, &opts.components);
+    let mut top_functions = link_files(&mut files);
+    stopwatch.lap("call graph");
+    clippy::attach(&mut files, &opts.lints);
+    if let Some(lines) = &opts.coverage {
+        coverage::attach(files.iter_mut().flat_map(|f| &mut f.functions), lines);
+        coverage::attach(top_functions.iter_mut(), lines);
+    }
+    mutants::attach(
+        files.iter_mut().flat_map(|f| &mut f.functions),
+        &opts.mutants,
+    );
+    mutants::attach(top_functions.iter_mut(), &opts.mutants);
+    derived::attach(
+        files.iter_mut().flat_map(|f| &mut f.functions),
+        &opts.derived_metrics,
+    );
+    derived::attach(top_functions.iter_mut(), &opts.derived_metrics);
+    grades::attach(&mut files, &opts.grades);
+    grades::attach_functions(top_functions.iter_mut(), &opts.grades);
+    if opts.discount_commented_code {
+        metrics.comments = metrics.comments.saturating_sub(metrics.commented_out_code);
+    }
+    metrics.cyclomatic_distribution = bucket_counts(&files, &opts.distribution);
+    metrics.cyclomatic_distribution_bounds = opts.distribution.clone();
+    let maintainability_index = calculate_maintainability_index(&metrics);
+
+    // Vendored code is reported but, unless --include-vendored, never gated.
+    let first_party: Vec<FileMetrics>;
+    let gate_scope: &[FileMetrics] = if opts.include_vendored || !files.iter().any(|f| f.vendored) {
+        &files
+    } else {
+        first_party = files.iter().filter(|f| !f.vendored).cloned().collect();
+        &first_party
+    };
+    // In new-code mode, gates and findings only see functions touched since the base.
+    let (new_code, changed_functions) = match &opts.new_code_since {
+        Some(since) => {
+            let changed = git::ChangedLines::since(&opts.roots, since)?;
+            let touched = new_code_only(gate_scope, &changed);
+            let risk = changerisk::compare(&touched, &changed, opts.complexity_model);
+            (Some(touched), risk)
+        }
+        None => (None, Vec::new()),
+    };
+    let gated_files = new_code.as_deref().unwrap_or(gate_scope);
+    let mut findings = collect_findings(gated_files, &opts.thresholds);
+    // Limits come from every function in scope, even in new-code mode.
+    let auto_thresholds = opts
+        .thresholds
+        .auto_threshold
+        .as_deref()
+        .and_then(|method| outliers::derive(gate_scope, method));
+    if let Some(auto) = &auto_thresholds {
+        findings.extend(outliers::findings(gated_files, auto));
+    }
+    if let Some(lines) = opts.snippet_lines {
+        snippet::attach(&mut findings, gated_files, lines);
+    }
+    let oversized_files = find_oversized_files(&files, &opts.thresholds);
+    let mut gate_failures = evaluate_gates(
+        gate_scope,
+        new_code.as_deref(),
+        &oversized_files,
+        &opts.thresholds,
+    );
+    gate_failures.extend(derived::gates(gated_files, &opts.derived_metrics));
+    stopwatch.lap("findings and gates");
+    let error_handling = error_handling::summarize(&files);
+    let function_distributions = distribution::summarize(&files);
+    let lifetime_density = lifetimes::summarize(&files);
+    let compile_time = constfn::summarize(&files, 10);
+    let public_api = api::summarize(&files);
+    let symbol_index = symbols::index(&files, 20);
+    for file in &mut files {
+        file.risk = symbol_index.file_risk.get(&file.file).copied().unwrap_or(0);
+    }
+    let magic_values = magic::summarize(&files);
+    let macro_definitions = macros::rank(&files, 10);
+    let hygiene = hygiene::summarize(&files);
+    let security_surface = security::summarize(&files);
+    let test_quality = tests::summarize(&files);
+    let includes = includes::summarize(&files);
+    let ffi_surface = ffi::summarize(&files);
+    let vendored = vendor::summarize(&files, opts.include_vendored);
+    let sample = opts
+        .sample
+        .filter(|_| opts.shard_reports.is_empty())
+        .map(|s| sample::summarize(s, population, &files));
+    let root_summaries = if opts.roots.len() > 1 {
+        roots::summarize(&files, &opts.roots)
+    } else {
+        Vec::new()
+    };
+    let component_summaries = components::summarize(&files, &opts.components);
+    let grades = grades::summarize(&files, &opts.grades);
+    let derived_metrics = derived::summarize(&files, &opts.derived_metrics, 5);
+    let class_metrics = classes::rank(&files, 20);
+    let lints = clippy::summarize(&files);
+    let dangerous_functions = coverage::dangerous(&files);
+    let weakly_tested = mutants::rank(&files, &opts.mutants);
+    let compile_time_debt = buildtime::correlate(&files, &opts.build_timings);
+    let doc_code = opts.doc_code.then(|| {
+        let sources: Vec<&str> = files.iter().map(|f| f.file.as_str()).collect();
+        let markdown: Vec<PathBuf> = source_files(opts, "md")
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+        doccode::collect(&sources, &markdown, opts.complexity_model)
+    });
+    let format_drift = if opts.check_format {
+        Some(format::check(&files, &mut diagnostics)?)
+    } else {
+        None
+    };
+
+    stopwatch.lap("aggregate");
+    let self_profile = opts
+        .profile_self
+        .then(|| profile::summarize(stopwatch, &files, 20));
+
+    Ok(Report {
+        meta: meta::Meta::collect(&opts.roots, &opts.thresholds),
+        complexity_model: opts.complexity_model,
+        metrics,
+        maintainability_index,
+        grades,
+        files,
+        top_functions,
+        findings,
+        suppressed_findings: Vec::new(),
+        expired_debt: Vec::new(),
+        rule_statistics: rules::enabled(&opts.thresholds)
+            .into_iter()
+            .map(|(rule, enabled)| rules::RuleStats {
+                rule: rule.to_string(),
+                enabled,
+                findings: 0,
+                suppressed: 0,
+                new: None,
+            })
+            .collect(),
+        gate_failures,
+        changed_functions,
+        error_handling,
+        oversized_files,
+        orphan_files,
+        auto_thresholds,
+        includes,
+        function_distributions,
+        lifetime_density,
+        compile_time,
+        public_api,
+        public_symbols: symbol_index.public_symbols,
+        risk_ranking: symbol_index.risk_ranking,
+        magic_values,
+        directory_tree: treemap::TreeNode::default(),
+        macro_definitions,
+        hygiene,
+        security_surface,
+        test_quality,
+        ffi_surface,
+        vendored,
+        roots: root_summaries,
+        components: component_summaries,
+        derived_metrics,
+        class_metrics,
+        lints,
+        format_drift,
+        dangerous_functions,
+        weakly_tested,
+        compile_time_debt,
+        debt_velocity: None,
+        orphaned_hotspots: None,
+        cfg_matrix: None,
+        doc_code,
+        self_profile,
+        shard: partial,
+        sample,
+        skipped_binary_files: diagnostics.iter().filter(|d| d.kind == "binary").count(),
+        diagnostics,
+    })
+}
+
+// --- Maintainability index ---
+// log2 floored at 0 for inputs below 1, so empty or degenerate operands
+// (log2(0) = -inf, 0 * -inf = NaN) drop out of the formula instead of
+// turning the index into NaN.
+fn log2_or_zero(x: f64) -> f64 {
+    if x > 1.0 {
+        x.log2()
+    } else {
+        0.0
+    }
+}
+
+fn calculate_maintainability_index(metrics: &CodeMetrics) -> f64 {
+    if metrics.functions == 0 {
+        return 0.0;
+    }
+
+    let vocabulary = metrics
+        .halstead_unique_operators
+        .saturating_add(metrics.halstead_unique_operands) as f64;
+    let halstead_volume = vocabulary * log2_or_zero(vocabulary);
+    let avg_cyclomatic = metrics.cyclomatic_complexity as f64 / metrics.functions as f64;
+
+    let index = 171.0
+        - 5.2 * log2_or_zero(halstead_volume)
+        - 0.23 * avg_cyclomatic
+        - 16.2 * log2_or_zero(metrics.loc as f64);
+    if index.is_finite() {
+        index.clamp(0.0, 100.0)
+    } else {
+        0.0
+    }
+}
+
+// --- MAIN ---
+fn main() {
+    // Panics on the main thread are internal errors; per-file worker threads
+    // report theirs through the timeout diagnostics instead.
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if std::thread::current().name() == Some("main") {
+            exit(EXIT_ERROR);
+        }
+    }));
+
+    let matches = Command::new("CodeHealth Analyzer")
+        .version("2.0")
+        .author("Your Name <your.email@example.com>")
+        .about("Scans codebases and reports metrics such as cyclomatic complexity, maintainability, and risk factors")
+        .after_help(EXIT_CODES_HELP)
+        .arg(
+            Arg::new("path")
+                .long("path")
+                .action(clap::ArgAction::Append)
+                .default_value(".")
+                .help("Path to the directory or file to analyze, or a git URL[#ref] to clone; repeat to analyze several roots together"),
+        )
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .value_parser(["text", "json", "openmetrics", "sarif", "lizard", "template"])
+                .default_value("text")
+                .help("Choose report format"),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("FILE")
+                .help("Write the report to FILE instead of stdout; .gz/.zst names are compressed accordingly"),
+        )
+        .arg(
+            Arg::new("compress")
+                .long("compress")
+                .value_parser(artifact::COMPRESSIONS)
+                .help("Compress the report (to stdout unless --output is given); inputs such as --baseline and merge/compare reports are decompressed transparently"),
+        )
+        .arg(
+            Arg::new("baseline")
+                .long("baseline")
+                .value_name("SARIF")
+                .help("Previous SARIF output; marks results new/unchanged/updated/absent and counts new findings per rule"),
+        )
+        .arg(
+            Arg::new("template")
+                .long("template")
+                .value_name("FILE")
+                .required_if_eq("report", "template")
+                .help("Handlebars template rendered with the JSON report data (--report template)"),
+        )
+        .arg(
+            Arg::new("clippy-json")
+                .long("clippy-json")
+                .value_name("FILE")
+                .conflicts_with("run-clippy")
+                .help("Merge lints from `cargo clippy --message-format=json` output saved in FILE"),
+        )
+        .arg(
+            Arg::new("run-clippy")
+                .long("run-clippy")
+                .action(clap::ArgAction::SetTrue)
+                .help("Run `cargo clippy` on the analyzed workspace and merge its lints"),
+        )
+        .arg(
+            Arg::new("max-complexity")
+                .long("max-complexity")
+                .value_parser(clap::value_parser!(u32))
+                .global(true)
+                .help("Fail if max cyclomatic complexity exceeds this threshold"),
+        )
+        .arg(
+            Arg::new("max-exit-points")
+                .long("max-exit-points")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Flag functions with more return/? exit points than this"),
+        )
+        .arg(
+            Arg::new("max-condition-operands")
+                .long("max-condition-operands")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Flag conditions with more &&/|| operands than this"),
+        )
+        .arg(
+            Arg::new("max-call-chain")
+                .long("max-call-chain")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Flag functions with a method-call chain longer than this"),
+        )
+        .arg(
+            Arg::new("max-quote-tokens")
+                .long("max-quote-tokens")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Flag proc-macro functions generating more quote! tokens than this"),
+        )
+        .arg(
+            Arg::new("max-macro-rules")
+                .long("max-macro-rules")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Flag macro_rules! definitions with more rules (arms) than this"),
+        )
+        .arg(
+            Arg::new("max-macro-depth")
+                .long("max-macro-depth")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Flag macro_rules! definitions whose token trees nest deeper than this"),
+        )
+        .arg(
+            Arg::new("max-macro-tokens")
+                .long("max-macro-tokens")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Flag macro_rules! definitions with more tokens than this"),
+        )
+        .arg(
+            Arg::new("outlier-complexity")
+                .long("outlier-complexity")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Complexity above which a function counts as an outlier (default 10)"),
+        )
+        .arg(
+            Arg::new("max-functions-over-complexity")
+                .long("max-functions-over-complexity")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Fail if more functions than this exceed the outlier complexity"),
+        )
+        .arg(
+            Arg::new("max-percent-functions-over-complexity")
+                .long("max-percent-functions-over-complexity")
+                .value_name("PERCENT")
+                .value_parser(parse_percent)
+                .global(true)
+                .help("Fail if more than this share of functions exceed the outlier complexity (e.g. 5%)"),
+        )
+        .arg(
+            Arg::new("complexity-model")
+                .long("complexity-model")
+                .value_parser(config::COMPLEXITY_MODELS)
+                .global(true)
+                .help("How cyclomatic complexity is counted (default: statements, or complexity-model in the config)"),
+        )
+        .arg(
+            Arg::new("auto-threshold")
+                .long("auto-threshold")
+                .value_name("METHOD")
+                .num_args(0..=1)
+                .default_missing_value("percentile")
+                .value_parser(outliers::METHODS)
+                .global(true)
+                .help("Flag functions whose complexity or LOC is an outlier for this codebase: above p97.5 (percentile, the default) or more than 2 MAD above the median (mad)"),
+        )
+        .arg(
+            Arg::new("feature-envy-ratio")
+                .long("feature-envy-ratio")
+                .value_name("RATIO")
+                .value_parser(clap::value_parser!(f64))
+                .global(true)
+                .help("Flag functions calling another module at least RATIO times as often as their own"),
+        )
+        .arg(
+            Arg::new("max-file-loc")
+                .long("max-file-loc")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Fail if any file has more lines than this"),
+        )
+        .arg(
+            Arg::new("max-include-lines")
+                .long("max-include-lines")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Flag include!/include_str! targets longer than this many lines"),
+        )
+        .arg(
+            Arg::new("max-items-per-module")
+                .long("max-items-per-module")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Fail if any file has more top-level items than this"),
+        )
+        .arg(
+            Arg::new("max-api-growth")
+                .long("max-api-growth")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Fail if the number of pub items grew by more than this since the API baseline"),
+        )
+        .arg(
+            Arg::new("max-total-complexity-increase")
+                .long("max-total-complexity-increase")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Fail if total cyclomatic complexity grew by more than this since the delta baseline"),
+        )
+        .arg(
+            Arg::new("max-new-functions-over-complexity")
+                .long("max-new-functions-over-complexity")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Fail if more functions than this crossed --outlier-complexity since the delta baseline"),
+        )
+        .arg(
+            Arg::new("max-findings")
+                .long("max-findings")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Fail if more findings than this remain after suppressions and the ignore file (0 = none allowed)"),
+        )
+        .arg(
+            Arg::new("ignore-file")
+                .long("ignore-file")
+                .global(true)
+                .help("Accepted findings by fingerprint, with optional expiry (defaults to ./.codehealth-ignore when present)"),
+        )
+        .arg(
+            Arg::new("max-debt-growth-per-week")
+                .long("max-debt-growth-per-week")
+                .value_name("MINUTES")
+                .value_parser(clap::value_parser!(f64))
+                .global(true)
+                .help("Fail if estimated debt grew faster than this many minutes per week over the --debt-window"),
+        )
+        .arg(
+            Arg::new("debt-window")
+                .long("debt-window")
+                .value_name("DAYS")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("28")
+                .global(true)
+                .help("Rolling window for debt velocity, measured from the oldest history snapshot inside it"),
+        )
+        .arg(
+            Arg::new("orphaned-hotspots")
+                .long("orphaned-hotspots")
+                .value_name("MONTHS")
+                .value_parser(clap::value_parser!(u64))
+                .global(true)
+                .help("List complex files whose blame owners have not committed in MONTHS months"),
+        )
+        .arg(
+            Arg::new("api-baseline")
+                .long("api-baseline")
+                .value_name("TAG")
+                .help("History tag to measure API growth from (default: latest tagged snapshot)"),
+        )
+        .arg(
+            Arg::new("delta-baseline")
+                .long("delta-baseline")
+                .value_name("TAG")
+                .help("History tag the max-*-increase and max-new-* gates compare against (default: latest tagged snapshot)"),
+        )
+        .arg(
+            Arg::new("timeout-per-file")
+                .long("timeout-per-file")
+                .value_name("DURATION")
+                .value_parser(parse_duration)
+                .global(true)
+                .help("Skip files whose analysis takes longer than this (e.g. 10s, 500ms)"),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .value_name("DURATION")
+                .value_parser(parse_duration)
+                .global(true)
+                .help("Stop analyzing after this long and report what was covered (e.g. 10m)"),
+        )
+        .arg(
+            Arg::new("cache")
+                .long("cache")
+                .value_name("FILE")
+                .num_args(0..=1)
+                .default_missing_value(cache::DEFAULT_CACHE_FILE)
+                .global(true)
+                .help("Reuse results for unchanged files (default file: .codehealth/cache.json)"),
+        )
+        .arg(
+            Arg::new("discount-commented-code")
+                .long("discount-commented-code")
+                .action(clap::ArgAction::SetTrue)
+                .global(true)
+                .help("Exclude commented-out code from comment density (it's debt, not documentation)"),
+        )
+        .arg(
+            Arg::new("check-format")
+                .long("check-format")
+                .action(clap::ArgAction::SetTrue)
+                .global(true)
+                .help("Report files and lines that rustfmt would reformat"),
+        )
+        .arg(
+            Arg::new("summary")
+                .long("summary")
+                .action(clap::ArgAction::SetTrue)
+                .help("Also print a short human-readable summary to stderr, keeping machine-readable reports on stdout clean"),
+        )
+        .arg(
+            Arg::new("gate-dry-run")
+                .long("gate-dry-run")
+                .action(clap::ArgAction::SetTrue)
+                .help("Evaluate gates and report what would fail, but exit 0"),
+        )
+        .arg(
+            Arg::new("fail-fast")
+                .long("fail-fast")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["gate-dry-run", "new-code-since"])
+                .help("Stop with exit 2 at the first file that makes a gate certain to fail (file size, max complexity, functions over complexity)"),
+        )
+        .arg(
+            Arg::new("cfg-matrix")
+                .long("cfg-matrix")
+                .action(clap::ArgAction::SetTrue)
+                .global(true)
+                .help("Also analyze once per [cfg-matrix] feature set from the config and report each metric's range"),
+        )
+        .arg(
+            Arg::new("shard")
+                .long("shard")
+                .value_name("K/N")
+                .value_parser(shard::parse)
+                .global(true)
+                .help("Analyze only shard K of N (files partitioned by path hash); combine JSON reports with `merge --shards`"),
+        )
+        .arg(
+            Arg::new("sample")
+                .long("sample")
+                .value_name("PCT")
+                .value_parser(sample::parse_percent)
+                .global(true)
+                .help("Analyze a deterministic PCT% of files (by seeded path hash) and extrapolate totals with 95% confidence intervals"),
+        )
+        .arg(
+            Arg::new("sample-seed")
+                .long("sample-seed")
+                .value_name("SEED")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("0")
+                .global(true)
+                .help("Seed for --sample; another seed picks a different, equally stable subset"),
+        )
+        .arg(
+            Arg::new("with-snippets")
+                .long("with-snippets")
+                .value_name("LINES")
+                .num_args(0..=1)
+                .default_missing_value("5")
+                .value_parser(clap::value_parser!(usize))
+                .global(true)
+                .help("Embed a source excerpt (the complex condition, or the first LINES lines of the function, default 5) in each finding"),
+        )
+        .arg(
+            Arg::new("item-metrics")
+                .long("item-metrics")
+                .value_name("FILE")
+                .help("Also write function metrics keyed by item path (my_crate::api::handler) to FILE, for joining with rustdoc JSON"),
+        )
+        .arg(
+            Arg::new("badge")
+                .long("badge")
+                .value_name("FILE")
+                .help("Also write an SVG badge with the project grade to FILE"),
+        )
+        .arg(
+            Arg::new("use-cargo-metadata")
+                .long("use-cargo-metadata")
+                .action(clap::ArgAction::SetTrue)
+                .global(true)
+                .help("Analyze only files reachable via `mod` from the targets `cargo metadata` lists, skipping include!d and uncompiled .rs files"),
+        )
+        .arg(
+            Arg::new("include-vendored")
+                .long("include-vendored")
+                .action(clap::ArgAction::SetTrue)
+                .global(true)
+                .help("Gate code under vendor/, third_party/ and [patch] paths too (by default it is reported separately and never gated)"),
+        )
+        .arg(
+            Arg::new("profile-self")
+                .long("profile-self")
+                .action(clap::ArgAction::SetTrue)
+                .global(true)
+                .help("Report time spent per analysis phase and the slowest files (read/parse/visit)"),
+        )
+        .arg(
+            Arg::new("exclude-doc-code")
+                .long("exclude-doc-code")
+                .action(clap::ArgAction::SetTrue)
+                .global(true)
+                .help("Skip Rust code in markdown files and doc comments"),
+        )
+        .arg(
+            Arg::new("coverage")
+                .long("coverage")
+                .value_name("FILE")
+                .global(true)
+                .help("grcov covdir or llvm-cov export JSON; adds per-function coverage"),
+        )
+        .arg(
+            Arg::new("mutants")
+                .long("mutants")
+                .value_name("FILE")
+                .global(true)
+                .help("cargo-mutants outcomes.json or a [{file, line, status}] array; adds per-function mutation scores"),
+        )
+        .arg(
+            Arg::new("build-timings")
+                .long("build-timings")
+                .value_name("FILE")
+                .global(true)
+                .help("cargo-timing.html or `cargo build --timings=json` output; adds a compile-time debt section"),
+        )
+        .arg(
+            Arg::new("new-code-since")
+                .long("new-code-since")
+                .value_name("REF|DATE")
+                .global(true)
+                .help("Only gate functions added or modified since a git ref or YYYY-MM-DD date"),
+        )
+        .arg(
+            Arg::new("files-from")
+                .long("files-from")
+                .value_name("FILE|-")
+                .help("Analyze only the newline-separated paths in FILE (- for stdin), e.g. from git diff --name-only"),
+        )
+        .arg(
+            Arg::new("path-root")
+                .long("path-root")
+                .global(true)
+                .help("Report paths relative to this directory (defaults to the scan path)"),
+        )
+        .arg(
+            Arg::new("history")
+                .long("history")
+                .global(true)
+                .help("History store file (defaults to .codehealth/history.jsonl)"),
+        )
+        .arg(
+            Arg::new("save-history")
+                .long("save-history")
+                .action(clap::ArgAction::SetTrue)
+                .help("Append this run as a snapshot to the history store"),
+        )
+        .arg(
+            Arg::new("tag")
+                .long("tag")
+                .help("Tag the saved snapshot (e.g. a release version); implies --save-history"),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .global(true)
+                .help("Threshold profile: strict, default, legacy, or one defined in the config file"),
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .global(true)
+                .help("Config file (defaults to ./.codehealth.toml when present)"),
+        )
+        .arg(
+            Arg::new("lang")
+                .long("lang")
+                .value_parser(locale::LANGS)
+                .default_value("en")
+                .global(true)
+                .help("Language of text and HTML report strings"),
+        )
+        .arg(
+            Arg::new("labels")
+                .long("labels")
+                .value_name("FILE")
+                .global(true)
+                .help("TOML file of `key = \"text\"` overriding report strings of the chosen --lang"),
+        )
+        .arg(
+            Arg::new("log-format")
+                .long("log-format")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .global(true)
+                .help("Format of diagnostics written to stderr (json = one structured record per line)"),
+        )
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .value_parser(["auto", "always", "never"])
+                .default_value("auto")
+                .global(true)
+                .help("Color the text report: auto = only on a terminal without NO_COLOR"),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Language breakdown of code, comment and blank lines (tokei-style)")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .default_value(".")
+                        .help("Path to the directory or file to analyze"),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .help("Choose report format"),
+                ),
+        )
+        .subcommand(
+            Command::new("run")
+                .about("Analyze the named targets from the config file and aggregate one report")
+                .arg(
+                    Arg::new("target")
+                        .long("target")
+                        .default_value("all")
+                        .help("Target name from [targets] in the config, or 'all'"),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .help("Choose report format"),
+                ),
+        )
+        .subcommand(
+            Command::new("annotate")
+                .about("Insert `// codehealth: cc=.. loc=..` comments above functions over a complexity threshold")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .default_value(".")
+                        .help("Path to the directory or file to annotate"),
+                )
+                .arg(
+                    Arg::new("threshold")
+                        .long("threshold")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10")
+                        .help("Annotate functions whose cyclomatic complexity exceeds this"),
+                )
+                .arg(
+                    Arg::new("remove")
+                        .long("remove")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Remove all codehealth annotations instead"),
+                ),
+        )
+        .subcommand(
+            Command::new("crate")
+                .about("Download a published crate from crates.io and analyze it")
+                .arg(
+                    Arg::new("name")
+                        .required(true)
+                        .value_name("NAME")
+                        .help("Crate name on crates.io"),
+                )
+                .arg(
+                    Arg::new("version")
+                        .long("version")
+                        .value_name("X.Y.Z")
+                        .help("Version to analyze (default: latest stable)"),
+                ),
+        )
+        .subcommand(
+            Command::new("issues")
+                .about("File the worst findings as GitHub or Jira issues (dry run unless --create)")
+                .arg(
+                    Arg::new("tracker")
+                        .long("tracker")
+                        .value_parser(["github", "jira"])
+                        .default_value("github")
+                        .help("Issue tracker to file into"),
+                )
+                .arg(
+                    Arg::new("top")
+                        .long("top")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10")
+                        .help("Number of findings to file, worst first"),
+                )
+                .arg(
+                    Arg::new("create")
+                        .long("create")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Actually open issues; without it they are only printed"),
+                )
+                .arg(
+                    Arg::new("repo")
+                        .long("repo")
+                        .value_name("OWNER/REPO")
+                        .help("GitHub repository (default: the origin remote)"),
+                )
+                .arg(
+                    Arg::new("project")
+                        .long("project")
+                        .value_name("KEY")
+                        .required_if_eq("tracker", "jira")
+                        .help("Jira project key"),
+                ),
+        )
+        .subcommand(
+            Command::new("simulate")
+                .about("Count the findings candidate thresholds would produce on the analyzed code")
+                .arg(
+                    Arg::new("threshold")
+                        .long("threshold")
+                        .required(true)
+                        .action(clap::ArgAction::Append)
+                        .value_name("NAME=RANGE")
+                        .value_parser(simulate::parse)
+                        .help("e.g. function-complexity=10..20, max-file-loc=500..2000:500, feature-envy-ratio=2,3,5 or derived:NAME=10..30"),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .help("Choose report format"),
+                ),
+        )
+        .subcommand(
+            Command::new("lsp")
+                .about("Run a language server on stdio: findings as diagnostics, with quick fixes to suppress them or explain the rule"),
+        )
+        .subcommand(
+            Command::new("daemon")
+                .about("Serve analyze-file, analyze-paths and get-report as line-delimited JSON-RPC on a unix socket, keeping a warm cache between calls")
+                .arg(
+                    Arg::new("socket")
+                        .long("socket")
+                        .value_name("PATH")
+                        .default_value(".codehealth/daemon.sock")
+                        .help("Unix socket to listen on"),
+                ),
+        )
+        .subcommand(
+            Command::new("precommit")
+                .about("Check functions changed in staged files; exits 2 (gate failure) with a terse list on violations"),
+        )
+        .subcommand(
+            Command::new("merge")
+                .about("Combine report JSONs from several repos into an org-level rollup")
+                .arg(
+                    Arg::new("reports")
+                        .required(true)
+                        .num_args(1..)
+                        .value_name("REPORT")
+                        .help("Report JSON files (from --report json), one per repo"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write the rollup JSON here instead of printing a summary"),
+                )
+                .arg(
+                    Arg::new("shards")
+                        .long("shards")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with("output")
+                        .help("Inputs are --shard reports of one scan; recombine them into a single report, rendered per the top-level --path, --report and analysis flags"),
+                ),
+        )
+        .subcommand(
+            Command::new("compare")
+                .about("Diff two report JSONs function by function")
+                .arg(
+                    Arg::new("old")
+                        .required(true)
+                        .value_name("OLD")
+                        .help("Earlier report JSON (from --report json)"),
+                )
+                .arg(
+                    Arg::new("new")
+                        .required(true)
+                        .value_name("NEW")
+                        .help("Later report JSON"),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .value_parser(["text", "json", "html", "markdown"])
+                        .default_value("text")
+                        .help("Choose report format; markdown suits a PR comment"),
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Re-analyze one function at every commit that touched its file and print a timeline")
+                .arg(
+                    Arg::new("function")
+                        .long("function")
+                        .required(true)
+                        .value_name("PATH::NAME")
+                        .help("File and function, e.g. src/main.rs::run or src/lib.rs::Parser::next"),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .help("Choose report format"),
+                ),
+        )
+        .subcommand(
+            Command::new("rank")
+                .about("List the worst functions in a saved report by one metric, with filters")
+                .arg(
+                    Arg::new("report-file")
+                        .required(true)
+                        .value_name("REPORT")
+                        .help("Report JSON (from --report json)"),
+                )
+                .arg(
+                    Arg::new("metric")
+                        .long("metric")
+                        .value_parser(rank::parse_metric)
+                        .default_value("cc")
+                        .help("Metric to rank by: a derived-metric variable (cc, cognitive, loc, nesting, ...) or derived:NAME"),
+                )
+                .arg(
+                    Arg::new("min")
+                        .long("min")
+                        .value_parser(clap::value_parser!(f64))
+                        .help("Only functions with at least this value"),
+                )
+                .arg(
+                    Arg::new("max")
+                        .long("max")
+                        .value_parser(clap::value_parser!(f64))
+                        .help("Only functions with at most this value"),
+                )
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .value_name("PREFIX")
+                        .help("Only files under this path, as written in the report (e.g. crates/api)"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("20")
+                        .help("Number of functions to list"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["table", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            Command::new("query")
+                .about("Select functions or files from a saved report with a small pipeline language")
+                .after_help(
+                    "A query is a source followed by stages:\n  \
+                     functions | files\n  \
+                     | where FIELD OP VALUE [and|or ...]   (OP: == != < <= > >= ~glob; not, parentheses)\n  \
+                     | sort FIELD [asc|desc]\n  \
+                     | limit N\n  \
+                     | select FIELD, ...\n\n\
+                     Example: functions | where file ~ \"src/api/**\" and cognitive > 20 | sort cognitive",
+                )
+                .arg(
+                    Arg::new("query")
+                        .required(true)
+                        .value_name("QUERY")
+                        .value_parser(query::parse)
+                        .help("The query, quoted as one argument"),
+                )
+                .arg(
+                    Arg::new("report-file")
+                        .required(true)
+                        .value_name("REPORT")
+                        .help("Report JSON (from --report json)"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["table", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                )
+                .arg(
+                    Arg::new("fail-if-any")
+                        .long("fail-if-any")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Exit 2 (gate failure) when the query returns anything"),
+                ),
+        )
+        .subcommand(
+            Command::new("trend")
+                .about("List history snapshots or compare two tagged snapshots")
+                .arg(
+                    Arg::new("between")
+                        .long("between")
+                        .num_args(2)
+                        .value_names(["FROM", "TO"])
+                        .help("Compare two snapshot tags and rank what contributed to growth"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10")
+                        .help("Number of contributing functions/files to list"),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .help("Choose report format"),
+                ),
+        )
+        .try_get_matches()
+        .unwrap_or_else(|err| {
+            // clap would exit 2 on usage errors, which means a gate failure here.
+            let _ = err.print();
+            exit(if err.use_stderr() { EXIT_ERROR } else { EXIT_OK })
+        });
+
+    let paths: Vec<&String> = matches.get_many::<String>("path").unwrap().collect();
+    // Single-path consumers (precommit, lsp, clippy, git) use the first root.
+    let path = paths[0];
+    let report = matches.get_one::<String>("report").unwrap();
+    let log_format = matches.get_one::<String>("log-format").unwrap();
+    logging::init(if log_format == "json" {
+        LogFormat::Json
+    } else {
+        LogFormat::Text
+    });
+    term::init(
+        term::ColorChoice::from_name(matches.get_one::<String>("color").unwrap())
+            .unwrap_or(term::ColorChoice::Auto),
+    );
+    locale::init(
+        matches.get_one::<String>("lang").unwrap(),
+        matches.get_one::<String>("labels").map(String::as_str),
+    )
+    .unwrap_or_else(|err| fail(&err));
+
+    if let Some(("stats", sub)) = matches.subcommand() {
+        let stats = stats::collect_stats(sub.get_one::<String>("path").unwrap());
+        if sub.get_one::<String>("report").unwrap() == "json" {
+            println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+        } else {
+            stats::print_stats(&stats);
+        }
+        return;
+    }
+
+    if let Some(("annotate", sub)) = matches.subcommand() {
+        annotate::run(
+            sub.get_one::<String>("path").unwrap(),
+            *sub.get_one::<usize>("threshold").unwrap(),
+            sub.get_flag("remove"),
+            complexity_model(sub, &load_config(&matches)),
+        );
+        return;
+    }
+
+    if let Some(("trend", sub)) = matches.subcommand() {
+        run_trend(sub);
+        return;
+    }
+
+    if let Some(("query", sub)) = matches.subcommand() {
+        let query = sub.get_one::<query::Query>("query").unwrap();
+        let (columns, rows) = query::run(query, sub.get_one::<String>("report-file").unwrap())
+            .unwrap_or_else(|err| fail(&err));
+        if sub.get_one::<String>("format").unwrap() == "json" {
+            println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+        } else {
+            query::print_table(&columns, &rows);
+        }
+        exit(if sub.get_flag("fail-if-any") && !rows.is_empty() {
+            EXIT_GATE_FAILURE
+        } else {
+            EXIT_OK
+        });
+    }
+
+    if let Some(("rank", sub)) = matches.subcommand() {
+        let metric = sub.get_one::<String>("metric").unwrap();
+        let query = rank::Query {
+            metric: metric.clone(),
+            min: sub.get_one::<f64>("min").copied(),
+            max: sub.get_one::<f64>("max").copied(),
+            path: sub.get_one::<String>("path").cloned(),
+            limit: *sub.get_one::<usize>("limit").unwrap(),
+        };
+        let rows = rank::rank(sub.get_one::<String>("report-file").unwrap(), &query)
+            .unwrap_or_else(|err| fail(&err));
+        if sub.get_one::<String>("format").unwrap() == "json" {
+            println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+        } else {
+            rank::print_table(&rows, metric);
+        }
+        return;
+    }
+
+    if let Some(("history", sub)) = matches.subcommand() {
+        let model = complexity_model(sub, &load_config(&matches));
+        let timeline = timeline::walk(sub.get_one::<String>("function").unwrap(), model)
+            .unwrap_or_else(|err| fail(&err));
+        if sub.get_one::<String>("report").unwrap() == "json" {
+            println!("{}", serde_json::to_string_pretty(&timeline).unwrap());
+        } else {
+            timeline::print_timeline(&timeline);
+        }
+        return;
+    }
+
+    if let Some(("compare", sub)) = matches.subcommand() {
+        let diff = compare::diff(
+            sub.get_one::<String>("old").unwrap(),
+            sub.get_one::<String>("new").unwrap(),
+        )
+        .unwrap_or_else(|err| fail(&err));
+        match sub.get_one::<String>("report").unwrap().as_str() {
+            "json" => println!("{}", serde_json::to_string_pretty(&diff).unwrap()),
+            "html" => print!("{}", compare::render_html(&diff)),
+            "markdown" => print!("{}", compare::render_markdown(&diff)),
+            _ => compare::print_diff(&diff),
+        }
+        return;
+    }
+
+    if let Some(("merge", sub)) = matches
+        .subcommand()
+        .filter(|(name, s)| *name != "merge" || !s.get_flag("shards"))
+    {
+        let reports: Vec<String> = sub
+            .get_many::<String>("reports")
+            .unwrap()
+            .cloned()
+            .collect();
+        let rollup = rollup::merge(&reports).unwrap_or_else(|err| fail(&err));
+        match sub.get_one::<String>("output") {
+            Some(out) => {
+                let json = serde_json::to_string_pretty(&rollup).unwrap();
+                fs::write(out, json + "\n")
+                    .unwrap_or_else(|e| fail(&format!("cannot write {}: {}", out, e)));
+            }
+            None => rollup::print_rollup(&rollup),
+        }
+        return;
+    }
+
+    let config = load_config(&matches);
+
+    if let Some(("run", sub)) = matches.subcommand() {
+        run_targets(sub, &config);
+        return;
+    }
+
+    let profile = matches.get_one::<String>("profile").map(String::as_str);
+    let thresholds = config
+        .resolve_thresholds(profile, &cli_thresholds(&matches))
+        .unwrap_or_else(|err| fail(&err));
+
+    // Expired suppressions silence nothing, here as in the full report.
+    let active: Vec<config::Suppression> = config
+        .suppressions
+        .iter()
+        .filter(|s| !rules::is_expired(s.expires.as_deref(), &logging::today()))
+        .cloned()
+        .collect();
+    if let Some(("precommit", _)) = matches.subcommand() {
+        let model = complexity_model(&matches, &config);
+        let code = precommit::run(path, &thresholds, model, &active);
+        exit(code.unwrap_or_else(|err| fail(&err)));
+    }
+
+    if let Some(("lsp", _)) = matches.subcommand() {
+        let model = complexity_model(&matches, &config);
+        lsp::serve(path, &thresholds, model, &active).unwrap_or_else(|err| fail(&err));
+        exit(EXIT_OK);
+    }
+
+    // Remote URLs and crates.io packages are analyzed from a temp workspace.
+    let roots: Vec<String> = if let Some(("crate", sub)) = matches.subcommand() {
+        let name = sub.get_one::<String>("name").unwrap();
+        let version = sub.get_one::<String>("version").map(String::as_str);
+        let dir = registry::download_crate(name, version).unwrap_or_else(|err| fail(&err));
+        vec![dir.to_string_lossy().into_owned()]
+    } else {
+        paths
+            .iter()
+            .map(|path| {
+                if git::is_remote_url(path) {
+                    let dir = git::clone_remote(path).unwrap_or_else(|err| fail(&err));
+                    dir.to_string_lossy().into_owned()
+                } else {
+                    path.to_string()
+                }
+            })
+            .collect()
+    };
+    let path = &roots[0];
+
+    let mut opts = AnalysisOptions {
+        roots: roots.clone(),
+        excludes: Vec::new(),
+        files_from: matches
+            .get_one::<String>("files-from")
+            .map(|spec| read_file_list(spec).unwrap_or_else(|err| fail(&err))),
+        thresholds,
+        discount_commented_code: matches.get_flag("discount-commented-code"),
+        new_code_since: matches.get_one::<String>("new-code-since").cloned(),
+        distribution: config.distribution.clone(),
+        complexity_model: complexity_model(&matches, &config),
+        timeout_per_file: matches.get_one::<Duration>("timeout-per-file").copied(),
+        timeout: matches.get_one::<Duration>("timeout").copied(),
+        cache: matches.get_one::<String>("cache").cloned(),
+        profile_self: matches.get_flag("profile-self"),
+        lints: clippy_lints(&matches, path).unwrap_or_else(|err| fail(&err)),
+        check_format: matches.get_flag("check-format"),
+        doc_code: !matches.get_flag("exclude-doc-code"),
+        coverage: load_coverage(&matches),
+        mutants: load_mutants(&matches),
+        derived_metrics: config.derived_metrics.clone(),
+        components: config.components.clone(),
+        grades: config.grades,
+        build_timings: load_build_timings(&matches),
+        shard: matches.get_one::<shard::Shard>("shard").copied(),
+        shard_reports: match matches.subcommand() {
+            Some(("merge", sub)) => sub
+                .get_many::<String>("reports")
+                .unwrap()
+                .cloned()
+                .collect(),
+            _ => Vec::new(),
+        },
+        sample: sample_option(&matches),
+        snippet_lines: matches.get_one::<usize>("with-snippets").copied(),
+        include_vendored: matches.get_flag("include-vendored"),
+        use_cargo_metadata: matches.get_flag("use-cargo-metadata"),
+        fail_fast: matches.get_flag("fail-fast"),
+        cfg_features: None,
+    };
+    // Several roots share no single base, so paths stay relative to the
+    // working directory, as for `run` targets.
+    let default_root = if roots.len() > 1 { "." } else { path.as_str() };
+    let path_root = matches
+        .get_one::<String>("path-root")
+        .map_or(default_root, String::as_str);
+    if let Some(("daemon", sub)) = matches.subcommand() {
+        let socket = sub.get_one::<String>("socket").unwrap();
+        #[cfg(unix)]
+        let served = daemon::serve(socket, opts, path_root, suppressions(&matches, &config));
+        #[cfg(not(unix))]
+        let served: Result<(), String> = Err(format!(
+            "daemon: {}: unix sockets are not supported here",
+            socket
+        ));
+        served.unwrap_or_else(|err| fail(&err));
+        exit(EXIT_OK);
+    }
+    let mut output = run_analysis(&opts).unwrap_or_else(|err| fail(&err));
+    output.meta.describe_config(&config);
+    canonicalize_report(&mut output, path_root);
+    let today = logging::today();
+    let mut baseline = matches
+        .get_one::<String>("baseline")
+        .map(|b| sarif::load_baseline(b).unwrap_or_else(|err| fail(&err)));
+    let expired_baseline = baseline
+        .as_mut()
+        .map(|b| rules::expire_baseline(b, &today))
+        .unwrap_or_default();
+    let snapshots = Path::new(history_path(&matches))
+        .is_file()
+        .then(|| history::load(history_path(&matches)).unwrap_or_else(|err| fail(&err)));
+    if let Some(snapshots) = &snapshots {
+        // Before suppressions, which may select findings by age.
+        let now = output.meta.scanned_at.clone();
+        age::attach(&mut output, snapshots, &now);
+    }
+    let suppressions = suppressions(&matches, &config);
+    rules::apply(
+        &mut output,
+        &suppressions,
+        baseline.as_ref(),
+        expired_baseline,
+        &today,
+    );
+    log_expired_debt(&output.expired_debt);
+    if let Some(limit) = opts.thresholds.max_findings {
+        output.gate_failures.extend(findings_gate(&output, limit));
+    }
+    if let Some(limit) = opts.thresholds.max_api_growth {
+        let baseline = matches
+            .get_one::<String>("api-baseline")
+            .map(String::as_str);
+        output.gate_failures.extend(api_growth_gate(
+            &output,
+            history_path(&matches),
+            baseline,
+            limit,
+        ));
+    }
+    let t = &opts.thresholds;
+    if t.max_total_complexity_increase.is_some() || t.max_new_functions_over_complexity.is_some() {
+        let baseline = matches
+            .get_one::<String>("delta-baseline")
+            .map(String::as_str);
+        output
+            .gate_failures
+            .extend(delta_gates(&output, history_path(&matches), baseline, t));
+    }
+    if let Some(snapshots) = &snapshots {
+        let current = history::Snapshot::from_report(&output, None, None);
+        let window = *matches.get_one::<u64>("debt-window").unwrap();
+        output.debt_velocity = velocity::compute(snapshots, &current, window);
+    }
+    if let Some(&months) = matches.get_one::<u64>("orphaned-hotspots") {
+        let level = opts
+            .thresholds
+            .outlier_complexity
+            .unwrap_or(DEFAULT_OUTLIER_COMPLEXITY);
+        output.orphaned_hotspots = Some(
+            ownership::find(&output, path_root, months, level).unwrap_or_else(|err| fail(&err)),
+        );
+    }
+    if matches.get_flag("cfg-matrix") {
+        if config.cfg_matrix.is_empty() {
+            fail("--cfg-matrix needs feature sets in a [cfg-matrix] config table");
+        }
+        output.cfg_matrix = Some(
+            cfgmatrix::analyze(&mut opts, &config.cfg_matrix).unwrap_or_else(|err| fail(&err)),
+        );
+    }
+    if let Some(limit) = opts.thresholds.max_debt_growth_per_week {
+        output
+            .gate_failures
+            .extend(debt_growth_gate(output.debt_velocity.as_ref(), limit));
+    }
+
+    if let Some(("simulate", sub)) = matches.subcommand() {
+        let simulations: Vec<simulate::Simulation> = sub
+            .get_many::<simulate::Candidates>("threshold")
+            .unwrap()
+            .map(|c| simulate::simulate(&output.files, c))
+            .collect();
+        if sub.get_one::<String>("report").unwrap() == "json" {
+            println!("{}", serde_json::to_string_pretty(&simulations).unwrap());
+        } else {
+            for (i, s) in simulations.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                simulate::print_simulation(s);
+            }
+        }
+        exit(EXIT_OK);
+    }
+
+    if let Some(("issues", sub)) = matches.subcommand() {
+        file_issues(sub, &output, path, path_root);
+    }
+
+    let tag = matches.get_one::<String>("tag").cloned();
+    if tag.is_some() || matches.get_flag("save-history") {
+        let commit = git::run_git(&git::work_dir(path), &["rev-parse", "HEAD"])
+            .ok()
+            .map(|c| c.trim().to_string());
+        let snapshot = history::Snapshot::from_report(&output, tag, commit);
+        history::append(history_path(&matches), &snapshot).unwrap_or_else(|err| fail(&err));
+    }
+    if let Some(file) = matches.get_one::<String>("item-metrics") {
+        let index = rustdoc::index(&output);
+        let json = serde_json::to_string_pretty(&index).unwrap() + "\n";
+        artifact::write(Some(file), &json, None).unwrap_or_else(|err| fail(&err));
+    }
+    if let Some(file) = matches.get_one::<String>("badge") {
+        let svg = grades::badge_svg(&output.grades.grade);
+        artifact::write(Some(file), &svg, None).unwrap_or_else(|err| fail(&err));
+    }
+
+    let rendered = match report.as_str() {
+        "json" => Some(serde_json::to_string_pretty(&output).unwrap() + "\n"),
+        "openmetrics" => Some(openmetrics::render(&output)),
+        "lizard" => Some(lizard::render(&output)),
+        "sarif" => {
+            let sarif = sarif::render(&output, baseline.as_ref());
+            Some(serde_json::to_string_pretty(&sarif).unwrap() + "\n")
+        }
+        "template" => {
+            let template = matches.get_one::<String>("template").unwrap();
+            Some(template::render(&output, template).unwrap_or_else(|err| fail(&err)))
+        }
+        _ => None,
+    };
+    let destination = matches.get_one::<String>("output").map(String::as_str);
+    let compression = matches
+        .get_one::<String>("compress")
+        .and_then(|c| artifact::Compression::from_name(c));
+    match rendered {
+        Some(text) => {
+            artifact::write(destination, &text, compression).unwrap_or_else(|err| fail(&err));
+            if matches.get_flag("summary") {
+                print_summary(&output);
+            }
+        }
+        None if destination.is_some() || compression.is_some() => {
+            fail("--output and --compress need a machine-readable --report format")
+        }
+        None => print_text_report(&output),
+    }
+
+    log_gate_failures(&output.gate_failures);
+    let dry_run = matches.get_flag("gate-dry-run");
+    if dry_run && !output.gate_failures.is_empty() {
+        logging::warn(
+            &format!(
+                "gate dry run: {} gate(s) would fail; not failing the run",
+                output.gate_failures.len()
+            ),
+            &[("dry_run", json!(true))],
+        );
+    }
+    exit(exit_code(&[&output], !dry_run));
+}
+
+// All exits go through here so temporary checkouts are removed.
+fn exit(code: i32) -> ! {
+    git::cleanup_checkouts();
+    std::process::exit(code);
+}
+
+fn fail(err: &str) -> ! {
+    logging::error(err, &[]);
+    exit(EXIT_ERROR);
+}
+
+// `90`, `90s`, `500ms`, `10m` or `1h`; a bare number is seconds.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let n: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", value))?;
+    match unit {
+        "" | "s" => Ok(Duration::from_secs(n)),
+        "ms" => Ok(Duration::from_millis(n)),
+        "m" => Ok(Duration::from_secs(n * 60)),
+        "h" => Ok(Duration::from_secs(n * 3600)),
+        _ => Err(format!(
+            "invalid duration unit '{}' (use ms, s, m or h)",
+            unit
+        )),
+    }
+}
+
+// `5`, `5%` or `2.5%`.
+fn parse_percent(value: &str) -> Result<f64, String> {
+    value
+        .trim_end_matches('%')
+        .parse::<f64>()
+        .ok()
+        .filter(|p| (0.0..=100.0).contains(p))
+        .ok_or_else(|| format!("invalid percentage '{}'", value))
+}
+
+fn load_config(matches: &ArgMatches) -> config::Config {
+    config::load(matches.get_one::<String>("config").map(String::as_str))
+        .unwrap_or_else(|err| fail(&err))
+}
+
+// Config suppressions plus the entries of the ignore file.
+fn suppressions(matches: &ArgMatches, config: &config::Config) -> Vec<config::Suppression> {
+    let entries = ignore::load(matches.get_one::<String>("ignore-file").map(String::as_str))
+        .unwrap_or_else(|err| fail(&err));
+    let mut all = config.suppressions.clone();
+    all.extend(entries);
+    all
+}
+
+fn log_expired_debt(expired: &[rules::ExpiredDebt]) {
+    for e in expired.iter().filter(|e| e.findings > 0) {
+        logging::warn(
+            &format!(
+                "{}: suppression expired on {}, {} finding(s) count again",
+                e.source, e.expires, e.findings
+            ),
+            &[
+                ("expires", json!(e.expires)),
+                ("findings", json!(e.findings)),
+            ],
+        );
+    }
+}
+
+// The command line wins over `complexity-model` in the config.
+fn complexity_model(matches: &ArgMatches, config: &config::Config) -> ComplexityModel {
+    matches
+        .get_one::<String>("complexity-model")
+        .and_then(|name| ComplexityModel::from_name(name))
+        .unwrap_or(config.complexity_model)
+}
+
+fn cli_thresholds(matches: &ArgMatches) -> Thresholds {
+    Thresholds {
+        max_complexity: matches.get_one::<u32>("max-complexity").copied(),
+        max_exit_points: matches.get_one::<usize>("max-exit-points").copied(),
+        max_condition_operands: matches.get_one::<usize>("max-condition-operands").copied(),
+        max_call_chain: matches.get_one::<usize>("max-call-chain").copied(),
+        max_quote_tokens: matches.get_one::<usize>("max-quote-tokens").copied(),
+        max_macro_rules: matches.get_one::<usize>("max-macro-rules").copied(),
+        max_macro_depth: matches.get_one::<usize>("max-macro-depth").copied(),
+        max_macro_tokens: matches.get_one::<usize>("max-macro-tokens").copied(),
+        max_file_loc: matches.get_one::<usize>("max-file-loc").copied(),
+        max_include_lines: matches.get_one::<usize>("max-include-lines").copied(),
+        auto_threshold: matches.get_one::<String>("auto-threshold").cloned(),
+        max_items_per_module: matches.get_one::<usize>("max-items-per-module").copied(),
+        max_api_growth: matches.get_one::<usize>("max-api-growth").copied(),
+        max_total_complexity_increase: matches
+            .get_one::<usize>("max-total-complexity-increase")
+            .copied(),
+        max_new_functions_over_complexity: matches
+            .get_one::<usize>("max-new-functions-over-complexity")
+            .copied(),
+        max_findings: matches.get_one::<usize>("max-findings").copied(),
+        max_debt_growth_per_week: matches.get_one::<f64>("max-debt-growth-per-week").copied(),
+        outlier_complexity: matches.get_one::<usize>("outlier-complexity").copied(),
+        max_functions_over_complexity: matches
+            .get_one::<usize>("max-functions-over-complexity")
+            .copied(),
+        max_percent_functions_over_complexity: matches
+            .get_one::<f64>("max-percent-functions-over-complexity")
+            .copied(),
+        feature_envy_ratio: matches.get_one::<f64>("feature-envy-ratio").copied(),
+    }
+}
+
+// The snapshot tagged `baseline`, else the latest tagged one; `None` (with a
+// warning naming the skipped `gates`) when nothing is tagged yet.
+fn baseline_snapshot<'a>(
+    snapshots: &'a [history::Snapshot],
+    history_file: &str,
+    baseline: Option<&str>,
+    gates: &str,
+) -> Option<&'a history::Snapshot> {
+    match baseline {
+        Some(tag) => Some(
+            history::find_tagged(snapshots, tag)
+                .unwrap_or_else(|| fail(&format!("no snapshot tagged '{}'", tag))),
+        ),
+        None => {
+            let base = snapshots.iter().rev().find(|s| s.tag.is_some());
+            if base.is_none() {
+                logging::warn(
+                    &format!(
+                        "{}: no tagged snapshot to compare against, gate skipped",
+                        gates
+                    ),
+                    &[("history", json!(history_file))],
+                );
+            }
+            base
+        }
+    }
+}
+
+// Growth since a tagged history snapshot, so a change may add code but not
+// spike the debt: total complexity, and functions newly over the outlier
+// level (new ones, or existing ones that crossed it).
+fn delta_gates(
+    report: &Report,
+    history_file: &str,
+    baseline: Option<&str>,
+    thresholds: &Thresholds,
+) -> Vec<GateFailure> {
+    let snapshots = history::load(history_file).unwrap_or_else(|err| fail(&err));
+    let Some(base) = baseline_snapshot(&snapshots, history_file, baseline, "delta gates") else {
+        return Vec::new();
+    };
+    let label = base.tag.clone().unwrap_or_default();
+    let mut failures = Vec::new();
+    if let Some(limit) = thresholds.max_total_complexity_increase {
+        let before = base.cyclomatic_complexity;
+        let after = report.metrics.cyclomatic_complexity;
+        let increase = after.saturating_sub(before);
+        if increase > limit {
+            failures.push(GateFailure {
+                gate: "max-total-complexity-increase".to_string(),
+                value: increase,
+                threshold: limit,
+                location: "project".to_string(),
+                message: format!(
+                    "Total complexity grew by {} since {} ({} → {}), limit is {}.",
+                    increase, label, before, after, limit
+                ),
+            });
+        }
+    }
+    if let Some(limit) = thresholds.max_new_functions_over_complexity {
+        let level = thresholds
+            .outlier_complexity
+            .unwrap_or(DEFAULT_OUTLIER_COMPLEXITY);
+        let was_over: HashSet<(&str, &str)> = base
+            .function_metrics
+            .iter()
+            .filter(|f| f.complexity > level)
+            .map(|f| (f.file.as_str(), f.function.as_str()))
+            .collect();
+        let crossed: Vec<String> = report
+            .files
+            .iter()
+            .flat_map(|f| &f.functions)
+            .filter(|f| f.complexity > level)
+            .filter(|f| !was_over.contains(&(f.file.as_str(), f.function.as_str())))
+            .map(|f| format!("{}::{}", f.file, f.function))
+            .collect();
+        if crossed.len() > limit {
+            failures.push(GateFailure {
+                gate: "max-new-functions-over-complexity".to_string(),
+                value: crossed.len(),
+                threshold: limit,
+                location: crossed.join(", "),
+                message: format!(
+                    "{} functions newly exceed complexity {} since {} (allowed: {}).",
+                    crossed.len(),
+                    level,
+                    label,
+                    limit
+                ),
+            });
+        }
+    }
+    failures
+}
+
+// Compares the current pub item count against a tagged history snapshot.
+fn api_growth_gate(
+    report: &Report,
+    history_file: &str,
+    baseline: Option<&str>,
+    limit: usize,
+) -> Option<GateFailure> {
+    let snapshots = history::load(history_file).unwrap_or_else(|err| fail(&err));
+    let base = baseline_snapshot(&snapshots, history_file, baseline, "max-api-growth")?;
+    let before = api::public_total(&base.public_api);
+    let after = api::public_total(&report.public_api);
+    let growth = after.saturating_sub(before);
+    let label = base.tag.clone().unwrap_or_default();
+    (growth > limit).then(|| GateFailure {
+        gate: "max-api-growth".to_string(),
+        value: growth,
+        threshold: limit,
+        location: "public API".to_string(),
+        message: format!(
+            "Grew by {} pub items since {} ({} → {}), limit is {}.",
+            growth, label, before, after, limit
+        ),
+    })
+}
+
+// Findings left once suppressions and the ignore file are applied; accepted
+// legacy findings live in the ignore file rather than in a raised limit.
+fn findings_gate(report: &Report, limit: usize) -> Option<GateFailure> {
+    let count = report.findings.len();
+    (count > limit).then(|| GateFailure {
+        gate: "max-findings".to_string(),
+        value: count,
+        threshold: limit,
+        location: "project".to_string(),
+        message: format!(
+            "{} findings remain after suppressions (allowed: {}).",
+            count, limit
+        ),
+    })
+}
+
+// Debt growing faster than `limit` minutes per week fails even while every
+// absolute threshold still passes.
+fn debt_growth_gate(velocity: Option<&velocity::Velocity>, limit: f64) -> Option<GateFailure> {
+    let Some(v) = velocity else {
+        logging::warn(
+            "max-debt-growth-per-week: no history snapshot within the window, gate skipped",
+            &[],
+        );
+        return None;
+    };
+    (v.debt_minutes_per_week > limit).then(|| GateFailure {
+        gate: "max-debt-growth-per-week".to_string(),
+        value: v.debt_minutes_per_week.round() as usize,
+        threshold: limit.round() as usize,
+        location: "project".to_string(),
+        message: format!(
+            "Debt grew by {:.0} minutes/week since {} (limit {:.0}).",
+            v.debt_minutes_per_week, v.since, limit
+        ),
+    })
+}
+
+fn read_file_list(spec: &str) -> Result<Vec<String>, String> {
+    let text = if spec == "-" {
+        std::io::read_to_string(std::io::stdin())
+            .map_err(|e| format!("cannot read file list from stdin: {}", e))?
+    } else {
+        fs::read_to_string(spec).map_err(|e| format!("cannot read {}: {}", spec, e))?
+    };
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+fn load_coverage(matches: &ArgMatches) -> Option<coverage::LineCoverage> {
+    matches
+        .get_one::<String>("coverage")
+        .map(|path| coverage::load(path).unwrap_or_else(|err| fail(&err)))
+}
+
+fn load_mutants(matches: &ArgMatches) -> Vec<mutants::Mutant> {
+    matches
+        .get_one::<String>("mutants")
+        .map(|path| mutants::load(path).unwrap_or_else(|err| fail(&err)))
+        .unwrap_or_default()
+}
+
+fn load_build_timings(matches: &ArgMatches) -> Vec<buildtime::UnitTiming> {
+    matches
+        .get_one::<String>("build-timings")
+        .map(|path| buildtime::load(path).unwrap_or_else(|err| fail(&err)))
+        .unwrap_or_default()
+}
+
+fn sample_option(matches: &ArgMatches) -> Option<sample::Sample> {
+    matches
+        .get_one::<f64>("sample")
+        .map(|&percent| sample::Sample {
+            percent,
+            seed: *matches.get_one::<u64>("sample-seed").unwrap(),
+        })
+}
+
+fn clippy_lints(matches: &ArgMatches, path: &str) -> Result<Vec<clippy::Lint>, String> {
+    let json = matches.get_one::<String>("clippy-json");
+    if json.is_none() && !matches.get_flag("run-clippy") {
+        return Ok(Vec::new());
+    }
+    let root = clippy::workspace_root(&git::work_dir(path))?;
+    match json {
+        Some(file) => clippy::load(file, &root),
+        None => clippy::run(&root),
+    }
+}
+
+fn file_issues(sub: &ArgMatches, report: &Report, path: &str, path_root: &str) -> ! {
+    let repo = sub.get_one::<String>("repo");
+    let linker = issues::SpanLinker::new(path, path_root, repo.map(String::as_str));
+    let drafts = issues::drafts(report, *sub.get_one::<usize>("top").unwrap(), &linker);
+    if !sub.get_flag("create") {
+        for d in &drafts {
+            println!("--- {}\n{}", d.title, d.body);
+        }
+        println!(
+            "{} issue(s) would be filed; pass --create to open them.",
+            drafts.len()
+        );
+        exit(EXIT_OK);
+    }
+
+    let tracker = match sub.get_one::<String>("tracker").unwrap().as_str() {
+        "jira" => issues::Tracker::Jira {
+            project: sub.get_one::<String>("project").unwrap().clone(),
+        },
+        _ => issues::Tracker::GitHub {
+            repo: repo.cloned(),
+        },
+    };
+    for d in &drafts {
+        if issues::exists(&tracker, &d.fingerprint).unwrap_or_else(|err| fail(&err)) {
+            println!("already filed: {} ({})", d.title, d.fingerprint);
+            continue;
+        }
+        let created = issues::create(&tracker, d).unwrap_or_else(|err| fail(&err));
+        println!("{} {}", created, d.title);
+    }
+    exit(EXIT_OK);
+}
+
+fn history_path(matches: &ArgMatches) -> &str {
+    matches
+        .get_one::<String>("history")
+        .map_or(history::DEFAULT_HISTORY_FILE, String::as_str)
+}
+
+// --- `trend`: snapshot listing and release-to-release comparison ---
+fn run_trend(matches: &ArgMatches) {
+    let snapshots = history::load(history_path(matches)).unwrap_or_else(|err| fail(&err));
+    let json = matches.get_one::<String>("report").unwrap() == "json";
+
+    let Some(mut between) = matches.get_many::<String>("between") else {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&snapshots).unwrap());
+        } else {
+            history::print_snapshots(&snapshots);
+        }
+        return;
+    };
+    let (from, to) = (between.next().unwrap(), between.next().unwrap());
+    let find = |tag: &str| {
+        history::find_tagged(&snapshots, tag)
+            .unwrap_or_else(|| fail(&format!("no snapshot tagged '{}'", tag)))
+    };
+    let comparison = history::compare(
+        find(from),
+        find(to),
+        *matches.get_one::<usize>("limit").unwrap(),
+    );
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&comparison).unwrap());
+    } else {
+        history::print_comparison(&comparison);
+    }
+}
+
+// --- `run`: analyze named targets from the config file ---
+fn run_targets(matches: &ArgMatches, config: &config::Config) {
+    let selected = matches.get_one::<String>("target").unwrap();
+    if config.targets.is_empty() {
+        fail("no [targets] defined in the config file");
+    }
+    let names: Vec<&String> = if selected == "all" {
+        config.targets.keys().collect()
+    } else if config.targets.contains_key(selected) {
+        vec![selected]
+    } else {
+        fail(&format!("unknown target '{}'", selected));
+    };
+
+    let cli = cli_thresholds(matches);
+    let cli_profile = matches.get_one::<String>("profile").map(String::as_str);
+    let mut multi = MultiTargetReport {
+        targets: BTreeMap::new(),
+        totals: CodeMetrics::default(),
+    };
+    let mut finding_limits: BTreeMap<&str, Option<usize>> = BTreeMap::new();
+
+    for name in names {
+        let target = &config.targets[name];
+        // Precedence: profile < config [thresholds] < target thresholds < CLI flags.
+        let thresholds = config
+            .resolve_thresholds(
+                cli_profile.or(target.profile.as_deref()),
+                &target.thresholds.clone().overlay(&cli),
+            )
+            .unwrap_or_else(|err| fail(&format!("target '{}': {}", name, err)));
+        let opts = AnalysisOptions {
+            roots: if target.paths.is_empty() {
+                vec![".".to_string()]
+            } else {
+                target.paths.clone()
+            },
+            excludes: target.exclude.clone(),
+            files_from: None,
+            thresholds,
+            discount_commented_code: matches.get_flag("discount-commented-code"),
+            new_code_since: matches.get_one::<String>("new-code-since").cloned(),
+            distribution: config.distribution.clone(),
+            complexity_model: complexity_model(matches, config),
+            timeout_per_file: matches.get_one::<Duration>("timeout-per-file").copied(),
+            timeout: matches.get_one::<Duration>("timeout").copied(),
+            cache: matches.get_one::<String>("cache").cloned(),
+            profile_self: matches.get_flag("profile-self"),
+            lints: Vec::new(),
+            check_format: matches.get_flag("check-format"),
+            doc_code: !matches.get_flag("exclude-doc-code"),
+            coverage: load_coverage(matches),
+            mutants: load_mutants(matches),
+            derived_metrics: config.derived_metrics.clone(),
+            components: config.components.clone(),
+            grades: config.grades,
+            build_timings: load_build_timings(matches),
+            shard: matches.get_one::<shard::Shard>("shard").copied(),
+            shard_reports: Vec::new(),
+            sample: sample_option(matches),
+            snippet_lines: matches.get_one::<usize>("with-snippets").copied(),
+            include_vendored: matches.get_flag("include-vendored"),
+            use_cargo_metadata: matches.get_flag("use-cargo-metadata"),
+            fail_fast: false,
+            cfg_features: None,
+        };
+        let mut report =
+            run_analysis(&opts).unwrap_or_else(|err| fail(&format!("target '{}': {}", name, err)));
+        report.meta.describe_config(config);
+        finding_limits.insert(name, opts.thresholds.max_findings);
+        merge_metrics(&mut multi.totals, &report.metrics);
+        multi.targets.insert(name.clone(), report);
+    }
+    multi.totals.kloc = multi.totals.loc as f64 / 1000.0;
+
+    // Target paths are relative to the working directory, so report them that way.
+    let path_root = matches
+        .get_one::<String>("path-root")
+        .map_or(".", String::as_str);
+    multi.totals.file_with_max_complexity =
+        relative_path(&multi.totals.file_with_max_complexity, path_root);
+    let suppressions = suppressions(matches, config);
+    let today = logging::today();
+    for (name, report) in multi.targets.iter_mut() {
+        canonicalize_report(report, path_root);
+        rules::apply(report, &suppressions, None, Vec::new(), &today);
+        log_expired_debt(&report.expired_debt);
+        if let Some(limit) = finding_limits[name.as_str()] {
+            report.gate_failures.extend(findings_gate(report, limit));
+        }
+    }
+
+    if matches.get_one::<String>("report").unwrap() == "json" {
+        println!("{}", serde_json::to_string_pretty(&multi).unwrap());
+    } else {
+        for (name, report) in &multi.targets {
+            term::heading("🎯", &tr!("target", name));
+            print_text_report(report);
+            println!();
+        }
+        term::heading("🎯", &tr!("all-targets", multi.targets.len()));
+        let totals = &multi.totals;
+        term::print_pairs(&[
+            (tr!("loc", totals.loc), term::Tone::Plain),
+            (
+                tr!("complexity", totals.cyclomatic_complexity),
+                term::Tone::Plain,
+            ),
+            (tr!("functions", totals.functions), term::Tone::Plain),
+        ]);
+        let mut table = term::Table::new(&[term::Align::Left, term::Align::Left]);
+        for (name, report) in &multi.targets {
+            let status = if report.gate_failures.is_empty() {
+                term::tone(&tr!("target-pass"), term::Tone::Good)
+            } else {
+                term::tone(&tr!("target-fail"), term::Tone::Bad)
+            };
+            table.row(vec![name.clone(), status]);
+        }
+        table.print();
+    }
+
+    for (name, report) in &multi.targets {
+        for f in &report.gate_failures {
+            logging::warn(
+                &format!("[{}] {}: {}", name, f.location, f.message),
+                &[
+                    ("target", json!(name)),
+                    ("gate", json!(f.gate)),
+                    ("value", json!(f.value)),
+                    ("threshold", json!(f.threshold)),
+                    ("file", json!(f.location)),
+                ],
+            );
+        }
+    }
+    let reports: Vec<&Report> = multi.targets.values().collect();
+    exit(exit_code(&reports, true));
+}
+
+// --- Text report ---
+// A few lines for CI logs, on stderr so stdout stays machine-readable.
+// One info record, so `--log-format json` gets the numbers as fields
+// instead of loose text lines on stderr.
+fn print_summary(report: &Report) {
+    let metrics = &report.metrics;
+    let average = metrics.cyclomatic_complexity as f64 / metrics.functions.max(1) as f64;
+    let top = &report.top_functions[..report.top_functions.len().min(3)];
+    let mut lines = vec![tr!(
+        "summary",
+        grade_label(&report.grades.grade),
+        report.files.len(),
+        metrics.loc,
+        metrics.functions,
+        metrics.cyclomatic_complexity,
+        format!("{:.2}", average),
+        format!("{:.2}", report.maintainability_index)
+    )];
+    lines.extend(top.iter().map(|f| {
+        format!(
+            "  {}::{} {}",
+            f.file,
+            f.function,
+            tr!("cell-complexity", f.complexity)
+        )
+    }));
+    lines.push(format!(
+        "  {}",
+        tr!(
+            "summary-counts",
+            report.findings.len(),
+            report.gate_failures.len()
+        )
+    ));
+    let top: Vec<serde_json::Value> = top
+        .iter()
+        .map(|f| json!({"file": f.file, "function": f.function, "complexity": f.complexity}))
+        .collect();
+    logging::info(
+        &lines.join("\n"),
+        &[
+            ("grade", json!(report.grades.grade)),
+            ("files", json!(report.files.len())),
+            ("loc", json!(metrics.loc)),
+            ("functions", json!(metrics.functions)),
+            ("complexity", json!(metrics.cyclomatic_complexity)),
+            ("average_complexity", json!(average)),
+            ("maintainability_index", json!(report.maintainability_index)),
+            ("top_functions", json!(top)),
+            ("findings", json!(report.findings.len())),
+            ("gate_failures", json!(report.gate_failures.len())),
+        ],
+    );
+}
+
+fn grade_label(grade: &str) -> String {
+    if grade.is_empty() {
+        tr!("grade-none")
+    } else {
+        grade.to_string()
+    }
+}
+
+// One line saying what produced the report, for pasted or archived logs.
+fn print_meta(meta: &meta::Meta) {
+    let mut parts = vec![
+        format!("{} {}", meta.tool, meta.version),
+        meta.scanned_at.clone(),
+    ];
+    if let Some(commit) = &meta.commit {
+        let short = &commit[..commit.len().min(12)];
+        let mut state: Vec<String> = meta.branch.iter().cloned().collect();
+        if meta.dirty == Some(true) {
+            state.push(tr!("meta-dirty"));
+        }
+        parts.push(if state.is_empty() {
+            tr!("meta-commit", short)
+        } else {
+            format!("{} ({})", tr!("meta-commit", short), state.join(", "))
+        });
+    }
+    if let (Some(file), Some(hash)) = (&meta.config_file, &meta.config_hash) {
+        parts.push(tr!("meta-config", file, hash));
+    }
+    println!("{}\n", term::dim(&parts.join(" · ")));
+}
+
+fn print_text_report(report: &Report) {
+    use term::Align::{Left, Right};
+    let metrics = &report.metrics;
+    print_meta(&report.meta);
+    println!("{}", term::bold(&tr!("code-metrics")));
+    let t = &report.meta.thresholds;
+    let level = t.outlier_complexity.unwrap_or(DEFAULT_OUTLIER_COMPLEXITY);
+    let plain = |line: String| (line, term::Tone::Plain);
+    let mut lines = vec![(
+        tr!(
+            "grade",
+            grade_label(&report.grades.grade),
+            format!("{:.1}", report.grades.score)
+        ),
+        term::grade_tone(&report.grades.grade),
+    )];
+    lines.push(plain(tr!("loc", metrics.loc)));
+    lines.push(plain(tr!("kloc", format!("{:.2}", metrics.kloc))));
+    lines.push(plain(tr!("complexity", metrics.cyclomatic_complexity)));
+    let average = metrics.cyclomatic_complexity as f64 / metrics.functions.max(1) as f64;
+    lines.push(plain(tr!("avg-complexity", format!("{:.2}", average))));
+    let buckets = bucket_labels(&metrics.cyclomatic_distribution_bounds)
+        .iter()
+        .zip(&metrics.cyclomatic_distribution)
+        .map(|(label, n)| format!("{}: {}", label, n))
+        .collect::<Vec<_>>()
+        .join(", ");
+    lines.push(plain(tr!("distribution", buckets)));
+    lines.push(plain(tr!("functions", metrics.functions)));
+    lines.push(plain(tr!("longest-function", metrics.longest_function_loc)));
+    lines.push(plain(tr!("max-nesting", metrics.max_nesting_depth)));
+    let density = metrics.comments as f64 / metrics.loc.max(1) as f64 * 100.0;
+    lines.push(plain(tr!(
+        "comment-density",
+        format!("{:.2}", density),
+        metrics.comment_lines.doc,
+        metrics.comment_lines.inline,
+        metrics.comment_lines.header
+    )));
+    lines.push((
+        tr!("commented-out", metrics.commented_out_code),
+        if metrics.commented_out_code > 0 {
+            term::Tone::Warn
+        } else {
+            term::Tone::Plain
+        },
+    ));
+    lines.push(plain(tr!(
+        "maintainability",
+        format!("{:.2}", report.maintainability_index)
+    )));
+    lines.push(plain(tr!(
+        "max-complexity-file",
+        &metrics.file_with_max_complexity
+    )));
+    lines.push((
+        tr!("max-file-complexity", metrics.max_file_complexity),
+        term::against(
+            metrics.max_file_complexity,
+            t.max_complexity.map(|m| m as usize),
+        ),
+    ));
+    let d = &report.function_distributions;
+    for (name, m) in [
+        ("label-complexity", &d.complexity),
+        ("label-loc", &d.loc),
+        ("label-nesting", &d.nesting),
+    ] {
+        lines.push(plain(tr!(
+            "percentiles",
+            tr!(name),
+            m.p50,
+            m.p75,
+            m.p90,
+            m.p95,
+            m.p99,
+            m.max
+        )));
+    }
+    term::print_pairs(&lines);
+
+    // --- Top offenders list ---
+    term::heading("⚠️", &tr!("top-functions"));
+    let mut table = term::Table::new(&[Right, Left, Left, Left, Left, Left, Left]).shrink(1);
+    for (i, f) in report.top_functions.iter().take(5).enumerate() {
+        let extras = [
+            f.coverage
+                .map(|c| tr!("cell-coverage", format!("{:.0}", c))),
+            f.mutation_score
+                .map(|m| tr!("cell-mutants-caught", format!("{:.0}", m))),
+            f.is_recursive.then(|| tr!("cell-recursive")),
+        ];
+        table.row(vec![
+            format!("{}.", i + 1),
+            format!("{}::{}", f.file, f.function),
+            term::tone(
+                &tr!("cell-complexity", f.complexity),
+                term::against(f.complexity, Some(level)),
+            ),
+            tr!("cell-cognitive", f.cognitive_complexity),
+            tr!("cell-loc", f.loc),
+            tr!("cell-exits", f.exit_points),
+            extras.into_iter().flatten().collect::<Vec<_>>().join(" "),
+        ]);
+    }
+    table.print();
+
+    if !report.dangerous_functions.is_empty() {
+        term::heading(
+            "☠️",
+            &tr!(
+                "dangerous-functions",
+                coverage::DANGEROUS_COMPLEXITY,
+                coverage::DANGEROUS_COVERAGE
+            ),
+        );
+        let mut table = term::Table::new(&[Left; 4]).shrink(0);
+        for d in &report.dangerous_functions {
+            table.row(vec![
+                format!("{}:{}", d.file, d.line),
+                d.function.clone(),
+                tr!("cell-complexity", d.complexity),
+                tr!("cell-coverage", format!("{:.0}", d.coverage)),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.weakly_tested.is_empty() {
+        term::heading("🧬", &tr!("weakly-tested"));
+        let mut table = term::Table::new(&[Left; 7]).shrink(0);
+        for w in &report.weakly_tested {
+            table.row(vec![
+                format!("{}:{}", w.file, w.line),
+                w.function.clone(),
+                tr!("cell-complexity", w.complexity),
+                tr!("cell-mutants", w.mutants),
+                tr!("cell-survived", w.survived),
+                tr!("cell-caught", format!("{:.0}", w.mutation_score)),
+                tr!("cell-priority", format!("{:.1}", w.priority)),
+            ]);
+        }
+        table.print();
+    }
+
+    if let Some(v) = &report.debt_velocity {
+        term::heading(
+            "📉",
+            &tr!(
+                "debt-velocity",
+                v.since,
+                format!("{:.1}", v.weeks),
+                format!("{:+.1}", v.complexity_per_week),
+                format!("{:+.1}", v.violations_per_week),
+                v.debt_minutes,
+                format!("{:+.0}", v.debt_minutes_per_week)
+            ),
+        );
+    }
+
+    if let Some(o) = report
+        .orphaned_hotspots
+        .as_ref()
+        .filter(|o| !o.hotspots.is_empty())
+    {
+        term::heading("🏚️", &tr!("orphaned-hotspots", o.months, &o.inactive_since));
+        let mut table = term::Table::new(&[Left; 5]).shrink(0);
+        for h in &o.hotspots {
+            let owners: Vec<String> = h
+                .inactive_owners
+                .iter()
+                .map(|w| tr!("cell-last-commit", w.author, w.last_commit))
+                .collect();
+            table.row(vec![
+                h.file.clone(),
+                tr!("cell-complexity-max", h.total_complexity, h.max_complexity),
+                tr!("cell-inactive-share", format!("{:.0}", h.inactive_share)),
+                tr!("cell-recent-commits", h.recent_commits),
+                owners.join(", "),
+            ]);
+        }
+        table.print();
+    }
+
+    if let Some(m) = &report.cfg_matrix {
+        term::heading("🎛️", &tr!("cfg-matrix", m.combinations.len()));
+        let mut table = term::Table::new(&[Left, Right, Left, Right, Left]);
+        for r in &m.ranges {
+            table.row(vec![
+                format!("{}:", r.metric),
+                format!("{:.2}", r.min),
+                format!("({}) ..", r.min_in),
+                format!("{:.2}", r.max),
+                format!("({})", r.max_in),
+            ]);
+        }
+        table.print();
+        let mut table = term::Table::new(&[Left; 7]);
+        for c in &m.combinations {
+            let features = if c.features.is_empty() {
+                "-".to_string()
+            } else {
+                c.features.join(",")
+            };
+            table.row(vec![
+                format!("[{}]", c.name),
+                tr!("cell-features", features),
+                tr!("cell-complexity", c.cyclomatic_complexity),
+                tr!("cell-functions", c.functions),
+                tr!("cell-nloc", c.nloc),
+                tr!("cell-max", c.max_function_complexity),
+                tr!("cell-findings", c.findings),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.compile_time_debt.is_empty() {
+        term::heading("🐢", &tr!("compile-time-debt"));
+        let mut table = term::Table::new(&[Left, Right, Right, Left, Left]).shrink(0);
+        for c in &report.compile_time_debt {
+            table.row(vec![
+                format!(
+                    "{}{}",
+                    c.crate_name,
+                    if c.complex_and_slow { " *" } else { "" }
+                ),
+                tr!("cell-seconds", format!("{:.1}", c.build_seconds)),
+                tr!(
+                    "cell-seconds-per-kloc",
+                    format!("{:.2}", c.seconds_per_kloc)
+                ),
+                tr!(
+                    "cell-avg-complexity",
+                    format!("{:.2}", c.average_complexity)
+                ),
+                tr!("cell-loc", c.loc),
+            ]);
+            for h in &c.hotspots {
+                table.row(vec![
+                    format!("  {}", h.module),
+                    String::new(),
+                    String::new(),
+                    tr!("cell-complexity", h.complexity),
+                    tr!("cell-loc", h.loc),
+                ]);
+            }
+        }
+        table.print();
+    }
+
+    if !report.risk_ranking.is_empty() {
+        term::heading("🎯", &tr!("risk-ranking"));
+        let mut table = term::Table::new(&[Right, Left, Left, Left, Left, Left]).shrink(1);
+        for (i, r) in report.risk_ranking.iter().take(5).enumerate() {
+            table.row(vec![
+                format!("{}.", i + 1),
+                format!("{}::{}", r.file, r.function),
+                tr!("cell-risk", r.risk),
+                tr!("cell-complexity", r.complexity),
+                tr!("cell-references", r.references),
+                tr!("cell-lints", r.lints),
+            ]);
+        }
+        table.print();
+    }
+
+    if let Some(drift) = &report.format_drift {
+        term::heading(
+            "🧽",
+            &tr!(
+                "format-drift",
+                drift.files_deviating,
+                drift.files_checked,
+                drift.lines_deviating
+            ),
+        );
+        let mut table = term::Table::new(&[Left, Right]).shrink(0);
+        for d in drift.files.iter().take(5) {
+            table.row(vec![d.file.clone(), tr!("cell-lines", d.lines)]);
+        }
+        table.print();
+    }
+
+    if let Some(doc) = report.doc_code.as_ref().filter(|d| d.blocks > 0) {
+        term::heading(
+            "📚",
+            &tr!(
+                "doc-code",
+                doc.blocks,
+                doc.loc,
+                doc.complexity,
+                doc.unparsed
+            ),
+        );
+        let mut table = term::Table::new(&[Left, Left, Right, Right, Left]).shrink(0);
+        for d in doc.files.iter().take(5) {
+            table.row(vec![
+                d.file.clone(),
+                format!("({})", d.kind),
+                tr!("cell-blocks", d.blocks),
+                tr!("cell-n-loc", d.loc),
+                tr!("cell-complexity-total", d.complexity),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.class_metrics.is_empty() {
+        term::heading("🏛️", &tr!("heaviest-types"));
+        let mut table = term::Table::new(&[Left; 7]).shrink(0);
+        for c in report.class_metrics.iter().take(5) {
+            table.row(vec![
+                format!("{}:{}", c.file, c.line),
+                c.name.clone(),
+                tr!("cell-wmc", c.wmc),
+                tr!("cell-methods", c.methods),
+                tr!("cell-max", c.max_method_complexity),
+                tr!("cell-fields", c.fields),
+                tr!("cell-lcom", c.lcom),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.lints.is_empty() {
+        term::heading("🧹", &tr!("clippy-lints"));
+        let mut table = term::Table::new(&[Left, Right, Left]).shrink(0);
+        for l in report.lints.iter().take(10) {
+            let kinds: Vec<String> = l
+                .lints
+                .iter()
+                .map(|(lint, n)| format!("{}={}", lint, n))
+                .collect();
+            table.row(vec![
+                l.file.clone(),
+                l.total.to_string(),
+                format!("({})", kinds.join(", ")),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.macro_definitions.is_empty() {
+        term::heading("🧩", &tr!("macro-definitions"));
+        let mut table = term::Table::new(&[Left, Left, Right, Right, Left]).shrink(0);
+        for m in report.macro_definitions.iter().take(5) {
+            table.row(vec![
+                format!("{}:{}", m.file, m.line),
+                format!("{}!", m.name),
+                tr!("cell-tokens", m.tokens),
+                tr!("cell-rules", m.rules),
+                tr!("cell-depth", m.depth),
+            ]);
+        }
+        table.print();
+    }
+
+    if let Some(worst) = report.magic_values.first() {
+        term::heading("🔢", &tr!("magic-values"));
+        let mut table = term::Table::new(&[Left, Right, Left]).shrink(0);
+        for m in report.magic_values.iter().take(5) {
+            table.row(vec![
+                m.file.clone(),
+                format!("{:.1}", m.per_100_loc),
+                tr!("cell-magic-counts", m.numbers, m.strings),
+            ]);
+        }
+        table.print();
+        let detail = report.files.iter().find(|f| f.file == worst.file);
+        let mut table = term::Table::new(&[Left; 4]).shrink(0);
+        for v in detail.iter().flat_map(|f| &f.magic_values).take(5) {
+            table.row(vec![
+                format!("    {}:{}", worst.file, v.line),
+                v.function.clone(),
+                v.kind.clone(),
+                v.value.clone(),
+            ]);
+        }
+        table.print();
+    }
+
+    for d in &report.derived_metrics {
+        let over = match d.max {
+            Some(max) => tr!("derived-over", d.functions_over, max),
+            None => String::new(),
+        };
+        term::heading("🧮", &tr!("derived", d.name, d.expression, over));
+        let mut table = term::Table::new(&[Left, Right]).shrink(0);
+        for v in &d.top {
+            table.row(vec![
+                format!("{}::{}", v.file, v.function),
+                format!("{:.2}", v.value),
+            ]);
+        }
+        table.print();
+    }
+
+    let vendored = &report.vendored;
+    if !vendored.files.is_empty() {
+        term::heading(
+            "📎",
+            &tr!(
+                "vendored",
+                tr!(if vendored.gated {
+                    "vendored-gated"
+                } else {
+                    "vendored-not-gated"
+                }),
+                vendored.files.len(),
+                vendored.loc,
+                vendored.functions,
+                vendored.total_complexity,
+                vendored.max_complexity
+            ),
+        );
+    }
+
+    if let Some(sample) = &report.sample {
+        term::heading(
+            "🎲",
+            &tr!(
+                "sample",
+                sample.percent,
+                sample.seed,
+                sample.sampled_files,
+                sample.population_files
+            ),
+        );
+        let mut table = term::Table::new(&[Left, Right, Left]);
+        for (name, e) in [
+            ("label-loc", &sample.loc),
+            ("label-functions", &sample.functions),
+            ("label-complexity", &sample.complexity),
+        ] {
+            table.row(vec![
+                format!("{}:", tr!(name)),
+                format!("~{:.0}", e.estimate),
+                format!("({:.0}–{:.0})", e.low, e.high),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.roots.is_empty() {
+        term::heading("📂", &tr!("roots"));
+        let mut table = term::Table::new(&[Left, Right, Right, Right, Left]).shrink(0);
+        for r in &report.roots {
+            table.row(vec![
+                format!("{}:", r.root),
+                tr!("cell-files", r.files),
+                tr!("cell-n-loc", r.loc),
+                tr!("cell-n-functions", r.functions),
+                tr!(
+                    "cell-complexity-total-max",
+                    r.total_complexity,
+                    r.max_complexity
+                ),
+            ]);
+        }
+        table.print();
+    }
+
+    let grades = &report.grades;
+    if !grades.grade.is_empty() {
+        let b = &grades.boundaries;
+        term::heading("🎓", &tr!("grades", b.a, b.b, b.c, b.d));
+        let mut table = term::Table::new(&[Left, Right, Right, Right, Right, Right]);
+        for (label, per) in [
+            ("grade-functions", &grades.functions),
+            ("grade-files", &grades.files),
+        ] {
+            let mut row = vec![tr!(label)];
+            row.extend(grades::LETTERS.iter().map(|l| {
+                term::tone(
+                    &format!("{} {}", l, per.get(*l).copied().unwrap_or(0)),
+                    term::grade_tone(l),
+                )
+            }));
+            table.row(row);
+        }
+        table.print();
+        let mut table = term::Table::new(&[Left, Left, Right, Right, Right]);
+        for c in &grades.crates {
+            table.row(vec![
+                format!("{}:", c.crate_name),
+                term::tone(&c.grade, term::grade_tone(&c.grade)),
+                format!("({:.1})", c.score),
+                tr!("cell-files", c.files),
+                tr!("cell-n-functions", c.functions),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.components.is_empty() {
+        term::heading("🧩", &tr!("components"));
+        let mut table = term::Table::new(&[Left, Right, Right, Right, Left, Left, Right]).shrink(5);
+        for c in &report.components {
+            table.row(vec![
+                format!("{}:", c.component),
+                tr!("cell-files", c.files),
+                tr!("cell-n-loc", c.loc),
+                tr!("cell-n-functions", c.functions),
+                tr!(
+                    "cell-complexity-avg-max",
+                    c.total_complexity,
+                    format!("{:.2}", c.average_complexity),
+                    c.max_complexity
+                ),
+                c.most_complex_function.clone(),
+                tr!("cell-n-findings", c.findings),
+            ]);
+        }
+        table.print();
+    }
+
+    let hygiene = &report.hygiene;
+    if !hygiene.files.is_empty() {
+        term::heading(
+            "🧹",
+            &tr!(
+                "hygiene",
+                hygiene.files_with_crlf,
+                hygiene.files_with_mixed_line_endings,
+                hygiene.files_mixing_indentation,
+                hygiene.trailing_whitespace_lines
+            ),
+        );
+        let mut table = term::Table::new(&[Left; 6]).shrink(0);
+        for f in hygiene.files.iter().take(5) {
+            let h = &f.hygiene;
+            table.row(vec![
+                f.file.clone(),
+                tr!("cell-crlf", h.crlf_lines),
+                tr!("cell-tabs", h.tab_indented_lines),
+                tr!("cell-spaces", h.space_indented_lines),
+                tr!("cell-mixed", h.mixed_indent_lines),
+                tr!("cell-trailing", h.trailing_whitespace_lines),
+            ]);
+        }
+        table.print();
+    }
+
+    let surface = &report.security_surface;
+    if !surface.files.is_empty() {
+        let t = &surface.totals;
+        term::heading(
+            "🔐",
+            &tr!(
+                "security-surface",
+                t.process_commands,
+                t.sql_concatenation,
+                t.transmutes,
+                t.secret_env_reads,
+                t.ffi
+            ),
+        );
+        let mut table = term::Table::new(&[Left; 6]).shrink(0);
+        for f in surface.files.iter().take(10) {
+            let c = &f.counts;
+            table.row(vec![
+                f.file.clone(),
+                tr!("cell-commands", c.process_commands),
+                tr!("cell-sql", c.sql_concatenation),
+                tr!("cell-transmute", c.transmutes),
+                tr!("cell-secrets", c.secret_env_reads),
+                tr!("cell-ffi", c.ffi),
+            ]);
+        }
+        table.print();
+    }
+
+    let quality = &report.test_quality;
+    if quality.tests > 0 {
+        term::heading(
+            "🧪",
+            &tr!(
+                "test-quality",
+                quality.tests,
+                format!("{:.1}", quality.assertions_per_test),
+                format!("{:.1}", quality.mean_loc),
+                quality.without_assertions
+            ),
+        );
+        let mut table = term::Table::new(&[Left; 3]).shrink(0);
+        for t in quality.assertion_free.iter().take(10) {
+            table.row(vec![
+                format!("{}:{}", t.file, t.line),
+                t.function.clone(),
+                tr!("cell-no-assertions", t.loc),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.ffi_surface.is_empty() {
+        term::heading("🔌", &tr!("ffi-surface"));
+        let mut table = term::Table::new(&[Left; 6]);
+        for c in &report.ffi_surface {
+            table.row(vec![
+                format!("{}:", crate_label(&c.crate_name)),
+                tr!("cell-extern-blocks", c.extern_blocks),
+                tr!("cell-foreign-fns", c.foreign_functions),
+                format!("#[no_mangle]={}", c.exported_functions),
+                tr!("cell-unsafe-ffi-calls", c.unsafe_call_sites),
+                tr!("cell-in-files", c.files.len()),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.error_handling.is_empty() {
+        term::heading("🧯", &tr!("error-handling"));
+        let mut table = term::Table::new(&[Left; 7]);
+        for c in &report.error_handling {
+            table.row(vec![
+                format!("{}:", crate_label(&c.crate_name)),
+                tr!("cell-result-fns", c.functions_returning_result, c.functions),
+                format!("?={}", c.try_ops),
+                tr!("cell-unwraps", c.unwraps),
+                tr!("cell-ratio", format!("{:.2}", c.try_to_unwrap_ratio)),
+                format!("Box<dyn Error>={}", c.box_dyn_error),
+                format!("anyhow={}", c.anyhow_uses),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.changed_functions.is_empty() {
+        let worse = report
+            .changed_functions
+            .iter()
+            .filter(|c| c.status == "worsened")
+            .count();
+        term::heading(
+            "🔀",
+            &tr!("changed-functions", report.changed_functions.len(), worse),
+        );
+        let mut table = term::Table::new(&[Left, Left, Left, Left, Right]).shrink(0);
+        for c in &report.changed_functions {
+            let before = c.before.map_or("-".to_string(), |b| {
+                format!("{}/{}", b.complexity, b.cognitive_complexity)
+            });
+            table.row(vec![
+                format!("{}:{}", c.file, c.line),
+                c.function.clone(),
+                format!("[{}]", tr!(&format!("status-{}", c.status))),
+                tr!(
+                    "cell-cc-cognitive",
+                    before,
+                    c.after.complexity,
+                    c.after.cognitive_complexity
+                ),
+                format!("({:+})", c.complexity_delta),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.oversized_files.is_empty() {
+        term::heading("📦", &tr!("oversized-files", report.oversized_files.len()));
+        let mut table = term::Table::new(&[Left; 4]).shrink(1);
+        for o in &report.oversized_files {
+            let c = &o.item_counts;
+            table.row(vec![
+                o.module.clone(),
+                format!("({})", o.file),
+                o.reasons.join("; "),
+                tr!(
+                    "cell-item-counts",
+                    c.functions,
+                    c.impls,
+                    c.structs + c.enums + c.unions + c.type_aliases,
+                    c.data()
+                ),
+            ]);
+        }
+        table.print();
+    }
+
+    if let Some(auto) = &report.auto_thresholds {
+        term::heading("📐", &tr!("auto-thresholds", auto.method, auto.functions));
+        let mut table = term::Table::new(&[Left, Right, Left, Left]);
+        for (what, l) in [
+            ("label-complexity", &auto.complexity),
+            ("label-loc", &auto.loc),
+        ] {
+            table.row(vec![
+                tr!(what),
+                format!("> {:.1}", l.limit),
+                tr!(
+                    "cell-median-spread",
+                    format!("{:.1}", l.median),
+                    if auto.method == "mad" { "MAD" } else { "p97.5" },
+                    format!("{:.1}", l.spread)
+                ),
+                tr!("cell-outliers", l.flagged),
+            ]);
+        }
+        table.print();
+    }
+
+    let large: Vec<&includes::IncludeSite> =
+        report.includes.iter().filter(|i| i.lines > 0).collect();
+    if !large.is_empty() {
+        term::heading(
+            "📎",
+            &tr!(
+                "includes",
+                report.includes.len(),
+                large.iter().map(|i| i.lines).sum::<usize>()
+            ),
+        );
+        let mut table = term::Table::new(&[Left, Left, Right, Right]).shrink(0);
+        for i in large.iter().take(10) {
+            table.row(vec![
+                format!("{}:{}", i.file, i.line),
+                format!("{}!(\"{}\")", i.kind, i.target),
+                tr!("cell-lines", i.lines),
+                tr!("cell-bytes", i.bytes),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.orphan_files.is_empty() {
+        term::heading("👻", &tr!("orphan-files", report.orphan_files.len()));
+        let mut table = term::Table::new(&[Left]).shrink(0);
+        for file in &report.orphan_files {
+            table.row(vec![file.clone()]);
+        }
+        table.print();
+    }
+
+    let mut dirs: Vec<&treemap::TreeNode> = report
+        .directory_tree
+        .children
+        .iter()
+        .filter(|c| c.kind == "dir" && c.risk > 0)
+        .collect();
+    if !dirs.is_empty() {
+        term::heading("🗂️", &tr!("directories"));
+        dirs.sort_by(|a, b| b.risk.cmp(&a.risk).then_with(|| a.path.cmp(&b.path)));
+        let mut table = term::Table::new(&[Left; 5]).shrink(0);
+        for d in dirs {
+            table.row(vec![
+                format!("{}/", d.path),
+                tr!("cell-risk", d.risk),
+                tr!("cell-complexity", d.complexity),
+                tr!("cell-loc", d.loc),
+                tr!("cell-worst", d.worst_child.as_deref().unwrap_or("-")),
+            ]);
+        }
+        table.print();
+    }
+
+    if report
+        .public_api
+        .iter()
+        .any(|c| c.public + c.crate_visible > 0)
+    {
+        term::heading("📦", &tr!("public-api"));
+        let kinds = |by_kind: &BTreeMap<String, usize>| {
+            by_kind
+                .iter()
+                .map(|(k, n)| format!("{}={}", k, n))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let mut table = term::Table::new(&[Left; 5]);
+        for c in &report.public_api {
+            table.row(vec![
+                format!("{}:", crate_label(&c.crate_name)),
+                format!("pub={}", c.public),
+                format!("[{}]", kinds(&c.public_by_kind)),
+                format!("pub(crate)={}", c.crate_visible),
+                format!("[{}]", kinds(&c.crate_visible_by_kind)),
+            ]);
+        }
+        table.print();
+    }
+
+    let heavy: Vec<_> = report
+        .lifetime_density
+        .iter()
+        .filter(|m| m.high_density)
+        .collect();
+    if !heavy.is_empty() {
+        term::heading("🔗", &tr!("lifetime-density"));
+        let mut table = term::Table::new(&[Left, Right, Left]);
+        for m in heavy {
+            table.row(vec![
+                format!("{}:", m.module),
+                tr!("cell-lifetimes-per-item", format!("{:.2}", m.density)),
+                tr!(
+                    "cell-lifetime-counts",
+                    m.lifetime_params,
+                    m.lifetime_annotations,
+                    m.items
+                ),
+            ]);
+        }
+        table.print();
+    }
+
+    if let Some(ct) = &report.compile_time {
+        term::heading(
+            "⏱️",
+            &tr!(
+                "compile-time",
+                ct.const_fn.functions,
+                ct.const_fn.total_complexity,
+                format!("{:.1}", ct.complexity_share),
+                format!("{:.2}", ct.const_fn.average_complexity),
+                format!("{:.2}", ct.runtime.average_complexity)
+            ),
+        );
+        let mut table = term::Table::new(&[Left; 4]).shrink(0);
+        for f in &ct.most_complex {
+            table.row(vec![
+                format!("{}:{}", f.file, f.line),
+                f.function.clone(),
+                tr!("cell-complexity", f.complexity),
+                tr!("cell-loc", f.loc),
+            ]);
+        }
+        table.print();
+    }
+
+    let mut codegen: Vec<&FunctionMetric> = report
+        .files
+        .iter()
+        .filter(|file| file.proc_macro)
+        .flat_map(|file| &file.functions)
+        .filter(|f| f.quote_blocks > 0)
+        .collect();
+    if !codegen.is_empty() {
+        codegen.sort_by_key(|f| std::cmp::Reverse(f.quote_tokens));
+        term::heading("🧬", &tr!("codegen-functions"));
+        let mut table = term::Table::new(&[Left, Right, Left, Left]).shrink(0);
+        for f in codegen.iter().take(5) {
+            table.row(vec![
+                format!("{}::{}", f.file, f.function),
+                tr!("cell-quote-tokens", f.quote_tokens),
+                tr!("cell-in-blocks", f.quote_blocks),
+                tr!("cell-complexity", f.complexity),
+            ]);
+        }
+        table.print();
+    }
+
+    let mut expressions: Vec<&FunctionMetric> = report
+        .files
+        .iter()
+        .flat_map(|file| &file.functions)
+        .filter(|f| f.max_expression_nodes > 0)
+        .collect();
+    if !expressions.is_empty() {
+        expressions.sort_by(|a, b| {
+            b.max_expression_nodes
+                .cmp(&a.max_expression_nodes)
+                .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+        });
+        term::heading("🌳", &tr!("largest-expressions"));
+        let mut table = term::Table::new(&[Left, Left, Right, Right, Left]).shrink(0);
+        for f in expressions.iter().take(5) {
+            table.row(vec![
+                format!("{}:{}", f.file, f.max_expression_line),
+                f.function.clone(),
+                tr!("cell-expression-nodes", f.max_expression_nodes),
+                tr!("cell-statements", f.loc),
+                tr!("cell-complexity", f.complexity),
+            ]);
+        }
+        table.print();
+    }
+
+    if let Some(p) = &report.self_profile {
+        term::heading("⏱️", &tr!("self-profile", format!("{:.1}", p.total_ms)));
+        let mut table = term::Table::new(&[Left, Right, Left]).shrink(0);
+        for phase in &p.phases {
+            table.row(vec![
+                phase.name.clone(),
+                tr!("cell-ms", format!("{:.1}", phase.ms)),
+                String::new(),
+            ]);
+        }
+        for f in p.slowest_files.iter().take(5) {
+            table.row(vec![
+                f.file.clone(),
+                tr!("cell-ms", format!("{:.1}", f.total_ms)),
+                tr!(
+                    "cell-phase-times",
+                    format!("{:.1}", f.timing.read_ms),
+                    format!("{:.1}", f.timing.parse_ms),
+                    format!("{:.1}", f.timing.visit_ms)
+                ),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.diagnostics.is_empty() {
+        term::heading(
+            "🩺",
+            &tr!(
+                "diagnostics",
+                report.diagnostics.len(),
+                report.skipped_binary_files
+            ),
+        );
+        let mut table = term::Table::new(&[Left; 3]).shrink(1);
+        for d in &report.diagnostics {
+            table.row(vec![
+                format!("[{}]", d.kind),
+                d.file.clone(),
+                d.message.clone(),
+            ]);
+        }
+        table.print();
+    }
+
+    if report.rule_statistics.iter().any(|r| r.enabled) {
+        term::heading("📏", &tr!("rule-statistics"));
+        let mut table = term::Table::new(&[Left, Right, Right, Right]);
+        for r in report.rule_statistics.iter().filter(|r| r.enabled) {
+            let tone = if r.findings > 0 {
+                term::Tone::Warn
+            } else {
+                term::Tone::Good
+            };
+            table.row(vec![
+                r.rule.clone(),
+                term::tone(&tr!("cell-n-findings", r.findings), tone),
+                tr!("cell-suppressed", r.suppressed),
+                r.new.map(|n| tr!("cell-new", n)).unwrap_or_default(),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.findings.is_empty() {
+        term::heading("🚩", &tr!("findings", report.findings.len()));
+        let mut table = term::Table::new(&[Left, Left, Left, Left, Left, Right]).shrink(1);
+        for f in &report.findings {
+            table.row(vec![
+                term::tone(&format!("[{}]", f.rule), term::Tone::Bad),
+                format!("{}:{}", f.file, f.line),
+                f.function.clone(),
+                f.message.clone(),
+                term::dim(&format!("({})", f.fingerprint)),
+                f.age_days.map(age::label).unwrap_or_default(),
+            ]);
+        }
+        table.print();
+    }
+
+    if !report.expired_debt.is_empty() {
+        term::heading("⏰", &tr!("expired-debt", report.expired_debt.len()));
+        let mut table = term::Table::new(&[Left; 5]);
+        for e in &report.expired_debt {
+            let target = [&e.rule, &e.file, &e.function, &e.fingerprint]
+                .iter()
+                .filter_map(|v| v.as_deref())
+                .collect::<Vec<_>>()
+                .join(" ");
+            table.row(vec![
+                target,
+                tr!("cell-expired", e.expires),
+                format!("({})", e.source),
+                tr!("cell-count-again", e.findings),
+                e.reason
+                    .as_ref()
+                    .map_or(String::new(), |r| format!("— {}", r)),
+            ]);
+        }
+        table.print();
+    }
+}
+
+fn crate_label(name: &str) -> String {
+    if name.is_empty() {
+        tr!("no-crate")
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    const EXTREMES: [usize; 4] = [0, 1, usize::MAX / 2, usize::MAX];
+
+    #[test]
+    fn log2_or_zero_is_zero_below_one() {
+        for x in [0.0, 0.5, 1.0, -1.0, f64::NAN, f64::NEG_INFINITY] {
+            assert_eq!(log2_or_zero(x), 0.0, "log2_or_zero({})", x);
+        }
+        assert_eq!(log2_or_zero(8.0), 3.0);
+    }
+
+    #[test]
+    fn maintainability_index_is_finite_and_bounded() {
+        for functions in EXTREMES {
+            for operators in EXTREMES {
+                for operands in EXTREMES {
+                    for size in EXTREMES {
+                        let metrics = CodeMetrics {
+                            functions,
+                            loc: size,
+                            cyclomatic_complexity: size,
+                            halstead_unique_operators: operators,
+                            halstead_unique_operands: operands,
+                            ..CodeMetrics::default()
+                        };
+                        let mi = calculate_maintainability_index(&metrics);
+                        assert!(
+                            mi.is_finite() && (0.0..=100.0).contains(&mi),
+                            "MI {} for functions={} operators={} operands={} size={}",
+                            mi,
+                            functions,
+                            operators,
+                            operands,
+                            size
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn maintainability_index_without_halstead_counts() {
+        let metrics = CodeMetrics {
+            functions: 3,
+            loc: 40,
+            cyclomatic_complexity: 6,
+            ..CodeMetrics::default()
+        };
+        let mi = calculate_maintainability_index(&metrics);
+        assert!(mi.is_finite() && mi > 0.0);
+    }
+
+    #[test]
+    fn merge_metrics_saturates() {
+        let huge = CodeMetrics {
+            loc: usize::MAX - 1,
+            cyclomatic_complexity: usize::MAX,
+            functions: usize::MAX / 2 + 1,
+            comments: usize::MAX,
+            commented_out_code: 3,
+            cyclomatic_distribution: vec![usize::MAX, 1],
+            ..CodeMetrics::default()
+        };
+        let mut total = huge.clone();
+        merge_metrics(&mut total, &huge);
+        assert_eq!(total.loc, usize::MAX);
+        assert_eq!(total.cyclomatic_complexity, usize::MAX);
+        assert_eq!(total.functions, usize::MAX);
+        assert_eq!(total.comments, usize::MAX);
+        assert_eq!(total.commented_out_code, 6);
+        assert_eq!(total.cyclomatic_distribution, vec![usize::MAX, 2]);
+    }
+
+    #[test]
+    fn merge_metrics_grows_distribution() {
+        let mut total = CodeMetrics::default();
+        let file = CodeMetrics {
+            cyclomatic_distribution: vec![1, 2, 3],
+            cyclomatic_distribution_bounds: vec![5, 10],
+            ..CodeMetrics::default()
+        };
+        merge_metrics(&mut total, &file);
+        merge_metrics(&mut total, &file);
+        assert_eq!(total.cyclomatic_distribution, vec![2, 4, 6]);
+        assert_eq!(total.cyclomatic_distribution_bounds, vec![5, 10]);
+    }
 }
@@ -0,0 +1,54 @@
+// --- Report metadata: what produced a report, from what, and when ---
+// Enough to tell archived reports apart and to re-run one: the tool
+// version, the analyzed tree's commit, the config file's hash and the
+// thresholds in effect after profiles and flags were applied.
+use crate::config::{Config, Thresholds};
+use crate::{git, logging};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Meta {
+    pub tool: String,
+    pub version: String,
+    pub scanned_at: String, // RFC 3339, UTC
+    pub paths: Vec<String>, // scan roots as given
+    pub commit: Option<String>,
+    pub branch: Option<String>, // None when detached or not a git tree
+    pub dirty: Option<bool>,    // tracked files modified since `commit`
+    pub config_file: Option<String>,
+    pub config_hash: Option<String>, // FNV-1a of the file's bytes, hex
+    pub thresholds: Thresholds,      // effective
+}
+
+impl Meta {
+    // Git details come from the first root; all are `None` outside git.
+    pub fn collect(roots: &[String], thresholds: &Thresholds) -> Meta {
+        let dir = git::work_dir(roots.first().map_or(".", String::as_str));
+        let git = |args: &[&str]| {
+            git::run_git(&dir, args)
+                .ok()
+                .map(|out| out.trim().to_string())
+        };
+        let commit = git(&["rev-parse", "HEAD"]);
+        Meta {
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            scanned_at: logging::rfc3339_now(),
+            paths: roots.to_vec(),
+            branch: git(&["rev-parse", "--abbrev-ref", "HEAD"]).filter(|b| b != "HEAD"),
+            dirty: commit
+                .as_ref()
+                .and_then(|_| git(&["status", "--porcelain", "--untracked-files=no"]))
+                .map(|status| !status.is_empty()),
+            commit,
+            config_file: None,
+            config_hash: None,
+            thresholds: thresholds.clone(),
+        }
+    }
+
+    pub fn describe_config(&mut self, config: &Config) {
+        self.config_file = config.file.clone();
+        self.config_hash = config.hash.clone();
+    }
+}
@@ -0,0 +1,74 @@
+// --- `--use-cargo-metadata`: analyze only files cargo actually compiles ---
+// Target source roots come from `cargo metadata`; from each, `mod`
+// declarations are followed the way modtree.rs resolves them. Files pulled
+// in with `include!` and stray `.rs` files no module reaches are left out.
+use crate::modtree;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// `src_path` of every target of every workspace member.
+pub fn target_roots(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let output = Command::new("cargo")
+        .current_dir(dir)
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .output()
+        .map_err(|e| format!("failed to run cargo: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "cargo metadata failed in {}: {}",
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let metadata: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("cargo metadata: invalid JSON: {}", e))?;
+    Ok(metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|p| p["targets"].as_array().into_iter().flatten())
+        .filter_map(|t| t["src_path"].as_str().map(PathBuf::from))
+        .collect())
+}
+
+// Canonical paths of every file reachable from the targets of the
+// workspaces containing `roots`.
+pub fn reachable(roots: &[String]) -> Result<HashSet<PathBuf>, String> {
+    let mut targets = Vec::new();
+    for root in roots {
+        targets.extend(target_roots(Path::new(root))?);
+    }
+    let mut reached: HashSet<PathBuf> = HashSet::new();
+    let mut queue: Vec<(PathBuf, bool)> = Vec::new();
+    for target in targets {
+        if let Ok(path) = target.canonicalize() {
+            if reached.insert(path.clone()) {
+                queue.push((path, true));
+            }
+        }
+    }
+
+    while let Some((file, is_root)) = queue.pop() {
+        // Unparsable files still count; only their children are unknown.
+        let Some(syntax) = fs::read_to_string(&file)
+            .ok()
+            .and_then(|text| syn::parse_file(&text).ok())
+        else {
+            continue;
+        };
+        let owns_dir = is_root || file.file_name() == Some("mod.rs".as_ref());
+        for decl in modtree::declarations(&syntax) {
+            let child = modtree::candidates(&file, owns_dir, &decl)
+                .into_iter()
+                .find_map(|c| modtree::normalize(&c).canonicalize().ok());
+            if let Some(child) = child.filter(|c| !reached.contains(c)) {
+                reached.insert(child.clone());
+                queue.push((child, false));
+            }
+        }
+    }
+    Ok(reached)
+}
@@ -0,0 +1,156 @@
+// --- Module tree: `mod foo;` declarations resolved to files ---
+// Module paths come from walking the declarations down from each crate
+// root, so `#[path]` attributes and inline `mod a { mod b; }` nesting land
+// files where rustc puts them. Files no declaration reaches are orphans:
+// dead code the compiler never sees.
+use crate::{crates, FileMetrics};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use syn::visit::{self, Visit};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ModDecl {
+    pub inline: Vec<String>, // enclosing inline modules, outermost first
+    pub name: String,
+    pub path: Option<String>, // `#[path = "..."]`
+}
+
+#[derive(Default)]
+struct DeclVisitor {
+    inline: Vec<String>,
+    decls: Vec<ModDecl>,
+}
+
+impl<'ast> Visit<'ast> for DeclVisitor {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        let name = node.ident.to_string();
+        if node.content.is_none() {
+            self.decls.push(ModDecl {
+                inline: self.inline.clone(),
+                name,
+                path: path_attr(&node.attrs),
+            });
+            return;
+        }
+        self.inline.push(name);
+        visit::visit_item_mod(self, node);
+        self.inline.pop();
+    }
+
+    // Modules can't be declared inside function bodies in a way that maps
+    // to files we resolve, so only item-level modules are visited.
+    fn visit_item_fn(&mut self, _: &'ast syn::ItemFn) {}
+}
+
+fn path_attr(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().find_map(|a| match &a.meta {
+        syn::Meta::NameValue(nv) if nv.path.is_ident("path") => match &nv.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s),
+                ..
+            }) => Some(s.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+pub fn declarations(syntax: &syn::File) -> Vec<ModDecl> {
+    let mut visitor = DeclVisitor::default();
+    visitor.visit_file(syntax);
+    visitor.decls
+}
+
+// Lexical only: `a/./b/../c.rs` -> `a/c.rs`, matching how walked paths look.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir if out.file_name().is_some() => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+// Candidate files for a declaration in `file`. Crate roots and `mod.rs`
+// own their directory; `foo.rs` owns `foo/`.
+pub fn candidates(file: &Path, owns_dir: bool, decl: &ModDecl) -> Vec<PathBuf> {
+    let parent = file.parent().unwrap_or(Path::new(""));
+    let mut dir = if owns_dir {
+        parent.to_path_buf()
+    } else {
+        parent.join(file.file_stem().unwrap_or_default())
+    };
+    for m in &decl.inline {
+        dir.push(m);
+    }
+    match &decl.path {
+        Some(p) if decl.inline.is_empty() => vec![parent.join(p)],
+        Some(p) => vec![dir.join(p)],
+        None => vec![
+            dir.join(format!("{}.rs", decl.name)),
+            dir.join(&decl.name).join("mod.rs"),
+        ],
+    }
+}
+
+// Rewrites `module` for every file reachable from a crate root and returns
+// the crate files nothing reaches. Files outside any crate keep their
+// path-derived module.
+pub fn resolve(files: &mut [FileMetrics]) -> Vec<String> {
+    let index: HashMap<PathBuf, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (normalize(Path::new(&f.file)), i))
+        .collect();
+    let mut resolver = crates::CrateResolver::default();
+    let mut reached = vec![false; files.len()];
+    let mut queue: Vec<usize> = Vec::new();
+    for (i, f) in files.iter().enumerate() {
+        if resolver
+            .locate(Path::new(&f.file), Path::new(""))
+            .target_root
+        {
+            reached[i] = true;
+            queue.push(i);
+        }
+    }
+    let roots = queue.clone();
+
+    while let Some(i) = queue.pop() {
+        let file = PathBuf::from(&files[i].file);
+        let owns_dir = roots.contains(&i) || file.file_name() == Some("mod.rs".as_ref());
+        for decl in files[i].mod_decls.clone() {
+            let Some(&child) = candidates(&file, owns_dir, &decl)
+                .iter()
+                .find_map(|c| index.get(&normalize(c)))
+            else {
+                continue;
+            };
+            if reached[child] {
+                continue;
+            }
+            let mut module = vec![files[i].module.clone()];
+            module.extend(decl.inline.iter().cloned());
+            module.push(decl.name.clone());
+            module.retain(|m| !m.is_empty());
+            files[child].module = module.join("::");
+            reached[child] = true;
+            queue.push(child);
+        }
+    }
+
+    let mut orphans: Vec<String> = files
+        .iter()
+        .zip(&reached)
+        .filter(|(f, &r)| !r && !f.crate_name.is_empty())
+        .map(|(f, _)| f.file.clone())
+        .collect();
+    orphans.sort();
+    orphans
+}
@@ -0,0 +1,137 @@
+// --- Mutation testing results (cargo-mutants) joined with per-function complexity ---
+// A surviving mutant is a change no test noticed; where that happens in
+// complex code is where tests are worth writing first.
+use crate::{artifact, FileMetrics, FunctionMetric};
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+const RANKED: usize = 20;
+
+pub struct Mutant {
+    pub file: PathBuf, // as the tool named it
+    pub line: usize,
+    pub caught: bool, // killed by a failing test or a timeout
+}
+
+pub fn load(path: &str) -> Result<Vec<Mutant>, String> {
+    let text = artifact::read_to_string(path)?;
+    let json: Value =
+        serde_json::from_str(&text).map_err(|e| format!("invalid mutants {}: {}", path, e))?;
+    if let Some(outcomes) = json["outcomes"].as_array() {
+        Ok(outcomes.iter().filter_map(cargo_mutants).collect())
+    } else if let Some(entries) = json.as_array() {
+        Ok(entries.iter().filter_map(generic).collect())
+    } else {
+        Err(format!(
+            "invalid mutants {}: expected cargo-mutants outcomes.json or a [{{file, line, status}}] array",
+            path
+        ))
+    }
+}
+
+// `mutants.out/outcomes.json`. The baseline and unviable mutants (which did
+// not build) say nothing about the tests.
+fn cargo_mutants(outcome: &Value) -> Option<Mutant> {
+    let mutant = &outcome["scenario"]["Mutant"];
+    let caught = match outcome["summary"].as_str()? {
+        "CaughtMutant" | "Timeout" => true,
+        "MissedMutant" => false,
+        _ => return None,
+    };
+    // Newer releases give a span, older ones a bare line.
+    let line = mutant["span"]["start"]["line"]
+        .as_u64()
+        .or_else(|| mutant["line"].as_u64())?;
+    Some(Mutant {
+        file: PathBuf::from(mutant["file"].as_str()?),
+        line: line as usize,
+        caught,
+    })
+}
+
+// `{"file", "line", "status"}` records, e.g. converted from mutagen's report.
+fn generic(entry: &Value) -> Option<Mutant> {
+    let caught = match entry["status"].as_str()? {
+        "killed" | "caught" | "timeout" => true,
+        "survived" | "missed" => false,
+        _ => return None,
+    };
+    Some(Mutant {
+        file: PathBuf::from(entry["file"].as_str()?),
+        line: entry["line"].as_u64()? as usize,
+        caught,
+    })
+}
+
+// Paths may be absolute or relative to the crate, so a scanned file matches
+// mutants whose path it ends with, as for coverage.
+fn in_function<'a>(
+    mutants: &'a [Mutant],
+    f: &'a FunctionMetric,
+) -> impl Iterator<Item = &'a Mutant> + 'a {
+    let path = fs::canonicalize(&f.file).unwrap_or_else(|_| PathBuf::from(&f.file));
+    mutants.iter().filter(move |m| {
+        !m.file.as_os_str().is_empty()
+            && path.ends_with(&m.file)
+            && (f.line..=f.end_line).contains(&m.line)
+    })
+}
+
+// Percentage of the function's viable mutants that tests caught; `None`
+// when no mutant landed in it.
+pub fn attach<'a>(functions: impl Iterator<Item = &'a mut FunctionMetric>, mutants: &[Mutant]) {
+    for f in functions {
+        let (caught, total) =
+            in_function(mutants, f).fold((0, 0), |(c, t), m| (c + usize::from(m.caught), t + 1));
+        if total > 0 {
+            f.mutation_score = Some(caught as f64 * 100.0 / total as f64);
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct WeaklyTested {
+    pub file: String,
+    pub function: String,
+    pub line: usize,
+    pub complexity: usize,
+    pub mutants: usize,
+    pub survived: usize,
+    pub mutation_score: f64,
+    pub priority: f64, // complexity × share of mutants that survived
+}
+
+// Functions with surviving mutants, most complex-and-weakly-tested first.
+pub fn rank(files: &[FileMetrics], mutants: &[Mutant]) -> Vec<WeaklyTested> {
+    let mut rows: Vec<WeaklyTested> = files
+        .iter()
+        .flat_map(|file| &file.functions)
+        .filter_map(|f| {
+            let (survived, total) = in_function(mutants, f)
+                .fold((0, 0), |(s, t), m| (s + usize::from(!m.caught), t + 1));
+            if survived == 0 {
+                return None;
+            }
+            let missed = survived as f64 / total as f64;
+            Some(WeaklyTested {
+                file: f.file.clone(),
+                function: f.function.clone(),
+                line: f.line,
+                complexity: f.complexity,
+                mutants: total,
+                survived,
+                mutation_score: (1.0 - missed) * 100.0,
+                priority: f.complexity as f64 * missed,
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.priority
+            .total_cmp(&a.priority)
+            .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+    });
+    rows.truncate(RANKED);
+    rows
+}
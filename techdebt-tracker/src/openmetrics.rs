@@ -0,0 +1,168 @@
+// --- OpenMetrics exposition (Prometheus text format) ---
+use crate::Report;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Default)]
+struct ModuleSeries {
+    loc: usize,
+    functions: usize,
+    complexity: usize,
+    max_function_complexity: usize,
+    findings: usize,
+    const_functions: usize,
+    const_complexity: usize,
+}
+
+type SeriesValue = fn(&ModuleSeries) -> usize;
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Series are labelled per crate/module/target kind so dashboards can alert on
+// a single crate; project totals are a `sum()` away.
+pub fn render(report: &Report) -> String {
+    let mut modules: BTreeMap<(&str, &str, &str), ModuleSeries> = BTreeMap::new();
+    for file in &report.files {
+        let key = (
+            file.crate_name.as_str(),
+            file.module.as_str(),
+            file.target_kind.as_str(),
+        );
+        let series = modules.entry(key).or_default();
+        series.loc = series.loc.saturating_add(file.loc);
+        series.functions = series.functions.saturating_add(file.functions.len());
+        series.complexity = series.complexity.saturating_add(file.total_complexity);
+        series.max_function_complexity = file
+            .functions
+            .iter()
+            .map(|f| f.complexity)
+            .fold(series.max_function_complexity, usize::max);
+        for f in file.functions.iter().filter(|f| f.is_const) {
+            series.const_functions += 1;
+            series.const_complexity = series.const_complexity.saturating_add(f.complexity);
+        }
+        series.findings += report
+            .findings
+            .iter()
+            .filter(|f| f.file == file.file)
+            .count();
+    }
+
+    let mut out = String::new();
+    let meta = &report.meta;
+    let _ = writeln!(out, "# TYPE codehealth_build info");
+    let _ = writeln!(
+        out,
+        "# HELP codehealth_build Analyzer version and analyzed commit."
+    );
+    let _ = writeln!(
+        out,
+        "codehealth_build_info{{version=\"{}\",commit=\"{}\",branch=\"{}\",config_hash=\"{}\"}} 1",
+        escape(&meta.version),
+        escape(meta.commit.as_deref().unwrap_or("")),
+        escape(meta.branch.as_deref().unwrap_or("")),
+        escape(meta.config_hash.as_deref().unwrap_or(""))
+    );
+    let families: [(&str, &str, SeriesValue); 7] = [
+        ("codehealth_loc", "Lines of code.", |s| s.loc),
+        ("codehealth_functions", "Number of functions.", |s| {
+            s.functions
+        }),
+        (
+            "codehealth_cyclomatic_complexity",
+            "Sum of cyclomatic complexity.",
+            |s| s.complexity,
+        ),
+        (
+            "codehealth_max_function_complexity",
+            "Highest cyclomatic complexity of a single function.",
+            |s| s.max_function_complexity,
+        ),
+        ("codehealth_findings", "Rule findings.", |s| s.findings),
+        (
+            "codehealth_const_fn_functions",
+            "Number of `const fn` functions (compile-time evaluated).",
+            |s| s.const_functions,
+        ),
+        (
+            "codehealth_const_fn_complexity",
+            "Sum of cyclomatic complexity of `const fn` functions.",
+            |s| s.const_complexity,
+        ),
+    ];
+
+    for (name, help, value) in families {
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        for ((krate, module, kind), series) in &modules {
+            let _ = writeln!(
+                out,
+                "{}{{crate=\"{}\",module=\"{}\",kind=\"{}\"}} {}",
+                name,
+                escape(krate),
+                escape(module),
+                escape(kind),
+                value(series)
+            );
+        }
+    }
+
+    let _ = writeln!(out, "# TYPE codehealth_maintainability_index gauge");
+    let _ = writeln!(
+        out,
+        "# HELP codehealth_maintainability_index Maintainability index (0-100)."
+    );
+    let _ = writeln!(
+        out,
+        "codehealth_maintainability_index {:.2}",
+        report.maintainability_index
+    );
+
+    // Letters ride along as a label so dashboards can show them as-is.
+    let grades = &report.grades;
+    let _ = writeln!(out, "# TYPE codehealth_grade_score gauge");
+    let _ = writeln!(
+        out,
+        "# HELP codehealth_grade_score Graded maintainability score (0-100) per crate, letter in `grade`."
+    );
+    for c in &grades.crates {
+        let _ = writeln!(
+            out,
+            "codehealth_grade_score{{crate=\"{}\",grade=\"{}\"}} {:.2}",
+            escape(&c.crate_name),
+            c.grade,
+            c.score
+        );
+    }
+    let _ = writeln!(out, "# TYPE codehealth_project_grade_score gauge");
+    let _ = writeln!(
+        out,
+        "# HELP codehealth_project_grade_score Graded maintainability score (0-100) of the project, letter in `grade`."
+    );
+    if !grades.grade.is_empty() {
+        let _ = writeln!(
+            out,
+            "codehealth_project_grade_score{{grade=\"{}\"}} {:.2}",
+            grades.grade, grades.score
+        );
+    }
+    let _ = writeln!(out, "# TYPE codehealth_functions_by_grade gauge");
+    let _ = writeln!(
+        out,
+        "# HELP codehealth_functions_by_grade Number of functions per letter grade."
+    );
+    for (grade, count) in &grades.functions {
+        let _ = writeln!(
+            out,
+            "codehealth_functions_by_grade{{grade=\"{}\"}} {}",
+            grade, count
+        );
+    }
+    out.push_str("# EOF\n");
+    out
+}
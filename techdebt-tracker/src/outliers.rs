@@ -0,0 +1,107 @@
+// --- `--auto-threshold`: limits derived from this repo's own distribution ---
+// Fixed numbers from the literature fit some codebases badly; instead a
+// function is flagged when its complexity or LOC is a statistical outlier
+// among the functions in scope.
+use crate::{FileMetrics, Finding, FunctionMetric};
+use serde::Serialize;
+
+pub const METHODS: [&str; 2] = ["percentile", "mad"];
+
+const PERCENTILE: f64 = 97.5;
+const MAD_FACTOR: f64 = 2.0; // limit = median + 2 MAD
+
+// Below this many functions a percentile or MAD says little.
+const MIN_FUNCTIONS: usize = 20;
+
+#[derive(Serialize)]
+pub struct AutoLimit {
+    pub median: f64,
+    pub spread: f64, // the MAD, or the percentile value itself
+    pub limit: f64,  // values above this are outliers
+    pub flagged: usize,
+}
+
+#[derive(Serialize)]
+pub struct AutoThresholds {
+    pub method: String,
+    pub functions: usize,
+    pub complexity: AutoLimit,
+    pub loc: AutoLimit,
+}
+
+fn median(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    }
+}
+
+fn limit(mut values: Vec<f64>, method: &str) -> AutoLimit {
+    values.sort_by(f64::total_cmp);
+    let med = median(&values);
+    let (spread, limit) = if method == "mad" {
+        let mut deviations: Vec<f64> = values.iter().map(|v| (v - med).abs()).collect();
+        deviations.sort_by(f64::total_cmp);
+        // Mostly-trivial code has a MAD of 0, which would flag everything
+        // above the median; one unit is the least spread counted.
+        let mad = median(&deviations);
+        (mad, med + MAD_FACTOR * mad.max(1.0))
+    } else {
+        let rank = (PERCENTILE / 100.0 * values.len() as f64).ceil() as usize;
+        let p = values[rank.clamp(1, values.len()) - 1];
+        (p, p)
+    };
+    AutoLimit {
+        median: med,
+        spread,
+        limit,
+        flagged: values.iter().filter(|&&v| v > limit).count(),
+    }
+}
+
+// None when there are too few functions to tell what is unusual.
+pub fn derive(files: &[FileMetrics], method: &str) -> Option<AutoThresholds> {
+    let functions: Vec<&FunctionMetric> = files.iter().flat_map(|f| &f.functions).collect();
+    if functions.len() < MIN_FUNCTIONS {
+        return None;
+    }
+    Some(AutoThresholds {
+        method: method.to_string(),
+        functions: functions.len(),
+        complexity: limit(
+            functions.iter().map(|f| f.complexity as f64).collect(),
+            method,
+        ),
+        loc: limit(functions.iter().map(|f| f.loc as f64).collect(), method),
+    })
+}
+
+pub fn findings(files: &[FileMetrics], auto: &AutoThresholds) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for f in files.iter().flat_map(|file| &file.functions) {
+        let over: Vec<String> = [
+            ("complexity", f.complexity, &auto.complexity),
+            ("LOC", f.loc, &auto.loc),
+        ]
+        .into_iter()
+        .filter(|(_, value, l)| *value as f64 > l.limit)
+        .map(|(what, value, l)| format!("{} {} > {:.1}", what, value, l.limit))
+        .collect();
+        if !over.is_empty() {
+            findings.push(Finding::new(
+                "statistical-outlier",
+                &f.file,
+                &f.function,
+                f.line,
+                format!(
+                    "outlier for this codebase ({}): {}",
+                    auto.method,
+                    over.join(", ")
+                ),
+            ));
+        }
+    }
+    findings
+}
@@ -0,0 +1,161 @@
+// --- `--orphaned-hotspots`: complex files whose owners have gone quiet ---
+// Owners come from `git blame` of the current lines; an author is inactive
+// when their last commit anywhere in the repository is older than the
+// window. A hotspot (a function over the outlier complexity) is orphaned
+// once inactive authors own most of its lines.
+use crate::{git, logging, Report};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAYS_PER_MONTH: f64 = 30.44;
+const ORPHANED_SHARE: f64 = 50.0; // % of lines owned by inactive authors
+
+#[derive(Serialize)]
+pub struct Owner {
+    pub author: String, // email, as git records it
+    pub lines: usize,
+    pub last_commit: String, // YYYY-MM-DD, anywhere in the repository
+}
+
+#[derive(Serialize)]
+pub struct OrphanedHotspot {
+    pub file: String,
+    pub total_complexity: usize,
+    pub max_complexity: usize,
+    pub lines: usize,
+    pub inactive_share: f64,         // % of blamed lines by inactive authors
+    pub inactive_owners: Vec<Owner>, // most lines first
+    pub recent_commits: usize,       // churn inside the window, by anyone
+    pub risk: f64,                   // total complexity × inactive share
+}
+
+#[derive(Serialize)]
+pub struct OrphanedHotspots {
+    pub months: u64,
+    pub inactive_since: String,         // YYYY-MM-DD
+    pub hotspots: Vec<OrphanedHotspot>, // riskiest first
+}
+
+fn cutoff(months: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let window = (months as f64 * DAYS_PER_MONTH * 86_400.0) as u64;
+    logging::rfc3339(now.saturating_sub(window))[..10].to_string()
+}
+
+// Newest commit date per author email; `git log` lists newest first.
+fn last_commits(dir: &Path) -> Result<HashMap<String, String>, String> {
+    let log = git::run_git(dir, &["log", "--format=%ae%x09%ad", "--date=short"])?;
+    let mut last = HashMap::new();
+    for line in log.lines() {
+        if let Some((author, date)) = line.split_once('\t') {
+            last.entry(author.to_string())
+                .or_insert_with(|| date.to_string());
+        }
+    }
+    Ok(last)
+}
+
+// Commits per file (relative to `dir`) since `date`.
+fn churn(dir: &Path, date: &str) -> Result<HashMap<String, usize>, String> {
+    let since = format!("--since={}", date);
+    let log = git::run_git(
+        dir,
+        &[
+            "log",
+            &since,
+            "--name-only",
+            "--relative",
+            "--format=",
+            "--",
+            ".",
+        ],
+    )?;
+    let mut counts = HashMap::new();
+    for file in log.lines().filter(|l| !l.is_empty()) {
+        *counts.entry(file.to_string()).or_default() += 1;
+    }
+    Ok(counts)
+}
+
+// Blamed lines per author email.
+fn blame(dir: &Path, file: &str) -> Result<BTreeMap<String, usize>, String> {
+    let porcelain = git::run_git(dir, &["blame", "-w", "--line-porcelain", "--", file])?;
+    let mut lines = BTreeMap::new();
+    for mail in porcelain
+        .lines()
+        .filter_map(|l| l.strip_prefix("author-mail "))
+    {
+        let author = mail.trim_start_matches('<').trim_end_matches('>');
+        *lines.entry(author.to_string()).or_default() += 1;
+    }
+    Ok(lines)
+}
+
+// Report paths are relative to `path_root`, which git runs in. Files git
+// does not track (or cannot blame) are skipped.
+pub fn find(
+    report: &Report,
+    path_root: &str,
+    months: u64,
+    hotspot_complexity: usize,
+) -> Result<OrphanedHotspots, String> {
+    let dir = git::work_dir(path_root);
+    let inactive_since = cutoff(months);
+    let last = last_commits(&dir)?;
+    let churn = churn(&dir, &inactive_since)?;
+    let mut hotspots = Vec::new();
+    for file in &report.files {
+        let max_complexity = file
+            .functions
+            .iter()
+            .map(|f| f.complexity)
+            .max()
+            .unwrap_or(0);
+        if max_complexity <= hotspot_complexity {
+            continue;
+        }
+        let Ok(owners) = blame(&dir, &file.file) else {
+            continue;
+        };
+        let lines: usize = owners.values().sum();
+        let mut inactive_owners: Vec<Owner> = owners
+            .into_iter()
+            .filter_map(|(author, lines)| {
+                // Uncommitted lines have no commit date and are not stale.
+                let last_commit = last.get(&author)?.clone();
+                (last_commit < inactive_since).then_some(Owner {
+                    author,
+                    lines,
+                    last_commit,
+                })
+            })
+            .collect();
+        let inactive: usize = inactive_owners.iter().map(|o| o.lines).sum();
+        let inactive_share = inactive as f64 * 100.0 / lines.max(1) as f64;
+        if inactive_share <= ORPHANED_SHARE {
+            continue;
+        }
+        inactive_owners.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.author.cmp(&b.author)));
+        hotspots.push(OrphanedHotspot {
+            file: file.file.clone(),
+            total_complexity: file.total_complexity,
+            max_complexity,
+            lines,
+            inactive_share,
+            inactive_owners,
+            recent_commits: churn.get(&file.file).copied().unwrap_or(0),
+            risk: file.total_complexity as f64 * inactive_share / 100.0,
+        });
+    }
+    hotspots.sort_by(|a, b| b.risk.total_cmp(&a.risk).then_with(|| a.file.cmp(&b.file)));
+    Ok(OrphanedHotspots {
+        months,
+        inactive_since,
+        hotspots,
+    })
+}
@@ -0,0 +1,178 @@
+// --- Path comparison that holds on Windows too ---
+// Windows hands out `\\?\C:\...` (long-path) forms from `canonicalize` and
+// some tools, treats `C:\Repo` and `c:\repo` as the same file and accepts
+// either separator, so comparisons here fold all of those; report paths
+// always use `/`.
+use std::path::{Component, Path, PathBuf, Prefix};
+
+// `\\?\C:\x` -> `C:\x`, `\\?\UNC\server\share\x` -> `\\server\share\x`,
+// with the drive letter upper-cased. Other paths are returned unchanged.
+pub fn strip_verbatim(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::VerbatimDisk(d) | Prefix::Disk(d) => {
+                    out.push(format!("{}:", d.to_ascii_uppercase() as char))
+                }
+                Prefix::VerbatimUNC(server, share) => out.push(format!(
+                    r"\\{}\{}",
+                    server.to_string_lossy(),
+                    share.to_string_lossy()
+                )),
+                _ => out.push(component),
+            },
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+// Case only matters where the file system is case-sensitive.
+pub fn fold_case(text: &str) -> String {
+    if cfg!(windows) {
+        text.to_lowercase()
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn slash(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+// `path` below `base`, compared component-wise with case folded as above.
+pub fn strip_prefix(path: &Path, base: &Path) -> Option<PathBuf> {
+    let mut rest = path.components();
+    for want in base.components() {
+        let have = rest.next()?;
+        if fold_case(&have.as_os_str().to_string_lossy())
+            != fold_case(&want.as_os_str().to_string_lossy())
+        {
+            return None;
+        }
+    }
+    Some(rest.as_path().to_path_buf())
+}
+
+// Identity of a file for de-duplication: two spellings of one path give
+// the same key. `path` should already be absolute.
+pub fn key(path: &Path) -> String {
+    fold_case(&slash(&strip_verbatim(path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components, is_excluded};
+
+    #[test]
+    fn key_ignores_separator_style() {
+        assert_eq!(
+            key(Path::new(r"/repo/src\foo.rs")),
+            key(Path::new("/repo/src/foo.rs"))
+        );
+        assert_eq!(slash(Path::new(r"src\api\mod.rs")), "src/api/mod.rs");
+    }
+
+    #[test]
+    fn exclude_patterns_match_backslash_paths() {
+        let root = Path::new("/repo");
+        let excludes = [r"src\generated".to_string()];
+        assert!(is_excluded(
+            Path::new("/repo/src/generated/a.rs"),
+            root,
+            &excludes
+        ));
+        assert!(is_excluded(
+            Path::new(r"/repo/src\generated\a.rs"),
+            root,
+            &excludes
+        ));
+        assert!(!is_excluded(
+            Path::new("/repo/src/gen/a.rs"),
+            root,
+            &excludes
+        ));
+        assert!(components::glob_matches(r"src\**\*.rs", r"src\api\mod.rs"));
+        assert!(components::glob_matches("src/**/*.rs", r"src\api\mod.rs"));
+    }
+
+    // Elsewhere a backslash is an ordinary file name character and case
+    // tells files apart.
+    #[cfg(not(windows))]
+    #[test]
+    fn unix_paths_stay_case_sensitive() {
+        assert_eq!(fold_case("Src/Foo.rs"), "Src/Foo.rs");
+        assert_ne!(
+            key(Path::new(r"/repo/src\Foo.rs")),
+            key(Path::new("/repo/src/foo.rs"))
+        );
+        assert_eq!(
+            strip_prefix(Path::new("/repo/src/a.rs"), Path::new("/repo")),
+            Some(PathBuf::from("src/a.rs"))
+        );
+        assert_eq!(
+            strip_prefix(Path::new("/Repo/src/a.rs"), Path::new("/repo")),
+            None
+        );
+        let verbatim = Path::new(r"\\?\C:\repo\a.rs");
+        assert_eq!(strip_verbatim(verbatim), verbatim);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn fold_case_lowercases_on_windows() {
+        assert_eq!(fold_case(r"C:\Repo\Src\Foo.rs"), r"c:\repo\src\foo.rs");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn strip_verbatim_disk_and_unc() {
+        assert_eq!(
+            strip_verbatim(Path::new(r"\\?\c:\Repo\src\a.rs")),
+            PathBuf::from(r"C:\Repo\src\a.rs")
+        );
+        assert_eq!(
+            strip_verbatim(Path::new(r"\\?\UNC\server\share\src\a.rs")),
+            PathBuf::from(r"\\server\share\src\a.rs")
+        );
+        assert_eq!(
+            strip_verbatim(Path::new(r"C:\Repo\a.rs")),
+            PathBuf::from(r"C:\Repo\a.rs")
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn strip_prefix_folds_case_and_separators() {
+        assert_eq!(
+            strip_prefix(Path::new(r"c:\repo/Src\a.rs"), Path::new("C:/Repo")),
+            Some(PathBuf::from(r"Src\a.rs"))
+        );
+        assert_eq!(
+            strip_prefix(
+                &strip_verbatim(Path::new(r"\\?\C:\REPO\src\a.rs")),
+                Path::new(r"c:\repo")
+            ),
+            Some(PathBuf::from(r"src\a.rs"))
+        );
+        assert_eq!(
+            strip_prefix(Path::new(r"C:\other\a.rs"), Path::new(r"C:\repo")),
+            None
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn key_dedups_spellings_on_windows() {
+        assert_eq!(
+            key(Path::new(r"C:\repo\src\Foo.rs")),
+            key(Path::new("c:/repo/src/foo.rs"))
+        );
+        assert_eq!(
+            key(Path::new(r"\\?\C:\repo\src\foo.rs")),
+            key(Path::new(r"c:\Repo\src/Foo.rs"))
+        );
+    }
+}
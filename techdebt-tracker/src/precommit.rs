@@ -0,0 +1,78 @@
+// --- `precommit`: check staged changes only, with terse blocking output ---
+use crate::config::{ComplexityModel, Suppression, Thresholds};
+use crate::{
+    analyze_source, collect_findings, git, rules, FileMetrics, EXIT_GATE_FAILURE, EXIT_OK,
+};
+
+// Analyzes the staged content of changed Rust files and checks the
+// functions the staged diff touches. Suppressions apply, except those keyed
+// by fingerprint, which needs the whole project. Returns the exit code:
+// EXIT_OK when clean, EXIT_GATE_FAILURE on any problem.
+pub fn run(
+    scan_path: &str,
+    thresholds: &Thresholds,
+    model: ComplexityModel,
+    suppressions: &[Suppression],
+) -> Result<i32, String> {
+    let (root, staged) = git::staged_changes(scan_path)?;
+    let mut files: Vec<&String> = staged.keys().filter(|f| f.ends_with(".rs")).collect();
+    files.sort();
+
+    let mut changed: Vec<FileMetrics> = Vec::new();
+    let mut unparsable: Vec<String> = Vec::new();
+    for file in files {
+        let content = git::staged_content(&root, file)?;
+        let (_, mut detail) = analyze_source(&content, file, model);
+        if let Some(err) = &detail.parse_error {
+            unparsable.push(format!("{}: does not parse: {}", file, err));
+        }
+        let ranges = &staged[file];
+        detail
+            .functions
+            .retain(|f| ranges.iter().any(|&(a, b)| a <= f.end_line && b >= f.line));
+        changed.push(detail);
+    }
+
+    let mut problems = unparsable;
+    let functions = changed.iter().flat_map(|f| &f.functions);
+    if let Some(limit) = thresholds.max_complexity {
+        for f in functions.clone().filter(|f| f.complexity > limit as usize) {
+            problems.push(format!(
+                "{}:{} {}: complexity {} > {}",
+                f.file, f.line, f.function, f.complexity, limit
+            ));
+        }
+    }
+    let allowed = |file: &str, function: &str, rule: &str| {
+        functions.clone().any(|f| {
+            f.file == file
+                && f.function == function
+                && f.allows.iter().any(|a| a == rule || a == "all")
+        })
+    };
+    for finding in collect_findings(&changed, thresholds) {
+        if !allowed(&finding.file, &finding.function, &finding.rule)
+            && !suppressions.iter().any(|s| rules::matches(s, &finding))
+        {
+            problems.push(format!(
+                "{}:{} {}: [{}] {}",
+                finding.file, finding.line, finding.function, finding.rule, finding.message
+            ));
+        }
+    }
+
+    let checked = changed.iter().map(|f| f.functions.len()).sum::<usize>();
+    if problems.is_empty() {
+        println!("codehealth: {} changed functions OK", checked);
+        return Ok(EXIT_OK);
+    }
+    println!(
+        "codehealth: {} problem(s) in {} changed functions:",
+        problems.len(),
+        checked
+    );
+    for p in &problems {
+        println!("  {}", p);
+    }
+    Ok(EXIT_GATE_FAILURE)
+}
@@ -0,0 +1,100 @@
+// --- Self-profiling: where the analyzer spends its time (--profile-self) ---
+use crate::FileMetrics;
+use serde::Serialize;
+use std::time::Instant;
+
+fn millis(since: Instant) -> f64 {
+    since.elapsed().as_secs_f64() * 1000.0
+}
+
+// Measured on every run (it is cheap); reported only with --profile-self.
+// Cache hits keep all zeros, since nothing was read or parsed.
+#[derive(Serialize, Clone, Default)]
+pub struct FileTiming {
+    pub read_ms: f64,
+    pub parse_ms: f64,
+    pub visit_ms: f64,
+}
+
+impl FileTiming {
+    pub fn total_ms(&self) -> f64 {
+        self.read_ms + self.parse_ms + self.visit_ms
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct Phase {
+    pub name: String,
+    pub ms: f64,
+}
+
+// Splits a run into consecutive phases.
+pub struct Stopwatch {
+    started: Instant,
+    lap: Instant,
+    pub phases: Vec<Phase>,
+}
+
+impl Stopwatch {
+    pub fn start() -> Stopwatch {
+        let now = Instant::now();
+        Stopwatch {
+            started: now,
+            lap: now,
+            phases: Vec::new(),
+        }
+    }
+
+    // Ends the current phase under `name`.
+    pub fn lap(&mut self, name: &str) {
+        self.phases.push(Phase {
+            name: name.to_string(),
+            ms: millis(self.lap),
+        });
+        self.lap = Instant::now();
+    }
+}
+
+// Times a closure, returning its result and the elapsed milliseconds.
+pub fn timed<T>(f: impl FnOnce() -> T) -> (T, f64) {
+    let start = Instant::now();
+    let value = f();
+    (value, millis(start))
+}
+
+#[derive(Serialize, Clone)]
+pub struct FileProfile {
+    pub file: String,
+    #[serde(flatten)]
+    pub timing: FileTiming,
+    pub total_ms: f64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SelfProfile {
+    pub total_ms: f64,
+    pub phases: Vec<Phase>,
+    pub slowest_files: Vec<FileProfile>,
+}
+
+pub fn summarize(stopwatch: Stopwatch, files: &[FileMetrics], limit: usize) -> SelfProfile {
+    let mut slowest_files: Vec<FileProfile> = files
+        .iter()
+        .map(|f| FileProfile {
+            file: f.file.clone(),
+            total_ms: f.timing.total_ms(),
+            timing: f.timing.clone(),
+        })
+        .collect();
+    slowest_files.sort_by(|a, b| {
+        b.total_ms
+            .total_cmp(&a.total_ms)
+            .then_with(|| a.file.cmp(&b.file))
+    });
+    slowest_files.truncate(limit);
+    SelfProfile {
+        total_ms: millis(stopwatch.started),
+        phases: stopwatch.phases,
+        slowest_files,
+    }
+}
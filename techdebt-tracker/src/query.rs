@@ -0,0 +1,448 @@
+// --- `query`: a small selector language over saved report JSON ---
+// `functions | where file ~ "src/api/**" and cognitive > 20 | sort cognitive
+// | limit 10 | select file, function, cognitive`. A query starts from
+// `functions` or `files`, then applies stages left to right. `~` is a path
+// glob: `*` and `?` stay within a path segment, `**` spans segments.
+use crate::term::{Align, Table};
+use crate::{artifact, derived, FileMetrics, FunctionMetric};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+
+#[derive(Deserialize)]
+struct InputReport {
+    files: Vec<FileMetrics>,
+}
+
+const FILE_FIELDS: [&str; 11] = [
+    "file",
+    "module",
+    "crate",
+    "target_kind",
+    "loc",
+    "nloc",
+    "items",
+    "complexity",
+    "functions",
+    "lints",
+    "vendored",
+];
+const FUNCTION_IDENTITY: [&str; 4] = ["file", "function", "line", "end_line"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Source {
+    Functions,
+    Files,
+}
+
+#[derive(Clone)]
+enum Cond {
+    Or(Box<Cond>, Box<Cond>),
+    And(Box<Cond>, Box<Cond>),
+    Not(Box<Cond>),
+    Compare(String, String, Value), // field, operator, literal
+}
+
+#[derive(Clone)]
+enum Stage {
+    Where(Cond),
+    Sort(String, bool), // field, descending
+    Limit(usize),
+    Select(Vec<String>),
+}
+
+#[derive(Clone)]
+pub struct Query {
+    source: Source,
+    stages: Vec<Stage>,
+    referenced: Vec<String>, // fields named by where/sort, for the default columns
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    Text(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 11] = ["==", "!=", "<=", ">=", "<", ">", "~", "|", "(", ")", ","];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if let Some(sym) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(sym));
+            rest = &rest[sym.len()..];
+        } else if c == '"' {
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    None => return Err("unterminated string".to_string()),
+                    Some((i, '"')) => break i + 2,
+                    Some((_, '\\')) => value.extend(chars.next().map(|(_, c)| c)),
+                    Some((_, c)) => value.push(c),
+                }
+            };
+            tokens.push(Token::Text(value));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || "_.:-".contains(c)))
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+            tokens.push(match word.parse::<f64>() {
+                Ok(n) => Token::Number(n),
+                Err(_) => Token::Word(word.to_string()),
+            });
+            rest = &rest[len..];
+        } else {
+            return Err(format!("unexpected '{}'", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    source: Source,
+    referenced: Vec<String>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Word(w)) if w == word);
+        self.pos += usize::from(found);
+        found
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        self.pos += usize::from(found);
+        found
+    }
+
+    fn field(&mut self) -> Result<String, String> {
+        let Some(Token::Word(name)) = self.next() else {
+            return Err("expected a field name".to_string());
+        };
+        let known = match self.source {
+            Source::Files => FILE_FIELDS.contains(&name.as_str()),
+            Source::Functions => {
+                FUNCTION_IDENTITY.contains(&name.as_str())
+                    || derived::VARIABLES.contains(&name.as_str())
+                    || name.starts_with("derived:")
+            }
+        };
+        if !known {
+            return Err(format!("unknown field '{}'", name));
+        }
+        if !self.referenced.contains(&name) {
+            self.referenced.push(name.clone());
+        }
+        Ok(name)
+    }
+
+    fn or(&mut self) -> Result<Cond, String> {
+        let mut left = self.and()?;
+        while self.eat_word("or") {
+            left = Cond::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Cond, String> {
+        let mut left = self.unary()?;
+        while self.eat_word("and") {
+            left = Cond::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Cond, String> {
+        if self.eat_word("not") {
+            return Ok(Cond::Not(Box::new(self.unary()?)));
+        }
+        if self.eat_symbol("(") {
+            let inner = self.or()?;
+            return if self.eat_symbol(")") {
+                Ok(inner)
+            } else {
+                Err("missing ')'".to_string())
+            };
+        }
+        let field = self.field()?;
+        let Some(Token::Symbol(op)) = self.next().filter(|t| {
+            matches!(t, Token::Symbol(s) if ["==", "!=", "<=", ">=", "<", ">", "~"].contains(s))
+        }) else {
+            return Err(format!("expected a comparison after '{}'", field));
+        };
+        let literal = match self.next() {
+            Some(Token::Number(n)) => json!(n),
+            Some(Token::Text(s)) | Some(Token::Word(s)) => json!(s),
+            _ => return Err(format!("expected a value after '{}'", op)),
+        };
+        Ok(Cond::Compare(field, op.to_string(), literal))
+    }
+
+    fn stage(&mut self) -> Result<Stage, String> {
+        match self.next() {
+            Some(Token::Word(w)) if w == "where" => Ok(Stage::Where(self.or()?)),
+            Some(Token::Word(w)) if w == "sort" => {
+                let field = self.field()?;
+                let descending = !self.eat_word("asc");
+                self.eat_word("desc");
+                Ok(Stage::Sort(field, descending))
+            }
+            Some(Token::Word(w)) if w == "limit" => match self.next() {
+                Some(Token::Number(n)) if n >= 0.0 && n.fract() == 0.0 => {
+                    Ok(Stage::Limit(n as usize))
+                }
+                _ => Err("limit needs a whole number".to_string()),
+            },
+            Some(Token::Word(w)) if w == "select" => {
+                let mut fields = vec![self.field()?];
+                while self.eat_symbol(",") {
+                    fields.push(self.field()?);
+                }
+                Ok(Stage::Select(fields))
+            }
+            _ => Err("expected where, sort, limit or select".to_string()),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Query, String> {
+    let tokens = tokenize(text)?;
+    let source = match tokens.first() {
+        Some(Token::Word(w)) if w == "functions" => Source::Functions,
+        Some(Token::Word(w)) if w == "files" => Source::Files,
+        _ => return Err("a query starts with 'functions' or 'files'".to_string()),
+    };
+    let mut parser = Parser {
+        tokens,
+        pos: 1,
+        source,
+        referenced: Vec::new(),
+    };
+    let mut stages = Vec::new();
+    while parser.peek().is_some() {
+        if !parser.eat_symbol("|") {
+            return Err("expected '|' between stages".to_string());
+        }
+        stages.push(parser.stage()?);
+    }
+    Ok(Query {
+        source,
+        stages,
+        referenced: parser.referenced,
+    })
+}
+
+fn function_row(f: &FunctionMetric) -> Map<String, Value> {
+    let mut row = Map::new();
+    row.insert("file".into(), json!(f.file));
+    row.insert("function".into(), json!(f.function));
+    row.insert("line".into(), json!(f.line));
+    row.insert("end_line".into(), json!(f.end_line));
+    for name in derived::VARIABLES {
+        let value = match name {
+            "coverage" => json!(f.coverage),
+            _ => json!(derived::variable(f, name)),
+        };
+        row.insert(name.into(), value);
+    }
+    for (name, value) in &f.derived {
+        row.insert(format!("derived:{}", name), json!(value));
+    }
+    row
+}
+
+fn file_row(f: &FileMetrics) -> Map<String, Value> {
+    let row = json!({
+        "file": f.file,
+        "module": f.module,
+        "crate": f.crate_name,
+        "target_kind": f.target_kind,
+        "loc": f.loc,
+        "nloc": f.nloc,
+        "items": f.items,
+        "complexity": f.total_complexity,
+        "functions": f.functions.len(),
+        "lints": f.lints.values().sum::<usize>(),
+        "vendored": f.vendored,
+    });
+    match row {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    }
+}
+
+// `*` and `?` never cross a `/`; `**` matches any run of segments.
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|i| glob(rest, &text[i..]))
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob(rest, &text[i..])),
+        [b'?', rest @ ..] => text.first().is_some_and(|&c| c != b'/') && glob(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64()?.partial_cmp(&y.as_f64()?),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        (Value::Bool(x), Value::String(y)) => Some(x.to_string().cmp(y)),
+        _ => None,
+    }
+}
+
+// Comparisons against a missing value (no coverage, say) are false.
+fn holds(cond: &Cond, row: &Map<String, Value>) -> bool {
+    match cond {
+        Cond::Or(a, b) => holds(a, row) || holds(b, row),
+        Cond::And(a, b) => holds(a, row) && holds(b, row),
+        Cond::Not(c) => !holds(c, row),
+        Cond::Compare(field, op, literal) => {
+            let value = row.get(field).unwrap_or(&Value::Null);
+            if op == "~" {
+                return match (value.as_str(), literal.as_str()) {
+                    (Some(text), Some(pattern)) => glob(pattern.as_bytes(), text.as_bytes()),
+                    _ => false,
+                };
+            }
+            let Some(order) = compare(value, literal) else {
+                return false;
+            };
+            match op.as_str() {
+                "==" => order.is_eq(),
+                "!=" => order.is_ne(),
+                "<" => order.is_lt(),
+                "<=" => order.is_le(),
+                ">" => order.is_gt(),
+                _ => order.is_ge(),
+            }
+        }
+    }
+}
+
+// Rows projected to the `select` fields, or to the identity fields plus
+// every field the query mentions.
+pub fn run(query: &Query, report_path: &str) -> Result<(Vec<String>, Vec<Value>), String> {
+    let text = artifact::read_to_string(report_path)?;
+    let report: InputReport =
+        serde_json::from_str(&text).map_err(|e| format!("{}: not a report: {}", report_path, e))?;
+    let mut rows: Vec<Map<String, Value>> = match query.source {
+        Source::Functions => report
+            .files
+            .iter()
+            .flat_map(|f| &f.functions)
+            .map(function_row)
+            .collect(),
+        Source::Files => report.files.iter().map(file_row).collect(),
+    };
+
+    let identity: &[&str] = match query.source {
+        Source::Functions => &["file", "line", "function"],
+        Source::Files => &["file"],
+    };
+    let mut columns: Vec<String> = identity.iter().map(|s| s.to_string()).collect();
+    columns.extend(
+        query
+            .referenced
+            .iter()
+            .filter(|f| !identity.contains(&f.as_str()))
+            .cloned(),
+    );
+
+    for stage in &query.stages {
+        match stage {
+            Stage::Where(cond) => rows.retain(|row| holds(cond, row)),
+            Stage::Sort(field, descending) => rows.sort_by(|a, b| {
+                let (x, y) = (a.get(field), b.get(field));
+                // Missing values sort last either way.
+                match (x.filter(|v| !v.is_null()), y.filter(|v| !v.is_null())) {
+                    (Some(x), Some(y)) => {
+                        let order = compare(x, y).unwrap_or(Ordering::Equal);
+                        if *descending {
+                            order.reverse()
+                        } else {
+                            order
+                        }
+                    }
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            }),
+            Stage::Limit(n) => rows.truncate(*n),
+            Stage::Select(fields) => columns = fields.clone(),
+        }
+    }
+
+    let projected = rows
+        .into_iter()
+        .map(|row| {
+            let picked: Map<String, Value> = columns
+                .iter()
+                .map(|c| (c.clone(), row.get(c).cloned().unwrap_or(Value::Null)))
+                .collect();
+            Value::Object(picked)
+        })
+        .collect();
+    Ok((columns, projected))
+}
+
+// Numeric columns are right-aligned; the `file` column is the one
+// truncated on narrow terminals.
+pub fn print_table(columns: &[String], rows: &[Value]) {
+    let cell = |v: &Value| match v {
+        Value::String(s) => s.clone(),
+        Value::Null => "-".to_string(),
+        Value::Number(n) => match n.as_f64() {
+            Some(x) if x.fract() != 0.0 => format!("{:.2}", x),
+            Some(x) => format!("{}", x as i64),
+            None => n.to_string(),
+        },
+        other => other.to_string(),
+    };
+    let aligns: Vec<Align> = columns
+        .iter()
+        .map(|c| {
+            if !rows.is_empty() && rows.iter().all(|r| r[c].is_number()) {
+                Align::Right
+            } else {
+                Align::Left
+            }
+        })
+        .collect();
+    let mut table = Table::new(&aligns);
+    if let Some(file) = columns.iter().position(|c| c == "file") {
+        table = table.shrink(file);
+    }
+    table.row(columns.to_vec());
+    for row in rows {
+        table.row(columns.iter().map(|c| cell(&row[c])).collect());
+    }
+    table.print();
+}
@@ -0,0 +1,105 @@
+// --- `rank`: ad-hoc top-N function queries over a saved report JSON ---
+use crate::term::{Align, Table};
+use crate::{artifact, derived, FileMetrics, FunctionMetric};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct InputReport {
+    files: Vec<FileMetrics>,
+}
+
+pub struct Query {
+    pub metric: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub path: Option<String>, // file path prefix, whole components
+    pub limit: usize,
+}
+
+#[derive(Serialize)]
+pub struct Ranked {
+    pub file: String,
+    pub function: String,
+    pub line: usize,
+    pub value: f64,
+}
+
+// Any derived.rs variable, or `derived:NAME` for a config-defined metric.
+pub fn parse_metric(name: &str) -> Result<String, String> {
+    if derived::VARIABLES.contains(&name) || name.starts_with("derived:") {
+        Ok(name.to_string())
+    } else {
+        Err(format!(
+            "unknown metric '{}' (known: {}, derived:NAME)",
+            name,
+            derived::VARIABLES.join(", ")
+        ))
+    }
+}
+
+// Functions without a value (no coverage data, metric not derived in that
+// run) are left out rather than ranked as 0.
+fn value(f: &FunctionMetric, metric: &str) -> Option<f64> {
+    match metric.strip_prefix("derived:") {
+        Some(name) => f.derived.get(name).copied(),
+        None if metric == "coverage" => f.coverage,
+        None => Some(derived::variable(f, metric)),
+    }
+}
+
+// Highest values first; ties by location so output is stable.
+pub fn rank(report_path: &str, query: &Query) -> Result<Vec<Ranked>, String> {
+    let text = artifact::read_to_string(report_path)?;
+    let report: InputReport =
+        serde_json::from_str(&text).map_err(|e| format!("{}: not a report: {}", report_path, e))?;
+
+    let mut rows: Vec<Ranked> = report
+        .files
+        .iter()
+        .filter(|file| {
+            query
+                .path
+                .as_ref()
+                .is_none_or(|p| Path::new(&file.file).starts_with(p))
+        })
+        .flat_map(|file| &file.functions)
+        .filter_map(|f| {
+            let value = value(f, &query.metric)?;
+            let in_range = query.min.is_none_or(|min| value >= min)
+                && query.max.is_none_or(|max| value <= max);
+            in_range.then(|| Ranked {
+                file: f.file.clone(),
+                function: f.function.clone(),
+                line: f.line,
+                value,
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.value
+            .total_cmp(&a.value)
+            .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+    });
+    rows.truncate(query.limit);
+    Ok(rows)
+}
+
+pub fn print_table(rows: &[Ranked], metric: &str) {
+    let mut table = Table::new(&[Align::Right, Align::Right, Align::Left, Align::Left]).shrink(2);
+    table.row(vec![
+        "#".to_string(),
+        metric.to_string(),
+        "LOCATION".to_string(),
+        "FUNCTION".to_string(),
+    ]);
+    for (i, r) in rows.iter().enumerate() {
+        table.row(vec![
+            (i + 1).to_string(),
+            format!("{:.1}", r.value).trim_end_matches(".0").to_string(),
+            format!("{}:{}", r.file, r.line),
+            r.function.clone(),
+        ]);
+    }
+    table.print();
+}
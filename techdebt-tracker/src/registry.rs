@@ -0,0 +1,152 @@
+// --- crates.io downloads: fetch and unpack a published crate ---
+use crate::git;
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const API: &str = "https://crates.io/api/v1/crates";
+// crates.io rejects requests without a descriptive User-Agent.
+const USER_AGENT: &str = concat!(
+    "codehealth-analyzer/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/niazanas8/codehealth-analyzer)"
+);
+
+fn curl(url: &str, output: Option<&Path>) -> Result<Vec<u8>, String> {
+    let mut cmd = Command::new("curl");
+    cmd.args(["-sSfL", "-A", USER_AGENT, url]);
+    if let Some(out) = output {
+        cmd.arg("-o").arg(out);
+    }
+    let out = cmd
+        .output()
+        .map_err(|e| format!("cannot run curl: {}", e))?;
+    if !out.status.success() {
+        return Err(format!(
+            "download of {} failed: {}",
+            url,
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    Ok(out.stdout)
+}
+
+// Newest non-prerelease version, falling back to the newest of any kind.
+fn latest_version(name: &str) -> Result<String, String> {
+    let body = curl(&format!("{}/{}", API, name), None)?;
+    let value: serde_json::Value =
+        serde_json::from_slice(&body).map_err(|e| format!("invalid crates.io response: {}", e))?;
+    let info = &value["crate"];
+    info["max_stable_version"]
+        .as_str()
+        .or_else(|| info["max_version"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("crate {} has no published versions", name))
+}
+
+// crates.io's own rule: an ASCII letter, then up to 63 letters, digits,
+// `-` or `_`. Anything else could escape the workspace once joined.
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.len() <= 64
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// `MAJOR.MINOR.PATCH[-pre][+build]` as semver defines it.
+fn valid_version(version: &str) -> bool {
+    let (rest, build) = match version.split_once('+') {
+        Some((rest, build)) => (rest, Some(build)),
+        None => (version, None),
+    };
+    let (core, pre) = match rest.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (rest, None),
+    };
+    let numeric = |part: &str| {
+        !part.is_empty()
+            && part.bytes().all(|b| b.is_ascii_digit())
+            && (part == "0" || !part.starts_with('0'))
+    };
+    let identifiers = |text: &str| {
+        text.split('.')
+            .all(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-'))
+    };
+    let parts: Vec<&str> = core.split('.').collect();
+    parts.len() == 3
+        && parts.iter().all(|p| numeric(p))
+        && pre.is_none_or(identifiers)
+        && build.is_none_or(identifiers)
+}
+
+// Downloads `name` (latest version unless given) into a temp workspace and
+// returns the unpacked source directory.
+pub fn download_crate(name: &str, version: Option<&str>) -> Result<PathBuf, String> {
+    if !valid_name(name) {
+        return Err(format!("invalid crate name: {}", name));
+    }
+    let version = match version {
+        Some(v) => v.to_string(),
+        None => latest_version(name)?,
+    };
+    if !valid_version(&version) {
+        return Err(format!("invalid version for {}: {}", name, version));
+    }
+    let dir = git::temp_workspace()?;
+    let archive = dir.join(format!("{}-{}.crate", name, version));
+    curl(
+        &format!("{}/{}/{}/download", API, name, version),
+        Some(&archive),
+    )?;
+
+    // A .crate file is a gzipped tarball with a single `<name>-<version>/`
+    // root; `unpack` skips entries that would land outside `dir`.
+    let file =
+        File::open(&archive).map_err(|e| format!("cannot open {}: {}", archive.display(), e))?;
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(&dir)
+        .map_err(|e| format!("cannot unpack {}: {}", archive.display(), e))?;
+    Ok(dir.join(format!("{}-{}", name, version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{valid_name, valid_version};
+
+    #[test]
+    fn names_follow_crates_io_rules() {
+        for name in ["serde", "serde_json", "proc-macro2", "a"] {
+            assert!(valid_name(name), "{}", name);
+        }
+        let long = "a".repeat(65);
+        for name in ["", "../../x", "2d", "-x", "a/b", "a.b", long.as_str()] {
+            assert!(!valid_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn versions_must_be_semver() {
+        for version in [
+            "1.0.0",
+            "0.13.2",
+            "1.0.0-alpha.1",
+            "1.0.0+build.5",
+            "2.0.0-rc-1+x",
+        ] {
+            assert!(valid_version(version), "{}", version);
+        }
+        for version in [
+            "",
+            "1.0",
+            "1.0.0.0",
+            "01.0.0",
+            "1.0.0-",
+            "1.0.0-a..b",
+            "../1.0.0",
+            "1.0.x",
+        ] {
+            assert!(!valid_version(version), "{}", version);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// --- `merge`: org-level rollup of report JSONs from many repositories ---
+use crate::artifact;
+use crate::term::Align::{Left, Right};
+use crate::term::Table;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// Only the fields the rollup needs, so reports from older versions still load.
+#[derive(Deserialize)]
+struct InputMetrics {
+    loc: usize,
+    functions: usize,
+    cyclomatic_complexity: usize,
+    #[serde(default)]
+    max_file_complexity: usize,
+}
+
+#[derive(Deserialize)]
+struct InputReport {
+    metrics: InputMetrics,
+    #[serde(default)]
+    maintainability_index: Option<f64>, // NaN is written as null
+    #[serde(default)]
+    findings: Vec<IgnoredAny>,
+    #[serde(default)]
+    gate_failures: Vec<IgnoredAny>,
+}
+
+#[derive(Serialize, Default, Clone)]
+pub struct RepoSummary {
+    pub loc: usize,
+    pub functions: usize,
+    pub cyclomatic_complexity: usize,
+    pub average_complexity: f64,
+    pub max_file_complexity: usize,
+    pub maintainability_index: f64, // LOC-weighted across repos in the totals
+    pub findings: usize,
+    pub gate_failures: usize,
+}
+
+#[derive(Serialize)]
+pub struct Rollup {
+    pub repos: BTreeMap<String, RepoSummary>,
+    pub totals: RepoSummary,
+}
+
+fn load(path: &str) -> Result<RepoSummary, String> {
+    let text = artifact::read_to_string(path)?;
+    let report: InputReport =
+        serde_json::from_str(&text).map_err(|e| format!("{}: not a report: {}", path, e))?;
+    let m = report.metrics;
+    Ok(RepoSummary {
+        loc: m.loc,
+        functions: m.functions,
+        cyclomatic_complexity: m.cyclomatic_complexity,
+        average_complexity: m.cyclomatic_complexity as f64 / m.functions.max(1) as f64,
+        max_file_complexity: m.max_file_complexity,
+        maintainability_index: report
+            .maintainability_index
+            .filter(|mi| mi.is_finite())
+            .unwrap_or(0.0),
+        findings: report.findings.len(),
+        gate_failures: report.gate_failures.len(),
+    })
+}
+
+// Repos are keyed by file stem (`backend.json` -> `backend`), falling back to
+// the full path when two inputs share a stem.
+pub fn merge(paths: &[String]) -> Result<Rollup, String> {
+    let mut repos = BTreeMap::new();
+    for path in paths {
+        let stem = Path::new(path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.clone());
+        let name = if repos.contains_key(&stem) {
+            path.clone()
+        } else {
+            stem
+        };
+        repos.insert(name, load(path)?);
+    }
+
+    let mut totals = RepoSummary::default();
+    let mut weighted_mi = 0.0;
+    for r in repos.values() {
+        totals.loc = totals.loc.saturating_add(r.loc);
+        totals.functions = totals.functions.saturating_add(r.functions);
+        totals.cyclomatic_complexity = totals
+            .cyclomatic_complexity
+            .saturating_add(r.cyclomatic_complexity);
+        totals.max_file_complexity = totals.max_file_complexity.max(r.max_file_complexity);
+        totals.findings = totals.findings.saturating_add(r.findings);
+        totals.gate_failures = totals.gate_failures.saturating_add(r.gate_failures);
+        weighted_mi += r.maintainability_index * r.loc as f64;
+    }
+    totals.average_complexity =
+        totals.cyclomatic_complexity as f64 / totals.functions.max(1) as f64;
+    totals.maintainability_index = weighted_mi / totals.loc.max(1) as f64;
+
+    Ok(Rollup { repos, totals })
+}
+
+// Names fall back to full paths on a stem clash, so that column shrinks.
+pub fn print_rollup(rollup: &Rollup) {
+    let mut table = Table::new(&[Left, Right, Right, Right, Right, Right]).shrink(0);
+    table.row(
+        ["Repo", "LOC", "Functions", "Avg CC", "MI", "Findings"]
+            .map(str::to_string)
+            .to_vec(),
+    );
+    let rows = rollup
+        .repos
+        .iter()
+        .map(|(name, r)| (name.as_str(), r))
+        .chain([("TOTAL", &rollup.totals)]);
+    for (name, r) in rows {
+        table.row(vec![
+            name.to_string(),
+            r.loc.to_string(),
+            r.functions.to_string(),
+            format!("{:.2}", r.average_complexity),
+            format!("{:.2}", r.maintainability_index),
+            r.findings.to_string(),
+        ]);
+    }
+    table.print();
+}
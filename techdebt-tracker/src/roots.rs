@@ -0,0 +1,57 @@
+// --- Several `--path` roots: which root each file came from, with totals ---
+use crate::{lexical_absolute, paths, FileMetrics};
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+pub struct RootSummary {
+    pub root: String, // as given on the command line
+    pub files: usize,
+    pub loc: usize,
+    pub functions: usize,
+    pub total_complexity: usize,
+    pub max_complexity: usize,
+}
+
+// Credits each file to the first root containing it, the same root
+// discovery found it under. Runs on raw paths, before canonicalization.
+pub fn attribute(files: &mut [FileMetrics], roots: &[String]) {
+    let roots: Vec<(&String, PathBuf)> = roots
+        .iter()
+        .filter_map(|r| Some((r, lexical_absolute(r).ok()?)))
+        .collect();
+    for file in files {
+        let Ok(abs) = lexical_absolute(&file.file) else {
+            continue;
+        };
+        let under = |abs_root: &PathBuf| paths::strip_prefix(&abs, abs_root).is_some();
+        if let Some((root, _)) = roots.iter().find(|(_, abs_root)| under(abs_root)) {
+            file.root = root.to_string();
+        }
+    }
+}
+
+// One entry per root, in command-line order, including roots with no files.
+pub fn summarize(files: &[FileMetrics], roots: &[String]) -> Vec<RootSummary> {
+    roots
+        .iter()
+        .map(|root| {
+            let owned = files.iter().filter(|f| f.root == *root);
+            RootSummary {
+                root: root.clone(),
+                files: owned.clone().count(),
+                loc: owned.clone().map(|f| f.loc).fold(0, usize::saturating_add),
+                functions: owned.clone().map(|f| f.functions.len()).sum(),
+                total_complexity: owned
+                    .clone()
+                    .map(|f| f.total_complexity)
+                    .fold(0, usize::saturating_add),
+                max_complexity: owned
+                    .flat_map(|f| &f.functions)
+                    .map(|f| f.complexity)
+                    .max()
+                    .unwrap_or(0),
+            }
+        })
+        .collect()
+}
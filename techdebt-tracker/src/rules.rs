@@ -0,0 +1,209 @@
+// --- Suppressions and per-rule statistics ---
+// A finding is suppressed by a `// codehealth-allow: <rule>[, <rule>]` comment
+// inside its function or directly above it, or by a `[[suppressions]]` entry
+// in the config. Suppressed findings stay in the report, but apart.
+// Suppressions and baseline entries past their `expires` date silence
+// nothing; they are listed as expired debt, with the findings they cover.
+use crate::config::{Suppression, Thresholds};
+use crate::{components, fingerprint, sarif, Finding, Report};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+pub const ALLOW_MARKER: &str = "// codehealth-allow:";
+
+fn allowed_rules(line: &str) -> impl Iterator<Item = String> + '_ {
+    line.trim_start()
+        .strip_prefix(ALLOW_MARKER)
+        .into_iter()
+        .flat_map(|rules| rules.split(','))
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+}
+
+// Allow comments covering a function spanning `start..=end` (1-based): any in
+// its body, plus those in the comment/attribute block right above it.
+pub fn function_allows(lines: &[&str], start: usize, end: usize) -> Vec<String> {
+    let mut allows: Vec<String> = lines
+        .iter()
+        .take(end)
+        .skip(start.saturating_sub(1))
+        .flat_map(|l| allowed_rules(l))
+        .collect();
+    for line in lines[..start.saturating_sub(1).min(lines.len())]
+        .iter()
+        .rev()
+    {
+        let t = line.trim_start();
+        if !t.starts_with("//") && !t.starts_with("#[") {
+            break;
+        }
+        allows.extend(allowed_rules(line));
+    }
+    allows.sort();
+    allows.dedup();
+    allows
+}
+
+// Every rule that can produce findings, with whether the thresholds enable it.
+pub fn enabled(thresholds: &Thresholds) -> Vec<(&'static str, bool)> {
+    vec![
+        ("too-many-exit-points", thresholds.max_exit_points.is_some()),
+        (
+            "complex-conditional",
+            thresholds.max_condition_operands.is_some(),
+        ),
+        ("long-call-chain", thresholds.max_call_chain.is_some()),
+        (
+            "giant-codegen-function",
+            thresholds.max_quote_tokens.is_some(),
+        ),
+        ("feature-envy", thresholds.feature_envy_ratio.is_some()),
+        ("large-include", thresholds.max_include_lines.is_some()),
+        ("statistical-outlier", thresholds.auto_threshold.is_some()),
+        (
+            "complex-macro",
+            thresholds.max_macro_rules.is_some()
+                || thresholds.max_macro_depth.is_some()
+                || thresholds.max_macro_tokens.is_some(),
+        ),
+    ]
+}
+
+#[derive(Serialize, Clone)]
+pub struct RuleStats {
+    pub rule: String,
+    pub enabled: bool,
+    pub findings: usize, // reported, i.e. not suppressed
+    pub suppressed: usize,
+    pub new: Option<usize>, // not in the baseline; only with --baseline
+}
+
+#[derive(Serialize, Clone)]
+pub struct ExpiredDebt {
+    pub source: String, // "config", "baseline" or an ignore-file `file:line`
+    pub expires: String,
+    pub reason: Option<String>,
+    pub rule: Option<String>,
+    pub file: Option<String>,
+    pub function: Option<String>,
+    pub fingerprint: Option<String>,
+    pub findings: usize, // findings it covered that count again
+}
+
+pub fn is_expired(expires: Option<&str>, today: &str) -> bool {
+    expires.is_some_and(|d| d <= today)
+}
+
+// Drops baseline results past their `properties.expires` date, so their
+// findings count as new again. Returns what was dropped, keyed by identity.
+pub fn expire_baseline(
+    baseline: &mut BTreeMap<String, Value>,
+    today: &str,
+) -> Vec<(String, ExpiredDebt)> {
+    let expired: Vec<String> = baseline
+        .iter()
+        .filter(|(_, r)| is_expired(r["properties"]["expires"].as_str(), today))
+        .map(|(key, _)| key.clone())
+        .collect();
+    expired
+        .into_iter()
+        .map(|key| {
+            let r = baseline.remove(&key).unwrap();
+            let mut parts = key.split('|').map(str::to_string);
+            let debt = ExpiredDebt {
+                source: "baseline".to_string(),
+                expires: r["properties"]["expires"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                reason: r["properties"]["reason"].as_str().map(str::to_string),
+                rule: parts.next(),
+                file: parts.next(),
+                function: parts.next(),
+                fingerprint: r["partialFingerprints"][sarif::FINDING_ID_KEY]
+                    .as_str()
+                    .map(str::to_string),
+                findings: 0,
+            };
+            (key, debt)
+        })
+        .collect()
+}
+
+pub fn matches(s: &Suppression, f: &Finding) -> bool {
+    let eq = |want: &Option<String>, have: &str| want.as_deref().is_none_or(|w| w == have);
+    eq(&s.rule, &f.rule)
+        && s.file
+            .as_deref()
+            .is_none_or(|w| w.replace('\\', "/") == f.file)
+        && eq(&s.function, &f.function)
+        && eq(&s.fingerprint, &f.fingerprint)
+        && s.older_than_days
+            .is_none_or(|days| f.age_days.is_some_and(|age| age >= days))
+}
+
+// Runs after canonicalization, since config entries name relative paths and
+// fingerprints. `baseline` holds SARIF results keyed by finding identity;
+// `expired_baseline` what expire_baseline() dropped from it. `today` is
+// YYYY-MM-DD.
+pub fn apply(
+    report: &mut Report,
+    suppressions: &[Suppression],
+    baseline: Option<&BTreeMap<String, Value>>,
+    expired_baseline: Vec<(String, ExpiredDebt)>,
+    today: &str,
+) {
+    let (expired, suppressions): (Vec<&Suppression>, Vec<&Suppression>) = suppressions
+        .iter()
+        .partition(|s| is_expired(s.expires.as_deref(), today));
+    let allows: HashMap<(&str, &str), &Vec<String>> = report
+        .files
+        .iter()
+        .flat_map(|f| &f.functions)
+        .map(|f| ((f.file.as_str(), f.function.as_str()), &f.allows))
+        .collect();
+    let identities = fingerprint::identities(&report.findings);
+    let mut kept = Vec::new();
+    let mut new_by_rule: BTreeMap<String, usize> = BTreeMap::new();
+    for (finding, identity) in report.findings.drain(..).zip(identities) {
+        let inline = allows
+            .get(&(finding.file.as_str(), finding.function.as_str()))
+            .is_some_and(|a| a.iter().any(|r| r == &finding.rule || r == "all"));
+        if inline || suppressions.iter().any(|s| matches(s, &finding)) {
+            report.suppressed_findings.push(finding);
+            continue;
+        }
+        if baseline.is_some_and(|b| !b.contains_key(&identity)) {
+            *new_by_rule.entry(finding.rule.clone()).or_default() += 1;
+        }
+        kept.push(finding);
+    }
+    report.findings = kept;
+
+    for s in expired {
+        report.expired_debt.push(ExpiredDebt {
+            source: s.origin.clone().unwrap_or_else(|| "config".to_string()),
+            expires: s.expires.clone().unwrap_or_default(),
+            reason: s.reason.clone(),
+            rule: s.rule.clone(),
+            file: s.file.clone(),
+            function: s.function.clone(),
+            fingerprint: s.fingerprint.clone(),
+            findings: report.findings.iter().filter(|f| matches(s, f)).count(),
+        });
+    }
+    let identities = fingerprint::identities(&report.findings);
+    for (key, mut debt) in expired_baseline {
+        debt.findings = identities.iter().filter(|id| **id == key).count();
+        report.expired_debt.push(debt);
+    }
+
+    for stats in &mut report.rule_statistics {
+        let count = |findings: &[Finding]| findings.iter().filter(|f| f.rule == stats.rule).count();
+        stats.findings = count(&report.findings);
+        stats.suppressed = count(&report.suppressed_findings);
+        stats.new = baseline.map(|_| new_by_rule.get(&stats.rule).copied().unwrap_or(0));
+    }
+    components::count_findings(report);
+}
@@ -0,0 +1,75 @@
+// --- `--item-metrics`: function metrics keyed by item path, for joining with rustdoc JSON ---
+// Rustdoc's numeric item ids change between builds, but each entry of its
+// `paths` table carries the item's path (`["my_crate", "api", "handler"]`)
+// and kind, which is what these keys and fields mirror.
+use crate::Report;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+pub struct ItemMetrics {
+    pub path: Vec<String>,
+    pub kind: &'static str, // rustdoc's item kind; functions only for now
+    pub crate_name: String,
+    pub file: String,
+    pub line: usize,
+    pub end_line: usize,
+    pub complexity: usize,
+    pub cognitive_complexity: usize,
+    pub loc: usize,
+    pub nesting: usize,
+    pub params: usize,
+}
+
+#[derive(Serialize)]
+pub struct ItemIndex {
+    pub format_version: u32,
+    pub items: BTreeMap<String, ItemMetrics>, // `my_crate::api::handler` -> metrics
+}
+
+// Files outside any crate have module paths relative to the scan root,
+// which rustdoc never produces; they are still listed under those paths.
+// Same-path functions (e.g. `#[cfg]` variants) keep the most complex.
+pub fn index(report: &Report) -> ItemIndex {
+    let mut items: BTreeMap<String, ItemMetrics> = BTreeMap::new();
+    for file in &report.files {
+        for f in &file.functions {
+            let mut path: Vec<String> = file
+                .module
+                .split("::")
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect();
+            path.push(f.function.clone());
+            let key = path.join("::");
+            if items
+                .get(&key)
+                .is_some_and(|i| i.complexity >= f.complexity)
+            {
+                continue;
+            }
+            items.insert(
+                key,
+                ItemMetrics {
+                    path,
+                    kind: "function",
+                    crate_name: file.crate_name.clone(),
+                    file: f.file.clone(),
+                    line: f.line,
+                    end_line: f.end_line,
+                    complexity: f.complexity,
+                    cognitive_complexity: f.cognitive_complexity,
+                    loc: f.loc,
+                    nesting: f.nesting,
+                    params: f.params,
+                },
+            );
+        }
+    }
+    ItemIndex {
+        format_version: FORMAT_VERSION,
+        items,
+    }
+}
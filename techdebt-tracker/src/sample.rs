@@ -0,0 +1,112 @@
+// --- `--sample PCT`: analyze a stable subset and extrapolate totals ---
+// Files are picked by a seeded hash of their root-relative path, so the same
+// seed selects the same files on every run and machine. Totals are scaled
+// up from the per-file sample mean, with a normal-approximation 95% interval
+// corrected for sampling without replacement.
+use crate::{fingerprint, FileMetrics};
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Clone, Copy)]
+pub struct Sample {
+    pub percent: f64,
+    pub seed: u64,
+}
+
+// `10%` or `10` -> a tenth of the files.
+pub fn parse_percent(spec: &str) -> Result<f64, String> {
+    let percent: f64 = spec
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("invalid sample '{}', expected a percentage like 10%", spec))?;
+    if !(percent > 0.0 && percent <= 100.0) {
+        return Err(format!("invalid sample '{}': need 0% < PCT <= 100%", spec));
+    }
+    Ok(percent)
+}
+
+impl Sample {
+    pub fn selects(&self, file: &Path, root: &Path) -> bool {
+        let relative = file.strip_prefix(root).unwrap_or(file);
+        let key = format!(
+            "{}:{}",
+            self.seed,
+            relative.to_string_lossy().replace('\\', "/")
+        );
+        // Basis points, so fractional percentages like 0.5% work.
+        ((fingerprint::fnv1a(&key) % 10_000) as f64) < self.percent * 100.0
+    }
+}
+
+#[derive(Serialize)]
+pub struct Estimate {
+    pub sampled: usize, // total over the sampled files
+    pub estimate: f64,  // extrapolated to every file
+    pub low: f64,       // 95% confidence interval
+    pub high: f64,
+}
+
+#[derive(Serialize)]
+pub struct SampleSummary {
+    pub percent: f64,
+    pub seed: u64,
+    pub population_files: usize, // files discovered before sampling
+    pub sampled_files: usize,
+    pub loc: Estimate,
+    pub functions: Estimate,
+    pub complexity: Estimate,
+}
+
+const Z_95: f64 = 1.96;
+
+fn estimate(values: &[usize], population: usize) -> Estimate {
+    let n = values.len();
+    let sampled = values.iter().copied().fold(0, usize::saturating_add);
+    if n == 0 {
+        return Estimate {
+            sampled,
+            estimate: 0.0,
+            low: 0.0,
+            high: 0.0,
+        };
+    }
+    let big_n = population as f64;
+    let mean = sampled as f64 / n as f64;
+    let variance = if n > 1 {
+        values
+            .iter()
+            .map(|&v| (v as f64 - mean).powi(2))
+            .sum::<f64>()
+            / (n - 1) as f64
+    } else {
+        0.0
+    };
+    let correction = if population > 1 {
+        ((big_n - n as f64) / (big_n - 1.0)).max(0.0)
+    } else {
+        0.0
+    };
+    let estimate = big_n * mean;
+    let margin = Z_95 * big_n * (variance / n as f64 * correction).sqrt();
+    Estimate {
+        sampled,
+        estimate,
+        // The sampled files themselves are a hard floor.
+        low: (estimate - margin).max(sampled as f64),
+        high: estimate + margin,
+    }
+}
+
+pub fn summarize(sample: Sample, population: usize, files: &[FileMetrics]) -> SampleSummary {
+    let column = |f: fn(&FileMetrics) -> usize| -> Vec<usize> { files.iter().map(f).collect() };
+    SampleSummary {
+        percent: sample.percent,
+        seed: sample.seed,
+        population_files: population,
+        sampled_files: files.len(),
+        loc: estimate(&column(|f| f.loc), population),
+        functions: estimate(&column(|f| f.functions.len()), population),
+        complexity: estimate(&column(|f| f.total_complexity), population),
+    }
+}
@@ -0,0 +1,129 @@
+// --- SARIF 2.1.0 output for code-scanning UIs, with optional baseline ---
+use crate::{artifact, fingerprint, Finding, Report};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+// v1 is the readable identity baselines match on; v2 is the hashed finding id.
+const FINGERPRINT_KEY: &str = "codehealth/v1";
+pub const FINDING_ID_KEY: &str = "codehealth/v2";
+
+pub fn rule_description(rule: &str) -> &'static str {
+    match rule {
+        "too-many-exit-points" => "Function has more return/? exit points than allowed",
+        "complex-conditional" => "Condition has more &&/|| operands than allowed",
+        "long-call-chain" => "Method-call chain is longer than allowed",
+        "giant-codegen-function" => "Proc-macro function generates more quote! tokens than allowed",
+        "feature-envy" => "Function calls into another module far more than its own",
+        "statistical-outlier" => "Function complexity or LOC is an outlier for this codebase",
+        "large-include" => "include!/include_str! pulls in a file longer than allowed",
+        "complex-macro" => "macro_rules! definition has more rules, nesting or tokens than allowed",
+        _ => "CodeHealth rule violation",
+    }
+}
+
+fn result(finding: &Finding, fingerprint: &str) -> Value {
+    let mut region = json!({ "startLine": finding.line.max(1) });
+    if let Some(text) = &finding.snippet {
+        region["snippet"] = json!({ "text": text });
+    }
+    let mut result = json!({
+        "ruleId": finding.rule,
+        "level": "warning",
+        "message": { "text": format!("{}: {}", finding.function, finding.message) },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": finding.file },
+                "region": region
+            }
+        }],
+        "partialFingerprints": {
+            FINGERPRINT_KEY: fingerprint,
+            FINDING_ID_KEY: finding.fingerprint
+        }
+    });
+    if let (Some(first_seen), Some(age_days)) = (&finding.first_seen, finding.age_days) {
+        result["properties"] = json!({ "firstSeen": first_seen, "ageDays": age_days });
+    }
+    result
+}
+
+// Results of a previous SARIF run, keyed by our fingerprint.
+pub fn load_baseline(path: &str) -> Result<BTreeMap<String, Value>, String> {
+    let text = artifact::read_to_string(path)?;
+    let sarif: Value =
+        serde_json::from_str(&text).map_err(|e| format!("{}: invalid SARIF: {}", path, e))?;
+    let results = sarif["runs"][0]["results"]
+        .as_array()
+        .ok_or_else(|| format!("{}: no runs[0].results in baseline", path))?;
+    Ok(results
+        .iter()
+        .filter(|r| r["baselineState"] != "absent")
+        .filter_map(|r| {
+            let key = r["partialFingerprints"][FINGERPRINT_KEY].as_str()?;
+            Some((key.to_string(), r.clone()))
+        })
+        .collect())
+}
+
+// With a baseline every result gets a `baselineState`: `new`, `unchanged`,
+// `updated` (same finding, different metric value) or `absent` (fixed).
+pub fn render(report: &Report, baseline: Option<&BTreeMap<String, Value>>) -> Value {
+    let keys = fingerprint::identities(&report.findings);
+    let mut results: Vec<Value> = Vec::new();
+    for (finding, key) in report.findings.iter().zip(&keys) {
+        let mut r = result(finding, key);
+        if let Some(base) = baseline {
+            r["baselineState"] = json!(match base.get(key) {
+                None => "new",
+                Some(old) if old["message"] == r["message"] => "unchanged",
+                Some(_) => "updated",
+            });
+        }
+        results.push(r);
+    }
+    if let Some(base) = baseline {
+        for (key, old) in base.iter().filter(|(k, _)| !keys.contains(k)) {
+            let mut r = old.clone();
+            r["partialFingerprints"][FINGERPRINT_KEY] = json!(key);
+            r["baselineState"] = json!("absent");
+            results.push(r);
+        }
+    }
+
+    // Absent results still reference their rule, so collect ids from all results.
+    let mut rules: Vec<&str> = results
+        .iter()
+        .filter_map(|r| r["ruleId"].as_str())
+        .collect();
+    rules.sort();
+    rules.dedup();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "codehealth-analyzer",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/niazanas8/codehealth-analyzer",
+                    "rules": rules.iter().map(|id| json!({
+                        "id": id,
+                        "shortDescription": { "text": rule_description(id) }
+                    })).collect::<Vec<_>>()
+                }
+            },
+            "results": results,
+            "properties": {
+                "meta": report.meta,
+                "grade": report.grades.grade,
+                "gradeScore": report.grades.score,
+                "crateGrades": report.grades.crates.iter().map(|c| json!({
+                    "crate": c.crate_name,
+                    "grade": c.grade,
+                    "score": c.score
+                })).collect::<Vec<_>>()
+            }
+        }]
+    })
+}
@@ -0,0 +1,185 @@
+// --- Security-sensitive surface: process spawning, SQL building, transmute, secrets, FFI ---
+// Counts call sites worth a security review, not vulnerabilities.
+use crate::FileMetrics;
+use serde::{Deserialize, Serialize};
+use syn::visit::Visit;
+
+// Environment variable names that usually hold credentials.
+const SECRET_MARKERS: [&str; 6] = [
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "API_KEY",
+    "PRIVATE_KEY",
+];
+const SQL_KEYWORDS: [&str; 5] = ["SELECT ", "INSERT ", "UPDATE ", "DELETE ", "WHERE "];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+pub struct SecurityCounts {
+    pub process_commands: usize,  // `Command::new`
+    pub sql_concatenation: usize, // SQL text built with `format!`, `+` or `push_str`
+    pub transmutes: usize,
+    pub secret_env_reads: usize, // `env::var("..._TOKEN")` and the like
+    pub ffi: usize,              // `extern "C"` blocks and functions
+}
+
+impl SecurityCounts {
+    pub fn total(&self) -> usize {
+        self.process_commands
+            + self.sql_concatenation
+            + self.transmutes
+            + self.secret_env_reads
+            + self.ffi
+    }
+
+    fn add(&mut self, other: &SecurityCounts) {
+        self.process_commands += other.process_commands;
+        self.sql_concatenation += other.sql_concatenation;
+        self.transmutes += other.transmutes;
+        self.secret_env_reads += other.secret_env_reads;
+        self.ffi += other.ffi;
+    }
+}
+
+fn looks_like_sql(text: &str) -> bool {
+    let upper = text.to_uppercase();
+    SQL_KEYWORDS.iter().any(|k| upper.contains(k))
+}
+
+fn is_sql_literal(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => looks_like_sql(&s.value()),
+        syn::Expr::Reference(r) => is_sql_literal(&r.expr),
+        // `"SELECT ...".to_string() + id`
+        syn::Expr::MethodCall(m) => is_sql_literal(&m.receiver),
+        _ => false,
+    }
+}
+
+fn last_two(path: &syn::Path) -> (String, String) {
+    let mut names = path.segments.iter().rev().map(|s| s.ident.to_string());
+    let last = names.next().unwrap_or_default();
+    (names.next().unwrap_or_default(), last)
+}
+
+fn is_rust_abi(abi: &syn::Abi) -> bool {
+    abi.name.as_ref().is_some_and(|n| n.value() == "Rust")
+}
+
+#[derive(Default)]
+struct SecurityVisitor {
+    counts: SecurityCounts,
+}
+
+impl<'ast> Visit<'ast> for SecurityVisitor {
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(p) = &*node.func {
+            match last_two(&p.path) {
+                (ty, f) if ty == "Command" && f == "new" => self.counts.process_commands += 1,
+                (_, f) if f == "transmute" || f == "transmute_copy" => self.counts.transmutes += 1,
+                (m, f) if m == "env" && (f == "var" || f == "var_os") => {
+                    if let Some(syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(key),
+                        ..
+                    })) = node.args.first()
+                    {
+                        let key = key.value().to_uppercase();
+                        if SECRET_MARKERS.iter().any(|m| key.contains(m)) {
+                            self.counts.secret_env_reads += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
+        if matches!(node.op, syn::BinOp::Add(_) | syn::BinOp::AddAssign(_))
+            && (is_sql_literal(&node.left) || is_sql_literal(&node.right))
+        {
+            self.counts.sql_concatenation += 1;
+        }
+        syn::visit::visit_expr_binary(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        if node.method == "push_str" && node.args.first().is_some_and(is_sql_literal) {
+            self.counts.sql_concatenation += 1;
+        }
+        syn::visit::visit_expr_method_call(self, node);
+    }
+
+    // `format!("SELECT ... {}", x)`: a SQL template with placeholders.
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        if node.path.is_ident("format") {
+            let template = node
+                .tokens
+                .clone()
+                .into_iter()
+                .next()
+                .and_then(|t| syn::parse2::<syn::LitStr>(t.into()).ok());
+            if template.is_some_and(|t| looks_like_sql(&t.value()) && t.value().contains('{')) {
+                self.counts.sql_concatenation += 1;
+            }
+        }
+        syn::visit::visit_macro(self, node);
+    }
+
+    fn visit_item_foreign_mod(&mut self, node: &'ast syn::ItemForeignMod) {
+        if !is_rust_abi(&node.abi) {
+            self.counts.ffi += 1;
+        }
+        syn::visit::visit_item_foreign_mod(self, node);
+    }
+
+    fn visit_signature(&mut self, node: &'ast syn::Signature) {
+        if node.abi.as_ref().is_some_and(|abi| !is_rust_abi(abi)) {
+            self.counts.ffi += 1;
+        }
+        syn::visit::visit_signature(self, node);
+    }
+}
+
+pub fn count(syntax: &syn::File) -> SecurityCounts {
+    let mut visitor = SecurityVisitor::default();
+    visitor.visit_file(syntax);
+    visitor.counts
+}
+
+#[derive(Serialize)]
+pub struct FileSecurity {
+    pub file: String,
+    #[serde(flatten)]
+    pub counts: SecurityCounts,
+}
+
+#[derive(Serialize, Default)]
+pub struct SecuritySurface {
+    #[serde(flatten)]
+    pub totals: SecurityCounts,
+    pub files: Vec<FileSecurity>, // only files with any, most first
+}
+
+pub fn summarize(files: &[FileMetrics]) -> SecuritySurface {
+    let mut surface = SecuritySurface::default();
+    for f in files.iter().filter(|f| f.security.total() > 0) {
+        surface.totals.add(&f.security);
+        surface.files.push(FileSecurity {
+            file: f.file.clone(),
+            counts: f.security,
+        });
+    }
+    surface.files.sort_by(|a, b| {
+        b.counts
+            .total()
+            .cmp(&a.counts.total())
+            .then_with(|| a.file.cmp(&b.file))
+    });
+    surface
+}
@@ -0,0 +1,128 @@
+// --- `--shard K/N`: split one scan across CI workers, then `merge --shards` ---
+// Each shard analyzes the files whose path hashes to it and embeds its
+// per-file results in the JSON report. Merging feeds those results back
+// through the cross-file passes and aggregation, so the combined report
+// matches an unsharded run of the same tree.
+use crate::cache::Hidden;
+use crate::{artifact, fingerprint, merge_metrics, CodeMetrics, Diagnostic, FileMetrics};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Shard {
+    pub index: usize, // 1-based
+    pub count: usize,
+}
+
+// `2/8` -> shard 2 of 8.
+pub fn parse(spec: &str) -> Result<Shard, String> {
+    let (index, count) = spec
+        .split_once('/')
+        .and_then(|(i, n)| Some((i.trim().parse().ok()?, n.trim().parse().ok()?)))
+        .ok_or_else(|| format!("invalid shard '{}', expected K/N", spec))?;
+    if count == 0 || index == 0 || index > count {
+        return Err(format!("invalid shard '{}': need 1 <= K <= N", spec));
+    }
+    Ok(Shard { index, count })
+}
+
+impl Shard {
+    // Partitioned by the path relative to its scan root, so the split does not
+    // depend on walk order or on where the checkout lives.
+    pub fn selects(&self, file: &Path, root: &Path) -> bool {
+        let relative = file.strip_prefix(root).unwrap_or(file);
+        let key = relative.to_string_lossy().replace('\\', "/");
+        fingerprint::fnv1a(&key) % self.count as u64 == (self.index - 1) as u64
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PartialFile {
+    detail: FileMetrics,
+    #[serde(flatten)]
+    hidden: Hidden,
+}
+
+// What a shard contributes to the merged report, captured before any
+// cross-file pass. Paths are left as scanned.
+#[derive(Serialize, Deserialize)]
+pub struct Partial {
+    #[serde(flatten)]
+    shard: Shard,
+    metrics: CodeMetrics,
+    files: Vec<PartialFile>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Partial {
+    pub fn capture(
+        shard: Shard,
+        metrics: &CodeMetrics,
+        files: &[FileMetrics],
+        diagnostics: &[Diagnostic],
+    ) -> Partial {
+        Partial {
+            shard,
+            metrics: metrics.clone(),
+            files: files
+                .iter()
+                .map(|f| PartialFile {
+                    detail: f.clone(),
+                    hidden: Hidden::capture(f),
+                })
+                .collect(),
+            diagnostics: diagnostics.to_vec(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ShardReport {
+    shard: Option<Partial>,
+}
+
+// Loads shard reports and checks they are exactly shards 1..=N of one split.
+pub fn combine(
+    paths: &[String],
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<(CodeMetrics, Vec<FileMetrics>), String> {
+    let mut partials = Vec::new();
+    for path in paths {
+        let text = artifact::read_to_string(path)?;
+        let report: ShardReport =
+            serde_json::from_str(&text).map_err(|e| format!("{}: not a report: {}", path, e))?;
+        let partial = report
+            .shard
+            .ok_or_else(|| format!("{}: not a --shard report", path))?;
+        partials.push(partial);
+    }
+    partials.sort_by_key(|p| p.shard.index);
+
+    let count = partials.first().map_or(0, |p| p.shard.count);
+    let indexes: Vec<usize> = partials.iter().map(|p| p.shard.index).collect();
+    if partials.iter().any(|p| p.shard.count != count) || indexes != (1..=count).collect::<Vec<_>>()
+    {
+        let got: Vec<String> = partials
+            .iter()
+            .map(|p| format!("{}/{}", p.shard.index, p.shard.count))
+            .collect();
+        return Err(format!(
+            "shard reports must cover 1/N..N/N exactly once, got {}",
+            got.join(", ")
+        ));
+    }
+
+    let mut total = CodeMetrics::default();
+    let mut files = Vec::new();
+    for partial in partials {
+        merge_metrics(&mut total, &partial.metrics);
+        diagnostics.extend(partial.diagnostics);
+        files.extend(partial.files.into_iter().map(|f| {
+            let mut detail = f.detail;
+            f.hidden.restore(&mut detail);
+            detail
+        }));
+    }
+    total.kloc = total.loc as f64 / 1000.0;
+    Ok((total, files))
+}
@@ -0,0 +1,151 @@
+// --- `simulate`: how many findings each candidate threshold would produce ---
+use crate::config::Thresholds;
+use crate::term::Align::{Left, Right};
+use crate::term::Table;
+use crate::{collect_findings, find_oversized_files, FileMetrics};
+use serde::Serialize;
+
+pub const SIMULATED: [&str; 7] = [
+    "function-complexity",
+    "max-exit-points",
+    "max-condition-operands",
+    "max-call-chain",
+    "feature-envy-ratio",
+    "max-file-loc",
+    "max-items-per-module",
+];
+
+#[derive(Clone)]
+pub struct Candidates {
+    pub threshold: String,
+    pub values: Vec<f64>,
+}
+
+// `NAME=10..20` (inclusive, step 1), `NAME=10..50:10` or `NAME=2,2.5,3`.
+// `derived:NAME` simulates a `max` for a config-defined metric.
+pub fn parse(spec: &str) -> Result<Candidates, String> {
+    let (name, values) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=RANGE, got '{}'", spec))?;
+    if !SIMULATED.contains(&name) && !name.starts_with("derived:") {
+        return Err(format!(
+            "cannot simulate '{}' (known: {}, derived:NAME)",
+            name,
+            SIMULATED.join(", ")
+        ));
+    }
+    let number = |s: &str| {
+        s.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("invalid value '{}' in '{}'", s, spec))
+    };
+    let values = match values.split_once("..") {
+        Some((from, rest)) => {
+            let (to, step) = rest.split_once(':').unwrap_or((rest, "1"));
+            let (from, to, step) = (number(from)?, number(to)?, number(step)?);
+            if step <= 0.0 || to < from {
+                return Err(format!("empty range in '{}'", spec));
+            }
+            let steps = ((to - from) / step).floor() as usize;
+            (0..=steps).map(|i| from + step * i as f64).collect()
+        }
+        None => values.split(',').map(number).collect::<Result<_, _>>()?,
+    };
+    Ok(Candidates {
+        threshold: name.to_string(),
+        values,
+    })
+}
+
+#[derive(Serialize)]
+pub struct Outcome {
+    pub value: f64,
+    pub findings: usize,
+    pub percent: f64, // of functions, or of files for file-level limits
+}
+
+#[derive(Serialize)]
+pub struct Simulation {
+    pub threshold: String,
+    pub outcomes: Vec<Outcome>,
+}
+
+fn only(name: &str, value: f64) -> Thresholds {
+    let mut t = Thresholds::default();
+    match name {
+        "max-exit-points" => t.max_exit_points = Some(value as usize),
+        "max-condition-operands" => t.max_condition_operands = Some(value as usize),
+        "max-call-chain" => t.max_call_chain = Some(value as usize),
+        "feature-envy-ratio" => t.feature_envy_ratio = Some(value),
+        "max-file-loc" => t.max_file_loc = Some(value as usize),
+        "max-items-per-module" => t.max_items_per_module = Some(value as usize),
+        _ => {}
+    }
+    t
+}
+
+// Counts are before suppressions and inline allows, i.e. what enforcing the
+// limit on today's code would surface.
+pub fn simulate(files: &[FileMetrics], candidates: &Candidates) -> Simulation {
+    let name = candidates.threshold.as_str();
+    let functions = files.iter().flat_map(|f| &f.functions);
+    let function_count = functions.clone().count();
+    let outcomes = candidates
+        .values
+        .iter()
+        .map(|&value| {
+            let (findings, population) = match name {
+                "function-complexity" => (
+                    functions
+                        .clone()
+                        .filter(|f| f.complexity as f64 > value)
+                        .count(),
+                    function_count,
+                ),
+                "max-file-loc" | "max-items-per-module" => (
+                    find_oversized_files(files, &only(name, value)).len(),
+                    files.len(),
+                ),
+                _ => match name.strip_prefix("derived:") {
+                    Some(metric) => (
+                        functions
+                            .clone()
+                            .filter(|f| f.derived.get(metric).is_some_and(|&v| v > value))
+                            .count(),
+                        function_count,
+                    ),
+                    None => (
+                        collect_findings(files, &only(name, value)).len(),
+                        function_count,
+                    ),
+                },
+            };
+            Outcome {
+                value,
+                findings,
+                percent: findings as f64 * 100.0 / population.max(1) as f64,
+            }
+        })
+        .collect();
+    Simulation {
+        threshold: candidates.threshold.clone(),
+        outcomes,
+    }
+}
+
+pub fn print_simulation(s: &Simulation) {
+    let mut table = Table::new(&[Left, Right, Right]);
+    table.row(vec![
+        s.threshold.clone(),
+        "Findings".to_string(),
+        "%".to_string(),
+    ]);
+    for o in &s.outcomes {
+        table.row(vec![
+            o.value.to_string(),
+            o.findings.to_string(),
+            format!("{:.1}%", o.percent),
+        ]);
+    }
+    table.print();
+}
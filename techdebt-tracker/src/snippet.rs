@@ -0,0 +1,61 @@
+// --- `--with-snippets`: short source excerpts attached to findings ---
+use crate::{FileMetrics, Finding};
+use std::collections::HashMap;
+use std::fs;
+
+// A complex condition is quoted from its line up to the one opening the
+// block or ending the statement; anything else quotes the head of the
+// offending function. Excerpts are capped at `max_lines` and dedented.
+pub fn attach(findings: &mut [Finding], files: &[FileMetrics], max_lines: usize) {
+    let mut sources: HashMap<String, Option<Vec<String>>> = HashMap::new();
+    for finding in findings.iter_mut() {
+        let lines = sources.entry(finding.file.clone()).or_insert_with(|| {
+            fs::read_to_string(&finding.file)
+                .ok()
+                .map(|text| text.lines().map(str::to_string).collect())
+        });
+        let Some(lines) = lines else {
+            continue;
+        };
+        let function = files
+            .iter()
+            .filter(|f| f.file == finding.file)
+            .flat_map(|f| &f.functions)
+            .find(|f| {
+                f.function == finding.function && (f.line..=f.end_line).contains(&finding.line)
+            });
+        let function_end = function.map_or(finding.line, |f| f.end_line);
+        let end = if finding.rule == "complex-conditional" {
+            lines
+                .iter()
+                .skip(finding.line.saturating_sub(1))
+                .position(|l| l.contains('{') || l.trim_end().ends_with([';', ',']))
+                .map_or(function_end, |n| (finding.line + n).min(function_end))
+        } else if finding.rule == "long-call-chain" {
+            function.map_or(function_end, |f| f.call_chain_lines.1)
+        } else {
+            function_end
+        };
+        finding.snippet = excerpt(lines, finding.line, end, max_lines);
+    }
+}
+
+fn excerpt(lines: &[String], start: usize, end: usize, max_lines: usize) -> Option<String> {
+    let first = start.max(1) - 1;
+    let last = end.min(first + max_lines).min(lines.len());
+    let picked = lines.get(first..last).filter(|p| !p.is_empty())?;
+    let indent = picked
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut text: Vec<&str> = picked
+        .iter()
+        .map(|l| l.get(indent..).unwrap_or(l.trim_start()))
+        .collect();
+    if end > last {
+        text.push("...");
+    }
+    Some(text.join("\n"))
+}
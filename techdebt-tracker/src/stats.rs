@@ -0,0 +1,258 @@
+// --- `stats` subcommand: tokei-style line breakdown per language ---
+use crate::term::Align::{Left, Right};
+use crate::term::Table;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+struct Language {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+}
+
+const C_BLOCK: Option<(&str, &str)> = Some(("/*", "*/"));
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "Rust",
+        extensions: &["rs"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "C",
+        extensions: &["c", "h"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "C++",
+        extensions: &["cc", "cpp", "cxx", "hpp", "hh"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Go",
+        extensions: &["go"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "Java",
+        extensions: &["java"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "JavaScript",
+        extensions: &["js", "mjs", "cjs", "jsx"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "TypeScript",
+        extensions: &["ts", "tsx"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "CSS",
+        extensions: &["css"],
+        line_comments: &[],
+        block_comment: C_BLOCK,
+    },
+    Language {
+        name: "HTML",
+        extensions: &["html", "htm"],
+        line_comments: &[],
+        block_comment: Some(("<!--", "-->")),
+    },
+    Language {
+        name: "Python",
+        extensions: &["py"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "Shell",
+        extensions: &["sh", "bash", "zsh"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "TOML",
+        extensions: &["toml"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "YAML",
+        extensions: &["yml", "yaml"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    Language {
+        name: "JSON",
+        extensions: &["json"],
+        line_comments: &[],
+        block_comment: None,
+    },
+    Language {
+        name: "Markdown",
+        extensions: &["md"],
+        line_comments: &[],
+        block_comment: None,
+    },
+];
+
+#[derive(Serialize, Default, Clone)]
+pub struct LineStats {
+    pub files: usize,
+    pub lines: usize,
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+}
+
+impl LineStats {
+    fn add(&mut self, other: &LineStats) {
+        self.files = self.files.saturating_add(other.files);
+        self.lines = self.lines.saturating_add(other.lines);
+        self.code = self.code.saturating_add(other.code);
+        self.comments = self.comments.saturating_add(other.comments);
+        self.blanks = self.blanks.saturating_add(other.blanks);
+    }
+}
+
+#[derive(Serialize)]
+pub struct StatsReport {
+    pub languages: BTreeMap<String, LineStats>,
+    pub total: LineStats,
+}
+
+fn language_for(path: &Path) -> Option<&'static Language> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|l| l.extensions.contains(&ext.as_str()))
+}
+
+// Line classification is deliberately simple: comment markers inside string
+// literals are not recognised, and a line mixing code and a comment is code.
+fn count_lines(content: &str, lang: &Language) -> LineStats {
+    let mut stats = LineStats {
+        files: 1,
+        ..Default::default()
+    };
+    let mut in_block = false;
+
+    for line in content.lines() {
+        stats.lines += 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            stats.blanks += 1;
+            continue;
+        }
+
+        if let Some((open, close)) = lang.block_comment {
+            if in_block || trimmed.starts_with(open) {
+                let body = if in_block {
+                    trimmed
+                } else {
+                    &trimmed[open.len()..]
+                };
+                match body.find(close) {
+                    Some(end) => {
+                        in_block = false;
+                        let rest = body[end + close.len()..].trim();
+                        if rest.is_empty() {
+                            stats.comments += 1;
+                        } else {
+                            stats.code += 1;
+                        }
+                    }
+                    None => {
+                        in_block = true;
+                        stats.comments += 1;
+                    }
+                }
+                continue;
+            }
+        }
+
+        if lang.line_comments.iter().any(|p| trimmed.starts_with(p)) {
+            stats.comments += 1;
+        } else {
+            stats.code += 1;
+        }
+    }
+
+    stats
+}
+
+fn is_skipped_dir(entry: &walkdir::DirEntry) -> bool {
+    entry.depth() > 0
+        && entry.file_type().is_dir()
+        && entry
+            .file_name()
+            .to_str()
+            .map(|n| n.starts_with('.') || n == "target")
+            .unwrap_or(false)
+}
+
+pub fn collect_stats(dir: &str) -> StatsReport {
+    let mut languages: BTreeMap<String, LineStats> = BTreeMap::new();
+    let mut total = LineStats::default();
+
+    let walker = WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| !is_skipped_dir(e));
+    for entry in walker.filter_map(Result::ok) {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Some(lang) = language_for(path) else {
+            continue;
+        };
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+
+        let stats = count_lines(&content, lang);
+        languages
+            .entry(lang.name.to_string())
+            .or_default()
+            .add(&stats);
+        total.add(&stats);
+    }
+
+    StatsReport { languages, total }
+}
+
+pub fn print_stats(report: &StatsReport) {
+    let row = |name: &str, s: &LineStats| {
+        let counts = [s.files, s.lines, s.code, s.comments, s.blanks];
+        std::iter::once(name.to_string())
+            .chain(counts.iter().map(usize::to_string))
+            .collect()
+    };
+
+    let mut table = Table::new(&[Left, Right, Right, Right, Right, Right]);
+    table.row(
+        ["Language", "Files", "Lines", "Code", "Comments", "Blanks"]
+            .map(str::to_string)
+            .to_vec(),
+    );
+    table.rule();
+    for (name, s) in &report.languages {
+        table.row(row(name, s));
+    }
+    table.rule();
+    table.row(row("Total", &report.total));
+    table.print();
+}
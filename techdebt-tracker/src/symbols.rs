@@ -0,0 +1,142 @@
+// --- Project-wide symbol references and usage-weighted risk ranking ---
+// Names are matched by their last path segment, so two items sharing a name
+// share a count; good enough to separate widely used code from leaf code.
+// Keys are `name` for paths, `.name` for method calls and `name!` for macros.
+use crate::FileMetrics;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use syn::visit::{self, Visit};
+
+#[derive(Default)]
+pub struct ReferenceVisitor {
+    pub references: BTreeMap<String, usize>,
+}
+
+impl<'ast> Visit<'ast> for ReferenceVisitor {
+    fn visit_path(&mut self, node: &'ast syn::Path) {
+        if let Some(last) = node.segments.last() {
+            *self.references.entry(last.ident.to_string()).or_default() += 1;
+        }
+        visit::visit_path(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        *self
+            .references
+            .entry(format!(".{}", node.method))
+            .or_default() += 1;
+        visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        if let Some(last) = node.path.segments.last() {
+            *self
+                .references
+                .entry(format!("{}!", last.ident))
+                .or_default() += 1;
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct SymbolReferences {
+    pub name: String, // module-qualified
+    pub kind: String,
+    pub file: String,
+    pub references: usize,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RiskEntry {
+    pub file: String,
+    pub function: String,
+    pub complexity: usize,
+    pub references: usize,
+    pub lints: usize,
+    pub risk: usize, // complexity × references × (1 + lints)
+}
+
+pub struct SymbolIndex {
+    pub public_symbols: Vec<SymbolReferences>,
+    pub risk_ranking: Vec<RiskEntry>,
+    pub file_risk: HashMap<String, usize>, // summed over every function
+}
+
+const RANKED_TYPES: [&str; 6] = ["fn", "struct", "enum", "trait", "type", "macro"];
+
+pub fn index(files: &[FileMetrics], limit: usize) -> SymbolIndex {
+    let mut totals: HashMap<&str, usize> = HashMap::new();
+    for file in files {
+        for (name, n) in &file.references {
+            *totals.entry(name).or_default() += n;
+        }
+    }
+    let get = |key: &str| totals.get(key).copied().unwrap_or(0);
+    // Methods (`Type::method`) are reached through `.method()` or a path.
+    let count = |name: &str, kind: &str| {
+        let last = name.rsplit("::").next().unwrap_or(name);
+        match kind {
+            "macro" => get(&format!("{}!", last)),
+            "fn" if name.contains("::") => get(last) + get(&format!(".{}", last)),
+            _ => get(last),
+        }
+    };
+
+    let mut public_symbols: Vec<SymbolReferences> = files
+        .iter()
+        .flat_map(|file| {
+            file.api_items
+                .iter()
+                .filter(|i| i.visibility == "pub" && RANKED_TYPES.contains(&i.kind.as_str()))
+                .map(|i| SymbolReferences {
+                    name: if file.module.is_empty() {
+                        i.name.clone()
+                    } else {
+                        format!("{}::{}", file.module, i.name)
+                    },
+                    kind: i.kind.clone(),
+                    file: file.file.clone(),
+                    references: count(&i.name, &i.kind),
+                })
+        })
+        .collect();
+    public_symbols.sort_by(|a, b| {
+        b.references
+            .cmp(&a.references)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let mut risk_ranking: Vec<RiskEntry> = files
+        .iter()
+        .flat_map(|file| &file.functions)
+        .map(|f| {
+            let references = count(&f.function, "fn");
+            RiskEntry {
+                file: f.file.clone(),
+                function: f.function.clone(),
+                complexity: f.complexity,
+                references,
+                lints: f.lints,
+                risk: f.complexity * references * (1 + f.lints),
+            }
+        })
+        .filter(|r| r.references > 0)
+        .collect();
+    let mut file_risk: HashMap<String, usize> = HashMap::new();
+    for r in &risk_ranking {
+        let total = file_risk.entry(r.file.clone()).or_default();
+        *total = total.saturating_add(r.risk);
+    }
+    risk_ranking.sort_by(|a, b| {
+        b.risk
+            .cmp(&a.risk)
+            .then_with(|| (&a.file, &a.function).cmp(&(&b.file, &b.function)))
+    });
+    risk_ranking.truncate(limit);
+
+    SymbolIndex {
+        public_symbols,
+        risk_ranking,
+        file_risk,
+    }
+}
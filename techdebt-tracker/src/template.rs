@@ -0,0 +1,17 @@
+// --- User-provided Handlebars templates over the full report data ---
+use crate::Report;
+use handlebars::Handlebars;
+use std::fs;
+
+// Templates see exactly the JSON report (`metrics`, `files`, `findings`, ...).
+// Output is not HTML, so nothing is escaped.
+pub fn render(report: &Report, template_path: &str) -> Result<String, String> {
+    let source = fs::read_to_string(template_path)
+        .map_err(|e| format!("cannot read template {}: {}", template_path, e))?;
+    let mut hb = Handlebars::new();
+    hb.register_escape_fn(handlebars::no_escape);
+    hb.register_template_string("report", source)
+        .map_err(|e| format!("invalid template {}: {}", template_path, e))?;
+    hb.render("report", report)
+        .map_err(|e| format!("cannot render template {}: {}", template_path, e))
+}
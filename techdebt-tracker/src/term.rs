@@ -0,0 +1,250 @@
+// --- Terminal rendering for the text report: color, alignment, width ---
+// `--color auto` colors only when stdout is a terminal, NO_COLOR is unset
+// and TERM is not `dumb`. The width comes from $COLUMNS, else 120 columns,
+// so CI logs without a terminal still get stable, aligned tables.
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+const DEFAULT_WIDTH: usize = 120;
+const MIN_SHRUNK_WIDTH: usize = 20; // a truncated path column never gets narrower
+const GAP: &str = "  ";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn from_name(name: &str) -> Option<ColorChoice> {
+        match name {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+}
+
+static COLOR: OnceLock<bool> = OnceLock::new();
+
+pub fn init(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::io::stdout().is_terminal()
+                && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                && std::env::var("TERM").map_or(true, |t| t != "dumb")
+        }
+    };
+    let _ = COLOR.set(enabled);
+}
+
+fn color_enabled() -> bool {
+    *COLOR.get().unwrap_or(&false)
+}
+
+pub fn width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.trim().parse().ok())
+        .filter(|&w: &usize| w > 0)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tone {
+    Plain,
+    Good,
+    Warn,
+    Bad,
+}
+
+fn paint(text: &str, code: &str) -> String {
+    if color_enabled() && !text.is_empty() {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn bold(text: &str) -> String {
+    paint(text, "1")
+}
+
+pub fn dim(text: &str) -> String {
+    paint(text, "2")
+}
+
+pub fn tone(text: &str, tone: Tone) -> String {
+    match tone {
+        Tone::Plain => text.to_string(),
+        Tone::Good => paint(text, "32"),
+        Tone::Warn => paint(text, "33"),
+        Tone::Bad => paint(text, "31"),
+    }
+}
+
+// Red over the limit, yellow within its top fifth, green below; plain
+// when the rule is off.
+pub fn against(value: usize, limit: Option<usize>) -> Tone {
+    match limit {
+        None => Tone::Plain,
+        Some(limit) if value > limit => Tone::Bad,
+        Some(limit) if value * 5 > limit * 4 => Tone::Warn,
+        Some(_) => Tone::Good,
+    }
+}
+
+pub fn grade_tone(grade: &str) -> Tone {
+    match grade {
+        "A" | "B" => Tone::Good,
+        "C" => Tone::Warn,
+        "D" | "F" => Tone::Bad,
+        _ => Tone::Plain,
+    }
+}
+
+pub fn heading(icon: &str, title: &str) {
+    println!("\n{} {}", icon, bold(title));
+}
+
+// Columns on screen, skipping ANSI color sequences.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => escape = true,
+            'm' if escape => escape = false,
+            _ if escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+// Keeps the end of a path, which names the file, cutting at a separator
+// where one falls inside the kept part: `…/src/api/handler.rs`.
+pub fn truncate_path(path: &str, max: usize) -> String {
+    let width = visible_width(path);
+    if width <= max || max < 2 {
+        return path.to_string();
+    }
+    let tail: String = path.chars().skip(width - (max - 1)).collect();
+    match tail.find(['/', '\\']) {
+        Some(i) if i + 1 < tail.len() => format!("…{}", &tail[i..]),
+        _ => format!("…{}", tail),
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+// Rows of cells printed in aligned columns. One column may be marked as
+// the path column: it is truncated from the left when the table would
+// overflow the terminal.
+pub struct Table {
+    aligns: Vec<Align>,
+    rows: Vec<Vec<String>>,
+    rules: Vec<usize>, // row indexes a dashed line is printed above
+    shrink: Option<usize>,
+}
+
+impl Table {
+    pub fn new(aligns: &[Align]) -> Table {
+        Table {
+            aligns: aligns.to_vec(),
+            rows: Vec::new(),
+            rules: Vec::new(),
+            shrink: None,
+        }
+    }
+
+    pub fn shrink(mut self, column: usize) -> Table {
+        self.shrink = Some(column);
+        self
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    // A dashed line across the full table width below the last row.
+    pub fn rule(&mut self) {
+        self.rules.push(self.rows.len());
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        let mut widths = vec![0; self.aligns.len()];
+        for row in &self.rows {
+            for (w, cell) in widths.iter_mut().zip(row) {
+                *w = (*w).max(visible_width(cell));
+            }
+        }
+        widths
+    }
+
+    pub fn print(mut self) {
+        let mut widths = self.widths();
+        if let Some(col) = self.shrink.filter(|&c| c < widths.len()) {
+            let total: usize = widths.iter().sum::<usize>() + GAP.len() * (widths.len() - 1);
+            let excess = total.saturating_sub(width());
+            let target = widths[col].saturating_sub(excess).max(MIN_SHRUNK_WIDTH);
+            if target < widths[col] {
+                for row in &mut self.rows {
+                    // Colored cells are left alone; paths are printed plain.
+                    if let Some(cell) = row.get_mut(col).filter(|c| !c.contains('\x1b')) {
+                        *cell = truncate_path(cell, target);
+                    }
+                }
+                widths = self.widths();
+            }
+        }
+        let total = widths.iter().sum::<usize>() + GAP.len() * widths.len().saturating_sub(1);
+        for (index, row) in self.rows.iter().enumerate() {
+            if self.rules.contains(&index) {
+                println!("{}", "-".repeat(total));
+            }
+            let mut line = String::new();
+            for (i, cell) in row.iter().enumerate() {
+                if i > 0 {
+                    line.push_str(GAP);
+                }
+                let pad = " ".repeat(widths[i].saturating_sub(visible_width(cell)));
+                match self.aligns.get(i).copied().unwrap_or(Align::Left) {
+                    Align::Left => {
+                        line.push_str(cell);
+                        line.push_str(&pad);
+                    }
+                    Align::Right => {
+                        line.push_str(&pad);
+                        line.push_str(cell);
+                    }
+                }
+            }
+            println!("{}", line.trim_end());
+        }
+        if self.rules.contains(&self.rows.len()) {
+            println!("{}", "-".repeat(total));
+        }
+    }
+}
+
+// `Label: value` lines (as the catalogs phrase them) with the values lined
+// up; each value is colored with its tone.
+pub fn print_pairs(lines: &[(String, Tone)]) {
+    let mut table = Table::new(&[Align::Left, Align::Left]);
+    for (line, t) in lines {
+        match line.split_once(": ") {
+            Some((label, value)) => table.row(vec![format!("{}:", label), tone(value, *t)]),
+            None => table.row(vec![tone(line, *t)]),
+        }
+    }
+    table.print();
+}
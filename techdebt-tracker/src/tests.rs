@@ -0,0 +1,123 @@
+// --- Test quality: assertions per test, assertion-free tests, test length ---
+// Tests are any `#[test]`-like function (`#[tokio::test]`, `#[rstest]`, ...)
+// at any depth, typically inside `#[cfg(test)] mod tests`. `assert*!`
+// macros count as assertions, and so does `#[should_panic]`.
+use crate::FileMetrics;
+use serde::{Deserialize, Serialize};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+const TEST_ATTRIBUTES: [&str; 4] = ["test", "rstest", "quickcheck", "proptest"];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TestMetric {
+    pub function: String,
+    pub line: usize,
+    pub loc: usize,
+    pub assertions: usize,
+    pub should_panic: bool,
+}
+
+fn is_test(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|a| {
+        a.path()
+            .segments
+            .last()
+            .is_some_and(|s| TEST_ATTRIBUTES.contains(&s.ident.to_string().as_str()))
+    })
+}
+
+#[derive(Default)]
+struct AssertionVisitor {
+    assertions: usize,
+}
+
+impl<'ast> Visit<'ast> for AssertionVisitor {
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        let name = node.path.segments.last().map(|s| s.ident.to_string());
+        if name.is_some_and(|n| n.contains("assert")) {
+            self.assertions += 1;
+        }
+        syn::visit::visit_macro(self, node);
+    }
+}
+
+#[derive(Default)]
+struct TestVisitor {
+    tests: Vec<TestMetric>,
+}
+
+impl<'ast> Visit<'ast> for TestVisitor {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        if is_test(&node.attrs) {
+            let mut asserts = AssertionVisitor::default();
+            asserts.visit_block(&node.block);
+            let should_panic = node.attrs.iter().any(|a| a.path().is_ident("should_panic"));
+            // From the signature, so attribute lines don't count.
+            let line = node.sig.span().start().line;
+            self.tests.push(TestMetric {
+                function: node.sig.ident.to_string(),
+                line,
+                loc: node.span().end().line + 1 - line,
+                assertions: asserts.assertions + usize::from(should_panic),
+                should_panic,
+            });
+        }
+        syn::visit::visit_item_fn(self, node);
+    }
+}
+
+pub fn scan(syntax: &syn::File) -> Vec<TestMetric> {
+    let mut visitor = TestVisitor::default();
+    visitor.visit_file(syntax);
+    visitor.tests
+}
+
+#[derive(Serialize)]
+pub struct AssertionFreeTest {
+    pub file: String,
+    pub function: String,
+    pub line: usize,
+    pub loc: usize,
+}
+
+#[derive(Serialize, Default)]
+pub struct TestQuality {
+    pub tests: usize,
+    pub assertions: usize,
+    pub assertions_per_test: f64,
+    pub mean_loc: f64,
+    pub without_assertions: usize,
+    pub assertion_free: Vec<AssertionFreeTest>, // longest first
+}
+
+pub fn summarize(files: &[FileMetrics]) -> TestQuality {
+    let mut quality = TestQuality::default();
+    let mut loc = 0;
+    for file in files {
+        for t in &file.tests {
+            quality.tests += 1;
+            quality.assertions += t.assertions;
+            loc += t.loc;
+            if t.assertions == 0 {
+                quality.assertion_free.push(AssertionFreeTest {
+                    file: file.file.clone(),
+                    function: t.function.clone(),
+                    line: t.line,
+                    loc: t.loc,
+                });
+            }
+        }
+    }
+    if quality.tests > 0 {
+        quality.assertions_per_test = quality.assertions as f64 / quality.tests as f64;
+        quality.mean_loc = loc as f64 / quality.tests as f64;
+    }
+    quality.without_assertions = quality.assertion_free.len();
+    quality.assertion_free.sort_by(|a, b| {
+        b.loc
+            .cmp(&a.loc)
+            .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+    });
+    quality
+}
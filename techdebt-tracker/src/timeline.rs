@@ -0,0 +1,138 @@
+// --- `history --function`: one function's complexity across git history ---
+use crate::config::ComplexityModel;
+use crate::term::Align::{Left, Right};
+use crate::term::Table;
+use crate::{analyze_source, git};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+pub struct TimelinePoint {
+    pub commit: String,
+    pub date: String,
+    pub subject: String,
+    pub path: String,  // repo-relative, follows renames
+    pub present: bool, // false once the function is gone or renamed
+    pub complexity: usize,
+    pub cognitive_complexity: usize,
+    pub loc: usize,
+}
+
+#[derive(Serialize)]
+pub struct FunctionTimeline {
+    pub file: String,
+    pub function: String,
+    pub points: Vec<TimelinePoint>, // oldest first
+}
+
+// Splits `src/lib.rs::Type::method` at the first `::` after the file name.
+pub fn parse_spec(spec: &str) -> Result<(&str, &str), String> {
+    spec.find(".rs::")
+        .map(|i| (&spec[..i + 3], &spec[i + 5..]))
+        .filter(|(_, name)| !name.is_empty())
+        .ok_or_else(|| format!("expected PATH.rs::FUNCTION, got '{}'", spec))
+}
+
+// Re-analyzes the file at every commit that touched it and picks out the
+// function. Commits where it does not exist are kept so gaps stay visible.
+pub fn walk(spec: &str, model: ComplexityModel) -> Result<FunctionTimeline, String> {
+    let (file, function) = parse_spec(spec)?;
+    let path = Path::new(file)
+        .canonicalize()
+        .map_err(|e| format!("cannot resolve {}: {}", file, e))?;
+    let root = PathBuf::from(
+        git::run_git(&git::work_dir(file), &["rev-parse", "--show-toplevel"])?.trim(),
+    )
+    .canonicalize()
+    .map_err(|e| e.to_string())?;
+    let relative = path
+        .strip_prefix(&root)
+        .map_err(|_| format!("{} is outside the git repository", file))?
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    let mut points = Vec::new();
+    for commit in git::file_log(&root, &relative)? {
+        let content = git::file_at(&root, &commit.hash, &commit.path)?;
+        let (_, detail) = analyze_source(&content, &commit.path, model);
+        let found = detail.functions.iter().find(|f| f.function == function);
+        points.push(TimelinePoint {
+            commit: commit.hash,
+            date: commit.date,
+            subject: commit.subject,
+            path: commit.path,
+            present: found.is_some(),
+            complexity: found.map_or(0, |f| f.complexity),
+            cognitive_complexity: found.map_or(0, |f| f.cognitive_complexity),
+            loc: found.map_or(0, |f| f.loc),
+        });
+    }
+    if !points.iter().any(|p| p.present) {
+        return Err(format!(
+            "function '{}' not found in the history of {}",
+            function, file
+        ));
+    }
+    points.reverse();
+
+    Ok(FunctionTimeline {
+        file: relative,
+        function: function.to_string(),
+        points,
+    })
+}
+
+pub fn print_timeline(t: &FunctionTimeline) {
+    println!(
+        "📜 {}::{} across {} commits:",
+        t.file,
+        t.function,
+        t.points.len()
+    );
+    let mut table = Table::new(&[Left, Left, Right, Right, Right, Left]);
+    table.row(
+        [
+            "Commit",
+            "Date",
+            "Complexity",
+            "Cognitive",
+            "LOC",
+            "Subject",
+        ]
+        .map(str::to_string)
+        .to_vec(),
+    );
+    let mut previous: Option<usize> = None;
+    for p in &t.points {
+        let short = p.commit[..p.commit.len().min(10)].to_string();
+        if !p.present {
+            let dash = "-".to_string();
+            table.row(vec![
+                short,
+                p.date.clone(),
+                dash.clone(),
+                dash.clone(),
+                dash,
+                p.subject.clone(),
+            ]);
+            previous = None;
+            continue;
+        }
+        let delta = match previous {
+            Some(before) if before != p.complexity => {
+                format!(" ({:+})", p.complexity as i64 - before as i64)
+            }
+            _ => String::new(),
+        };
+        table.row(vec![
+            short,
+            p.date.clone(),
+            format!("{}{}", p.complexity, delta),
+            p.cognitive_complexity.to_string(),
+            p.loc.to_string(),
+            p.subject.clone(),
+        ]);
+        previous = Some(p.complexity);
+    }
+    table.print();
+}
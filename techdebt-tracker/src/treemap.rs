@@ -0,0 +1,94 @@
+// --- Directory rollup: a nested tree of totals for treemap UIs ---
+// Built from canonical report paths, so every node's `path` matches the
+// `file` of the rows beneath it. Files are leaves; directories sum them.
+use crate::FileMetrics;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Default)]
+pub struct TreeNode {
+    pub name: String,
+    pub path: String,
+    pub kind: &'static str, // "dir" or "file"
+    pub files: usize,
+    pub loc: usize,
+    pub complexity: usize,
+    pub risk: usize,
+    pub worst_child: Option<String>, // path of the riskiest child
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreeNode>,
+}
+
+#[derive(Default)]
+struct Dir<'a> {
+    dirs: BTreeMap<&'a str, Dir<'a>>,
+    files: Vec<&'a FileMetrics>,
+}
+
+fn join(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+fn leaf(file: &FileMetrics, name: &str) -> TreeNode {
+    TreeNode {
+        name: name.to_string(),
+        path: file.file.clone(),
+        kind: "file",
+        files: 1,
+        loc: file.loc,
+        complexity: file.total_complexity,
+        risk: file.risk,
+        ..TreeNode::default()
+    }
+}
+
+fn node(dir: Dir, name: &str, path: String) -> TreeNode {
+    let mut children: Vec<TreeNode> = dir
+        .dirs
+        .into_iter()
+        .map(|(child, d)| node(d, child, join(&path, child)))
+        .collect();
+    children.extend(dir.files.into_iter().map(|f| {
+        let name = f.file.rsplit('/').next().unwrap_or(&f.file);
+        leaf(f, name)
+    }));
+    let add = |f: fn(&TreeNode) -> usize| children.iter().map(f).fold(0, usize::saturating_add);
+    let worst_child = children
+        .iter()
+        .max_by(|a, b| {
+            (a.risk, a.complexity)
+                .cmp(&(b.risk, b.complexity))
+                .then_with(|| b.path.cmp(&a.path))
+        })
+        .map(|c| c.path.clone());
+    TreeNode {
+        name: name.to_string(),
+        files: add(|c| c.files),
+        loc: add(|c| c.loc),
+        complexity: add(|c| c.complexity),
+        risk: add(|c| c.risk),
+        path,
+        kind: "dir",
+        worst_child,
+        children,
+    }
+}
+
+// The root is the report's path root, named ".".
+pub fn build(files: &[FileMetrics]) -> TreeNode {
+    let mut root = Dir::default();
+    for file in files {
+        let mut parts: Vec<&str> = file.file.split('/').filter(|p| !p.is_empty()).collect();
+        parts.pop();
+        let mut dir = &mut root;
+        for part in parts {
+            dir = dir.dirs.entry(part).or_default();
+        }
+        dir.files.push(file);
+    }
+    node(root, ".", String::new())
+}
@@ -0,0 +1,62 @@
+// --- Debt velocity: how fast debt grows per week over a rolling window ---
+// Absolute gates pass while debt creeps up; comparing the current run with
+// the oldest history snapshot in the window catches the trend instead.
+use crate::history::Snapshot;
+use crate::logging;
+use serde::Serialize;
+
+// Remediation estimate, fixed so snapshots stay comparable across runs:
+// each complexity point over the level below costs a refactoring slice,
+// each finding a fix.
+const COMPLEXITY_LEVEL: usize = 10;
+const MINUTES_PER_COMPLEXITY_POINT: usize = 10;
+const MINUTES_PER_FINDING: usize = 20;
+
+const SECONDS_PER_WEEK: f64 = 7.0 * 86_400.0;
+
+#[derive(Serialize)]
+pub struct Velocity {
+    pub window_days: u64,
+    pub since: String, // timestamp of the baseline snapshot
+    pub weeks: f64,
+    pub complexity_per_week: f64,
+    pub violations_per_week: f64,
+    pub debt_minutes: usize,
+    pub debt_minutes_per_week: f64,
+}
+
+pub fn debt_minutes(snapshot: &Snapshot) -> usize {
+    let over: usize = snapshot
+        .function_metrics
+        .iter()
+        .map(|f| f.complexity.saturating_sub(COMPLEXITY_LEVEL))
+        .sum();
+    over.saturating_mul(MINUTES_PER_COMPLEXITY_POINT)
+        .saturating_add(snapshot.findings.saturating_mul(MINUTES_PER_FINDING))
+}
+
+// Rates from the oldest snapshot inside the window to `current`. Under a
+// day apart counts as a day, so back-to-back runs can't extrapolate noise
+// into a huge weekly rate. None without a snapshot in the window.
+pub fn compute(snapshots: &[Snapshot], current: &Snapshot, window_days: u64) -> Option<Velocity> {
+    let now = logging::parse_rfc3339(&current.timestamp)?;
+    let start = now.saturating_sub(window_days * 86_400);
+    let (base, at) = snapshots
+        .iter()
+        .filter_map(|s| Some((s, logging::parse_rfc3339(&s.timestamp)?)))
+        .filter(|&(_, t)| t >= start && t <= now)
+        .min_by_key(|&(_, t)| t)?;
+
+    let weeks = ((now - at) as f64).max(86_400.0) / SECONDS_PER_WEEK;
+    let per_week = |before: usize, after: usize| (after as f64 - before as f64) / weeks;
+    let debt = debt_minutes(current);
+    Some(Velocity {
+        window_days,
+        since: base.timestamp.clone(),
+        weeks,
+        complexity_per_week: per_week(base.cyclomatic_complexity, current.cyclomatic_complexity),
+        violations_per_week: per_week(base.findings, current.findings),
+        debt_minutes: debt,
+        debt_minutes_per_week: per_week(debt_minutes(base), debt),
+    })
+}
@@ -0,0 +1,100 @@
+// --- Vendored / third-party code: recognized, reported apart, not gated ---
+use crate::{paths, FileMetrics};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Directory names that hold code copied in from elsewhere.
+const VENDOR_DIRS: [&str; 3] = ["vendor", "third_party", "third-party"];
+
+#[derive(Serialize, Default)]
+pub struct VendoredSummary {
+    pub gated: bool, // --include-vendored
+    pub files: Vec<String>,
+    pub loc: usize,
+    pub functions: usize,
+    pub total_complexity: usize,
+    pub max_complexity: usize,
+}
+
+// `path = ...` entries of every `[patch.<source>]` table in the root
+// manifest, resolved against the manifest's directory.
+fn patch_dirs(root: &Path) -> Vec<PathBuf> {
+    let Ok(text) = fs::read_to_string(root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Ok(manifest) = toml::from_str::<toml::Value>(&text) else {
+        return Vec::new();
+    };
+    let Some(patch) = manifest.get("patch").and_then(toml::Value::as_table) else {
+        return Vec::new();
+    };
+    patch
+        .values()
+        .filter_map(toml::Value::as_table)
+        .flat_map(|deps| deps.values())
+        .filter_map(|dep| dep.get("path")?.as_str())
+        .filter_map(|p| root.join(p).canonicalize().ok())
+        .collect()
+}
+
+fn in_vendor_dir(rel: &Path) -> bool {
+    rel.parent().is_some_and(|dir| {
+        dir.components()
+            .any(|c| VENDOR_DIRS.iter().any(|v| c.as_os_str() == *v))
+    })
+}
+
+// Recognizes files under a vendor directory of one of `roots`, or inside a
+// `[patch]` path of a root manifest.
+pub struct VendorDirs<'a> {
+    roots: &'a [String],
+    patched: Vec<PathBuf>,
+}
+
+impl<'a> VendorDirs<'a> {
+    pub fn new(roots: &'a [String]) -> Self {
+        let patched = roots
+            .iter()
+            .flat_map(|r| patch_dirs(Path::new(r)))
+            .collect();
+        VendorDirs { roots, patched }
+    }
+
+    pub fn contains(&self, file: &str) -> bool {
+        let path = Path::new(file);
+        let rel = self
+            .roots
+            .iter()
+            .find_map(|r| paths::strip_prefix(path, Path::new(r)))
+            .unwrap_or_else(|| path.to_path_buf());
+        in_vendor_dir(&rel)
+            || (!self.patched.is_empty()
+                && path
+                    .canonicalize()
+                    .is_ok_and(|abs| self.patched.iter().any(|p| abs.starts_with(p))))
+    }
+}
+
+pub fn mark(files: &mut [FileMetrics], roots: &[String]) {
+    let dirs = VendorDirs::new(roots);
+    for file in files {
+        file.vendored = dirs.contains(&file.file);
+    }
+}
+
+pub fn summarize(files: &[FileMetrics], gated: bool) -> VendoredSummary {
+    let mut summary = VendoredSummary {
+        gated,
+        ..VendoredSummary::default()
+    };
+    for file in files.iter().filter(|f| f.vendored) {
+        summary.files.push(file.file.clone());
+        summary.loc += file.loc;
+        summary.functions += file.functions.len();
+        summary.total_complexity += file.total_complexity;
+        let worst = file.functions.iter().map(|f| f.complexity).max();
+        summary.max_complexity = summary.max_complexity.max(worst.unwrap_or(0));
+    }
+    summary
+}